@@ -24,6 +24,7 @@ use windows::{
     System::Launcher,
 };
 
+use crate::error::AppError;
 use chrono::{Datelike, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use tauri::command;
@@ -90,7 +91,7 @@ impl From<&objc2_event_kit::EKEvent> for CalendarMatter {
 }
 
 #[command]
-pub async fn open_calendar_setting() -> Result<(), String> {
+pub async fn open_calendar_setting() -> Result<(), AppError> {
     #[cfg(target_os = "macos")]
     unsafe {
         let nsstr =
@@ -102,7 +103,7 @@ pub async fn open_calendar_setting() -> Result<(), String> {
     }
     #[cfg(target_os = "windows")]
     {
-        async fn open_calendar_settings_windows() -> Result<(), String> {
+        async fn open_calendar_settings_windows() -> Result<(), AppError> {
             Ok(())
         }
 
@@ -132,7 +133,7 @@ pub async fn get_calendar_permission_status() -> i32 {
 }
 
 #[command]
-pub async fn request_calendar_access() -> Result<(), String> {
+pub async fn request_calendar_access() -> Result<(), AppError> {
     #[cfg(target_os = "macos")]
     unsafe {
         let queue = Queue::main();
@@ -155,7 +156,7 @@ pub async fn request_calendar_access() -> Result<(), String> {
 }
 
 #[command]
-pub async fn get_calendar_events() -> Result<Vec<CalendarMatter>, String> {
+pub async fn get_calendar_events() -> Result<Vec<CalendarMatter>, AppError> {
     #[cfg(target_os = "macos")]
     unsafe {
         let store = EKEventStore::new();
@@ -210,7 +211,7 @@ pub async fn get_calendar_events() -> Result<Vec<CalendarMatter>, String> {
             Ok(store) => store,
             Err(e) => {
                 log::error!("Error getting store: {}", e);
-                return Err(e.to_string());
+                return Err(AppError::Platform(e.to_string()));
             }
         };
 
@@ -221,7 +222,7 @@ pub async fn get_calendar_events() -> Result<Vec<CalendarMatter>, String> {
             Ok(calendars) => calendars,
             Err(e) => {
                 log::error!("Error getting calendars: {}", e);
-                return Err(e.to_string());
+                return Err(AppError::Platform(e.to_string()));
             }
         };
 
@@ -1,26 +1,181 @@
 // https://github.com/RandomEngy/tauri-sqlite/blob/main/src-tauri/src/database.rs
 
+use crate::recurrence::{MatterOverride, RepeatTime};
 use crate::utils;
-use chrono::{DateTime, TimeZone, Utc};
-use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result, Transaction};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use std::sync::RwLock;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard, OnceLock, TryLockError};
 use tauri::AppHandle;
+use thiserror::Error;
 
-const CURRENT_DB_VERSION: u32 = 1;
+const CURRENT_DB_VERSION: u32 = 5;
 
 const DB_NAME: &str = "fates.db";
 
+// 文件数据库额外打开的只读连接数，读取时互不阻塞，也不会阻塞写入
+const READER_CONNECTIONS: usize = 4;
+
+// 每个连接缓存的预编译语句数，时间线和通知轮询的查询使用 prepare_cached
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+// 其他连接持有锁时最多等待的时间
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// 未分组事项归入的默认分组
+pub const DEFAULT_TIMELINE_GROUP: &str = "default";
+
+// 返回给前端的本地时间格式
+const LOCAL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// 数据库应有的索引：(名称, 表, 列)
+const INDEXES: [(&str, &str, &str); 3] = [
+    ("idx_matter_time", "matter", "start_time, end_time"),
+    ("idx_matter_type_priority", "matter", "type, priority"),
+    ("idx_matter_updated_at", "matter", "updated_at"),
+];
+
+// 记录变更日志的表及其主键
+const CHANGE_TRACKED_TABLES: [(&str, &str); 4] = [
+    ("matter", "id"),
+    ("repeat_task", "id"),
+    ("todo", "id"),
+    ("tags", "name"),
+];
+
+// 调度器上一次检查定时通知的时间，用于避免重复触发
+pub const SCHEDULER_LAST_FIRE_CHECK_KEY: &str = "scheduler_last_fire_check";
+
+// 上次拉取通知的时间，用于应用重新打开时补发错过的通知
+pub const NOTIFICATION_LAST_SEEN_KEY: &str = "notification_last_seen";
+
+// 开启后不允许创建同名（不区分大小写）的周期任务
+pub const UNIQUE_REPEAT_TITLES_KEY: &str = "unique_repeat_titles";
+
+// 替换导入、恢复、批量删除等操作前是否自动备份，默认开启
+pub const AUTO_BACKUP_KEY: &str = "auto_backup_before_destructive";
+
+// 自动备份保存在数据库文件所在目录下的该子目录中
+const AUTO_BACKUP_DIR: &str = "backups";
+
+// 每周记录时长目标（秒）
+pub const WEEKLY_GOAL_KEY: &str = "weekly_goal";
+
+// 最近一次发送“完成每周目标”通知的周（周开始日期），每周只通知一次
+pub const WEEKLY_GOAL_NOTIFIED_KEY: &str = "weekly_goal_notified_week";
+
+// 事项开始前多少分钟发送提醒，0 表示不提醒
+pub const REMINDER_LEAD_MINUTES_KEY: &str = "reminder_lead_minutes";
+
+// 不属于用户设置的运行状态键，快照和恢复设置时跳过
+const KV_STATE_KEYS: [&str; 3] = [
+    SCHEDULER_LAST_FIRE_CHECK_KEY,
+    NOTIFICATION_LAST_SEEN_KEY,
+    WEEKLY_GOAL_NOTIFIED_KEY,
+];
+const KV_STATE_KEY_PREFIX: &str = "repeat_task";
+
+fn is_settings_key(key: &str) -> bool {
+    !KV_STATE_KEYS.contains(&key) && !key.starts_with(KV_STATE_KEY_PREFIX)
+}
+
+// query_by_field 允许查询的字段，字段名会直接拼接到 SQL 中，必须在此列表内
+const MATTER_QUERY_FIELDS: [&str; 11] = [
+    "id",
+    "title",
+    "description",
+    "tags",
+    "priority",
+    "type",
+    "reserved_1",
+    "reserved_2",
+    "reserved_3",
+    "reserved_4",
+    "reserved_5",
+];
+
+// 新建事项默认优先级/类型的 KV 键
+pub const DEFAULT_MATTER_PRIORITY_KEY: &str = "default_matter_priority";
+pub const DEFAULT_MATTER_TYPE_KEY: &str = "default_matter_type";
+
+// 检查时间异常的表，以及该表是否有 updated_at 列
+const TIME_ANOMALY_TABLES: [(&str, bool); 4] = [
+    ("matter", true),
+    ("repeat_task", true),
+    ("todo", true),
+    ("notification_records", false),
+];
+
+// created_at 晚于当前时间超过该秒数时视为时钟异常
+const FUTURE_TIMESTAMP_TOLERANCE_SECONDS: i64 = 24 * 60 * 60;
+
+// 二进制备份的文件头、格式版本以及包含的表
+const BINARY_EXPORT_MAGIC: &[u8; 8] = b"FATESBIN";
+const BINARY_EXPORT_VERSION: u32 = 1;
+const BINARY_EXPORT_TABLES: [&str; 10] = [
+    "matter",
+    "kvstore",
+    "tags",
+    "tag_category",
+    "repeat_task",
+    "repeat_task_exceptions",
+    "todo",
+    "notification_records",
+    "timeline_group",
+    "recurring_notification",
+];
+
+// 由迁移新增的表及新增时的版本，更早版本的数据库中没有这些表
+const MIGRATED_TABLES: [(&str, u32); 1] = [("repeat_task_exceptions", 5)];
+
 fn default_datetime() -> DateTime<Utc> {
     Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap()
 }
 
-fn check_is_default_datetime(datetime: DateTime<Utc>) -> bool {
+fn default_repeat_task_status() -> i32 {
+    1
+}
+
+fn default_recurring_notification_type() -> i32 {
+    NotificationType::Custom as i32
+}
+
+fn default_active() -> bool {
+    true
+}
+
+pub fn check_is_default_datetime(datetime: DateTime<Utc>) -> bool {
     datetime.timestamp() == 0 && datetime.timestamp_subsec_nanos() == 0
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// 拆分以逗号分隔的标签字符串
+pub fn split_tags(tags: &str) -> impl Iterator<Item = &str> {
+    tags.split(',').map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+// 去掉清单行开头的 "-"、"*" 和 "[ ]" 等标记，只剩标记的行返回 None
+fn checklist_item_title(line: &str) -> Option<&str> {
+    let mut title = line.trim();
+    for marker in ["-", "*"] {
+        title = title.strip_prefix(marker).unwrap_or(title).trim_start();
+    }
+    for marker in ["[ ]", "[x]", "[X]"] {
+        title = title.strip_prefix(marker).unwrap_or(title).trim_start();
+    }
+    (!title.is_empty()).then_some(title)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Matter {
     #[serde(default)]
     pub id: String, // UUID
@@ -53,22 +208,203 @@ pub struct Matter {
     pub reserved_5: Option<String>,
 }
 
+// 附带本地时间字段的 Matter，避免前端自行做时区换算
+#[derive(Debug, Serialize)]
+pub struct LocalMatter {
+    #[serde(flatten)]
+    pub matter: Matter,
+    pub start_local: String,
+    pub end_local: String,
+    pub duration: String,
+}
+
+// 日视图中的事项及其所在的列，重叠的事项分配到不同的列
+#[derive(Debug, Serialize)]
+pub struct MatterLane {
+    #[serde(flatten)]
+    pub matter: Matter,
+    pub lane: usize,
+}
+
+// 回收站中的事项及其删除时间
+#[derive(Debug, Serialize)]
+pub struct TrashedMatter {
+    #[serde(flatten)]
+    pub matter: Matter,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayLayout {
+    pub matters: Vec<MatterLane>,
+    pub lane_count: usize,
+}
+
+// 事项相对某一时刻所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatterPhase {
+    Past,
+    Ongoing,
+    Upcoming,
+}
+
+// 事项数据完整性统计
+#[derive(Debug, Serialize)]
+pub struct CompletenessReport {
+    pub total: i64,
+    pub missing_tags: i64,
+    pub missing_description: i64,
+    pub default_timestamps: i64, // 开始、创建或更新时间为默认值（1970-01-01）
+    pub open_ended: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatterWithConflict {
+    #[serde(flatten)]
+    pub matter: Matter,
+    pub has_conflict: bool,
+}
+
+// 与给定时间段重叠的事项，以及重叠的区间和时长
+#[derive(Debug, Serialize)]
+pub struct MatterConflict {
+    #[serde(flatten)]
+    pub matter: Matter,
+    pub overlap_start: DateTime<Utc>,
+    pub overlap_end: DateTime<Utc>,
+    pub overlap_seconds: i64,
+}
+
+// 时间重叠的两个事项，first 开始得更早，以及重叠的区间和时长
+#[derive(Debug, Serialize)]
+pub struct MatterOverlap {
+    pub first: Matter,
+    pub second: Matter,
+    pub overlap_start: DateTime<Utc>,
+    pub overlap_end: DateTime<Utc>,
+    pub overlap_seconds: i64,
+}
+
+// 周视图中事项在某一天内显示的片段，跨午夜的事项在每一天各有一段
+#[derive(Debug, Serialize)]
+pub struct WeekGridSegment {
+    #[serde(flatten)]
+    pub matter: MatterLane,
+    pub start_local: String,
+    pub end_local: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeekGridDay {
+    pub date: NaiveDate,
+    pub segments: Vec<WeekGridSegment>,
+    pub lane_count: usize,
+    pub total_seconds: i64, // 当天内已结束事项的总时长
+}
+
+// 新建事项的草稿，未填写的优先级/类型由 KV 中的默认值补全
+#[derive(Debug, Deserialize)]
+pub struct MatterDraft {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Option<String>,
+    #[serde(default = "default_datetime")]
+    pub start_time: DateTime<Utc>,
+    #[serde(default = "default_datetime")]
+    pub end_time: DateTime<Utc>,
+    #[serde(default)]
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub type_: Option<i32>,
+    #[serde(default)]
+    pub reserved_3: Option<String>, // 时间线分组
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RepeatTask {
+    #[serde(default)]
     pub id: String,
     pub title: String,
+    #[serde(default)]
     pub tags: Option<String>,
     pub repeat_time: String,
+    #[serde(default = "default_repeat_task_status")]
     pub status: i32, // 1=Active, 0=Stopped, -1=Archived
     #[serde(default = "default_datetime")]
     pub created_at: DateTime<Utc>,
     #[serde(default = "default_datetime")]
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
     pub priority: i32,
     #[serde(default)]
     pub description: Option<String>,
 }
 
+// 周期任务某一天（展开时所用时区的本地日期）的例外：skip 跳过这一次，
+// override 用 override_json 中的字段替换这一次的事项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatTaskException {
+    #[serde(default)]
+    pub task_id: String,
+    pub occurrence_date: NaiveDate,
+    pub action: String, // "skip", "override"
+    #[serde(default)]
+    pub override_json: Option<String>,
+    #[serde(default = "default_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatExceptionAction {
+    Skip,
+    Override,
+}
+
+impl RepeatExceptionAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RepeatExceptionAction::Skip => "skip",
+            RepeatExceptionAction::Override => "override",
+        }
+    }
+}
+
+impl TryFrom<&str> for RepeatExceptionAction {
+    type Error = String;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "skip" => Ok(RepeatExceptionAction::Skip),
+            "override" => Ok(RepeatExceptionAction::Override),
+            _ => Err(format!("Invalid repeat exception action: {}", value)),
+        }
+    }
+}
+
+// 不区分大小写重名的周期任务
+#[derive(Debug, Serialize)]
+pub struct DuplicateRepeatTitle {
+    pub title: String,
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportItemError {
+    pub index: usize,
+    pub message: String,
+}
+
+// 批量导入的结果，imported 为成功（或 dry_run 时可以成功）导入的 id
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    pub errors: Vec<ImportItemError>,
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KVStore {
     pub key: String,
@@ -77,6 +413,8 @@ pub struct KVStore {
     pub created_at: DateTime<Utc>,
     #[serde(default = "default_datetime")]
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>, // 为空表示永不过期
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -86,6 +424,77 @@ pub struct Tag {
     pub created_at: DateTime<Utc>,
     #[serde(default = "default_datetime")]
     pub last_used_at: DateTime<Utc>,
+    #[serde(default)]
+    pub color: Option<String>, // 手动指定的颜色，为空时按名称生成
+}
+
+// 引用了已删除标签的事项或周期任务
+#[derive(Debug, Serialize)]
+pub struct OrphanTagRef {
+    pub entity: String, // "matter" 或 "repeat_task"
+    pub id: String,
+    pub orphan_tags: Vec<String>,
+}
+
+// 标签及其被事项引用的次数
+#[derive(Debug, Serialize)]
+pub struct TagUsage {
+    #[serde(flatten)]
+    pub tag: Tag,
+    pub usage_count: i64,
+}
+
+// 导入标签列表的结果：新增、保留和删除的标签数量
+#[derive(Debug, Default, Serialize)]
+pub struct TagImportReport {
+    pub added: usize,
+    pub kept: usize, // 已存在的标签，包括不在列表中但仍被引用、因此没有删除的标签
+    pub pruned: usize,
+}
+
+// 删除标签前的影响范围：引用该标签的事项和周期任务数量
+#[derive(Debug, Serialize)]
+pub struct TagDeleteImpact {
+    pub tag: String,
+    pub matters: usize,
+    pub repeat_tasks: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagSuggestion {
+    pub name: String,
+    pub usage_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimelineGroup {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+// 与前端 vis-timeline 的 TimelineItem 字段保持一致
+#[derive(Debug, Serialize)]
+pub struct TimelineItem {
+    pub id: String,
+    pub group: String,
+    pub content: String,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+    #[serde(rename = "className")]
+    pub class_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub priority: i32,
+    pub matter_type: i32,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelineData {
+    pub groups: Vec<TimelineGroup>,
+    pub items: Vec<TimelineItem>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -97,6 +506,44 @@ pub struct Todo {
     pub created_at: DateTime<Utc>,
     #[serde(default = "default_datetime")]
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub position: i64, // 列表中的排序位置
+}
+
+// 待办状态，只能按 todo -> in_progress -> completed 逐步推进或逐步回退
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoStatus {
+    Todo = 0,
+    InProgress = 1,
+    Completed = 2,
+}
+
+impl TodoStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoStatus::Todo => "todo",
+            TodoStatus::InProgress => "in_progress",
+            TodoStatus::Completed => "completed",
+        }
+    }
+
+    // 相邻状态之间可以转换，状态不变也视为合法
+    pub fn can_transition_to(self, next: TodoStatus) -> bool {
+        (self as i32 - next as i32).abs() <= 1
+    }
+}
+
+impl TryFrom<&str> for TodoStatus {
+    type Error = String;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "todo" => Ok(TodoStatus::Todo),
+            "in_progress" => Ok(TodoStatus::InProgress),
+            "completed" => Ok(TodoStatus::Completed),
+            _ => Err(format!("Invalid todo status: {}", value)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +563,28 @@ pub struct NotificationRecord {
     pub reserved_3: Option<String>,
     pub reserved_4: Option<String>,
     pub reserved_5: Option<String>,
+    #[serde(default)]
+    pub fire_at: Option<DateTime<Utc>>, // 定时通知的触发时间，为空表示立即生效
+}
+
+// 独立于事项和周期任务的重复提醒，例如每小时提醒喝水
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringNotification {
+    #[serde(default)]
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub repeat_time: String, // 与周期任务相同的 "weekdayBits|HH:MM|HH:MM"，不排除节假日
+    #[serde(default)]
+    pub interval_minutes: Option<i64>, // 为空时只在开始时间提醒，否则在时间段内每隔该分钟数提醒
+    #[serde(default = "default_recurring_notification_type")]
+    pub type_: i32,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default = "default_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "default_datetime")]
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,31 +593,341 @@ pub enum NotificationStatus {
     Read = 1,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationType {
+    System = 0,
+    Reminder = 1,
+    Custom = 2,
+}
+
+impl TryFrom<i32> for NotificationType {
+    type Error = String;
+
+    fn try_from(value: i32) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(NotificationType::System),
+            1 => Ok(NotificationType::Reminder),
+            2 => Ok(NotificationType::Custom),
+            _ => Err(format!("Invalid notification type: {}", value)),
+        }
+    }
+}
+
+// 变更日志中的一条记录，old_data/new_data 为整行数据的 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeLogEntry {
+    pub seq: i64,
+    pub entity: String,
+    pub entity_id: String,
+    pub operation: String, // "insert", "update", "delete"
+    pub old_data: Option<String>,
+    pub new_data: Option<String>,
+    pub changed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub undone: bool, // 已被撤销
+    #[serde(default)]
+    pub reverts_seq: Option<i64>, // 由撤销/重做 seq 对应的变更产生
+}
+
+// 分页查询的一页通知，total 为符合条件的总数
+#[derive(Debug, Serialize)]
+pub struct NotificationPage {
+    pub items: Vec<NotificationRecord>,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationTypeSummary {
+    pub notification_type: NotificationType,
+    pub total: i64,
+    pub unread: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeAnomalyKind {
+    DefaultTimestamp, // 应有值的时间为默认值（1970-01-01）
+    FutureCreatedAt,
+    UpdatedBeforeCreated,
+}
+
+// 一条时间异常的记录，corrected 表示已自动修正
+#[derive(Debug, Serialize)]
+pub struct TimeAnomaly {
+    pub entity: String,
+    pub id: String,
+    pub kinds: Vec<TimeAnomalyKind>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub corrected: bool,
+}
+
+// 二进制备份中一张表的数据
+#[derive(Debug, Serialize, Deserialize)]
+struct TableDump {
+    table: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<CellValue>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum CellValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl From<rusqlite::types::Value> for CellValue {
+    fn from(value: rusqlite::types::Value) -> Self {
+        match value {
+            rusqlite::types::Value::Null => CellValue::Null,
+            rusqlite::types::Value::Integer(value) => CellValue::Integer(value),
+            rusqlite::types::Value::Real(value) => CellValue::Real(value),
+            rusqlite::types::Value::Text(value) => CellValue::Text(value),
+            rusqlite::types::Value::Blob(value) => CellValue::Blob(value),
+        }
+    }
+}
+
+impl From<CellValue> for rusqlite::types::Value {
+    fn from(value: CellValue) -> Self {
+        match value {
+            CellValue::Null => rusqlite::types::Value::Null,
+            CellValue::Integer(value) => rusqlite::types::Value::Integer(value),
+            CellValue::Real(value) => rusqlite::types::Value::Real(value),
+            CellValue::Text(value) => rusqlite::types::Value::Text(value),
+            CellValue::Blob(value) => rusqlite::types::Value::Blob(value),
+        }
+    }
+}
+
+// 破坏性操作的结果，以及操作前自动备份的文件路径（未开启自动备份时为空）
+#[derive(Debug, Serialize)]
+pub struct BackedUp<T> {
+    pub result: T,
+    pub backup_path: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum DbError {
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("无效参数：{0}")]
+    InvalidArgument(String),
+    #[error("未找到资源：{0}")]
+    NotFound(String),
+    #[error("资源冲突：{0}")]
+    Conflict(String),
+    #[error("无效的状态转换：{from} -> {to}")]
+    InvalidTransition { from: String, to: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// 写入共用一个连接；WAL 模式下读取从只读连接中取一个空闲的，没有只读连接时使用写连接
+struct ConnectionPool {
+    writer: Mutex<Connection>,
+    readers: Vec<Mutex<Connection>>,
+    next_reader: AtomicUsize,
+}
+
+impl ConnectionPool {
+    fn read(&self) -> LockResult<MutexGuard<'_, Connection>> {
+        if self.readers.is_empty() {
+            return self.writer.lock();
+        }
+        for reader in &self.readers {
+            match reader.try_lock() {
+                Ok(guard) => return Ok(guard),
+                Err(TryLockError::Poisoned(e)) => return Err(e),
+                Err(TryLockError::WouldBlock) => {}
+            }
+        }
+        // 全部繁忙时轮流排队等待
+        let index = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[index].lock()
+    }
+
+    fn write(&self) -> LockResult<MutexGuard<'_, Connection>> {
+        self.writer.lock()
+    }
+}
+
+// 通知的变更，由 SafeConnection 上注册的监听器转发给前端
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum NotificationChange<'a> {
+    Created(&'a NotificationRecord),
+    // id、type_ 和 related_task_id 都为空时表示全部标记为已读
+    Read {
+        id: Option<&'a str>,
+        type_: Option<i32>,
+        related_task_id: Option<&'a str>,
+    },
+}
+
+pub type NotificationListener = Box<dyn Fn(&NotificationChange) + Send + Sync>;
+
 pub struct SafeConnection {
-    conn: RwLock<Connection>,
+    conn: ConnectionPool,
+    notification_listener: OnceLock<NotificationListener>,
 }
 
 impl SafeConnection {
     pub fn new(conn: Connection) -> Self {
+        Self::with_readers(conn, Vec::new())
+    }
+
+    // readers 必须是同一个数据库文件的只读连接
+    fn with_readers(conn: Connection, readers: Vec<Connection>) -> Self {
         Self {
-            conn: RwLock::new(conn),
+            conn: ConnectionPool {
+                writer: Mutex::new(conn),
+                readers: readers.into_iter().map(Mutex::new).collect(),
+                next_reader: AtomicUsize::new(0),
+            },
+            notification_listener: OnceLock::new(),
+        }
+    }
+
+    // 注册通知变更的监听器，只能注册一次
+    pub fn set_notification_listener(
+        &self,
+        listener: impl Fn(&NotificationChange) + Send + Sync + 'static,
+    ) {
+        if self.notification_listener.set(Box::new(listener)).is_err() {
+            log::warn!("Notification listener is already set");
         }
     }
+
+    pub(crate) fn notify(&self, change: NotificationChange) {
+        if let Some(listener) = self.notification_listener.get() {
+            listener(&change);
+        }
+    }
+
+    /// 持有写锁并在一个事务中执行 f，f 返回 Ok 时提交，返回错误时回滚
+    pub fn with_transaction<T, E>(
+        &self,
+        f: impl FnOnce(&Transaction) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E>
+    where
+        E: From<rusqlite::Error>,
+    {
+        let mut conn = self.conn.write().unwrap();
+        let tx = conn.transaction()?;
+        // tx 未提交就被释放时自动回滚
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
 }
 
-unsafe impl Send for SafeConnection {}
-unsafe impl Sync for SafeConnection {}
+// 数据库文件路径，数据目录无法获取或创建时返回错误而不是崩溃
+fn database_path(app_handle: &AppHandle) -> std::result::Result<PathBuf, DbError> {
+    let app_dir = utils::get_app_data_dir(app_handle.clone()).map_err(std::io::Error::other)?;
+    Ok(app_dir.join(DB_NAME))
+}
 
-pub fn initialize_database(app_handle: &AppHandle) -> Result<Arc<SafeConnection>> {
-    let app_dir = utils::get_app_data_dir(app_handle.clone()).unwrap();
-    let db_path = app_dir.join(DB_NAME);
+pub fn initialize_database(
+    app_handle: &AppHandle,
+) -> std::result::Result<Arc<SafeConnection>, DbError> {
+    open_database(&database_path(app_handle)?)
+}
 
+// 打开（必要时创建）数据库文件，执行迁移并打开只读连接
+fn open_database(db_path: &Path) -> std::result::Result<Arc<SafeConnection>, DbError> {
     let flags = OpenFlags::SQLITE_OPEN_READ_WRITE
         | OpenFlags::SQLITE_OPEN_CREATE
         | OpenFlags::SQLITE_OPEN_NO_MUTEX;
 
-    let conn = Connection::open_with_flags(db_path, flags)?;
+    let mut conn = Connection::open_with_flags(db_path, flags)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    // WAL 模式下读写互不阻塞；NORMAL 在 WAL 下不会损坏数据库，只是断电时可能丢失最近的提交
+    let journal_mode: String =
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+    if !journal_mode.eq_ignore_ascii_case("wal") {
+        log::warn!("Failed to enable WAL, journal mode is {}", journal_mode);
+    }
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+    run_migrations(&mut conn)?;
+    install_change_triggers(&conn)?;
+    ensure_indexes(&conn)?;
+
+    let readers = if journal_mode.eq_ignore_ascii_case("wal") {
+        open_readers(db_path)?
+    } else {
+        Vec::new()
+    };
+    let db = Arc::new(SafeConnection::with_readers(conn, readers));
+    // 启动时清理已过期的通知，失败不影响启动
+    match NotificationRecord::purge_expired(&db, Utc::now()) {
+        Ok(0) => {}
+        Ok(removed) => log::info!("Removed {} expired notifications", removed),
+        Err(e) => log::warn!("Failed to remove expired notifications: {}", e),
+    }
+
+    Ok(db)
+}
+
+// 打开只读连接，需要在迁移完成后调用，保证读到的是最新的表结构
+fn open_readers(db_path: &Path) -> Result<Vec<Connection>> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    (0..READER_CONNECTIONS)
+        .map(|_| {
+            let reader = Connection::open_with_flags(db_path, flags)?;
+            reader.busy_timeout(BUSY_TIMEOUT)?;
+            reader.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+            Ok(reader)
+        })
+        .collect()
+}
+
+// 测试用的内存数据库，与 initialize_database 执行相同的迁移、触发器和索引
+#[cfg(test)]
+pub(crate) fn open_in_memory() -> Arc<SafeConnection> {
+    let mut conn = Connection::open_in_memory().unwrap();
+    run_migrations(&mut conn).unwrap();
+    install_change_triggers(&conn).unwrap();
+    ensure_indexes(&conn).unwrap();
+    Arc::new(SafeConnection::new(conn))
+}
+
+// 数据库结构迁移，MIGRATIONS[i] 将 user_version 从 i 升级到 i + 1。
+// 修改表结构时追加一步迁移并增加 CURRENT_DB_VERSION，已执行过的迁移不要再改动
+type Migration = fn(&Connection) -> Result<()>;
+
+const MIGRATIONS: [Migration; CURRENT_DB_VERSION as usize] =
+    [migrate_v1, migrate_v2, migrate_v3, migrate_v4, migrate_v5];
+
+// 读取 PRAGMA user_version，依次执行缺少的迁移，每一步与版本号更新在同一事务中提交
+fn run_migrations(conn: &mut Connection) -> Result<()> {
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > CURRENT_DB_VERSION {
+        log::warn!(
+            "Database version {} is newer than supported version {}",
+            version,
+            CURRENT_DB_VERSION
+        );
+        return Ok(());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", index as u32 + 1)?;
+        tx.commit()?;
+        log::info!("Migrated database to version {}", index + 1);
+    }
+    Ok(())
+}
 
+// 版本 1：建立所有表，并为迁移框架之前创建的数据库补齐后来新增的列
+fn migrate_v1(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS matter (
             id TEXT PRIMARY KEY,
@@ -189,11 +968,6 @@ pub fn initialize_database(app_handle: &AppHandle) -> Result<Arc<SafeConnection>
         [],
     )?;
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_matter_time ON matter(start_time, end_time)",
-        [],
-    )?;
-
     conn.execute(
         "CREATE TABLE IF NOT EXISTS repeat_task (
             id TEXT PRIMARY KEY,
@@ -215,7 +989,8 @@ pub fn initialize_database(app_handle: &AppHandle) -> Result<Arc<SafeConnection>
             title TEXT NOT NULL,
             status TEXT NOT NULL,
             created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL
+            updated_at DATETIME NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0
         )",
         [],
     )?;
@@ -236,18 +1011,425 @@ pub fn initialize_database(app_handle: &AppHandle) -> Result<Arc<SafeConnection>
             reserved_2 TEXT,
             reserved_3 TEXT,
             reserved_4 TEXT,
-            reserved_5 TEXT
+            reserved_5 TEXT,
+            fire_at DATETIME
         )",
         [],
     )?;
 
-    Ok(Arc::new(SafeConnection::new(conn)))
-}
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS timeline_group (
+            id TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            sort_order INTEGER DEFAULT 0
+        )",
+        [],
+    )?;
 
-impl Matter {
-    pub fn create(conn: &Arc<SafeConnection>, matter: &Matter) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS recurring_notification (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            repeat_time TEXT NOT NULL,
+            interval_minutes INTEGER,
+            type INTEGER NOT NULL DEFAULT 2,
+            active INTEGER NOT NULL DEFAULT 1,
+            created_at DATETIME NOT NULL,
+            updated_at DATETIME NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tag_category (
+            tag TEXT PRIMARY KEY,
+            category TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    ensure_column(conn, "notification_records", "fire_at", "DATETIME")?;
+    ensure_column(conn, "tags", "color", "TEXT")?;
+    ensure_column(conn, "todo", "position", "INTEGER NOT NULL DEFAULT 0")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS change_log (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            operation TEXT NOT NULL,
+            old_data TEXT,
+            new_data TEXT,
+            changed_at DATETIME NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0,
+            reverts_seq INTEGER
+        )",
+        [],
+    )?;
+    ensure_column(conn, "change_log", "undone", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "change_log", "reverts_seq", "INTEGER")?;
+    Ok(())
+}
+
+// 版本 2：事项的全文索引，由触发器与 matter 表保持同步，并为已有事项建立索引。
+// 插入时先删除同 id 的索引，INSERT OR REPLACE 替换旧行时不会触发删除触发器
+fn migrate_v2(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS matter_fts USING fts5(
+            id UNINDEXED,
+            title,
+            description,
+            tags
+        );
+
+        CREATE TRIGGER IF NOT EXISTS matter_fts_insert AFTER INSERT ON matter
+        BEGIN
+            DELETE FROM matter_fts WHERE id = NEW.id;
+            INSERT INTO matter_fts (id, title, description, tags)
+            VALUES (NEW.id, NEW.title, NEW.description, NEW.tags);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS matter_fts_update AFTER UPDATE ON matter
+        BEGIN
+            DELETE FROM matter_fts WHERE id = OLD.id;
+            INSERT INTO matter_fts (id, title, description, tags)
+            VALUES (NEW.id, NEW.title, NEW.description, NEW.tags);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS matter_fts_delete AFTER DELETE ON matter
+        BEGIN
+            DELETE FROM matter_fts WHERE id = OLD.id;
+        END;
+
+        DELETE FROM matter_fts;
+        INSERT INTO matter_fts (id, title, description, tags)
+        SELECT id, title, description, tags FROM matter;",
+    )
+}
+
+// 版本 3：键值对的过期时间，为空表示永不过期
+fn migrate_v3(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "kvstore", "expires_at", "DATETIME")
+}
+
+// 版本 4：事项的删除时间，不为空表示已移入回收站
+fn migrate_v4(conn: &Connection) -> Result<()> {
+    ensure_column(conn, "matter", "deleted_at", "DATETIME")
+}
+
+// 版本 5：周期任务的例外，每个任务的每个日期最多一条
+fn migrate_v5(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS repeat_task_exceptions (
+            task_id TEXT NOT NULL,
+            occurrence_date TEXT NOT NULL,
+            action TEXT NOT NULL,
+            override_json TEXT,
+            created_at DATETIME NOT NULL,
+            PRIMARY KEY (task_id, occurrence_date)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// 初始化失败时以只读方式打开已有数据库，写操作会返回 SQLite 的只读错误
+pub fn open_read_only_database(
+    app_handle: &AppHandle,
+) -> std::result::Result<Arc<SafeConnection>, DbError> {
+    let db_path = database_path(app_handle)?;
+
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let conn = Connection::open_with_flags(db_path, flags)?;
+
+    Ok(Arc::new(SafeConnection::new(conn)))
+}
+
+// 补建缺失的索引，已存在的索引不会重复创建
+fn ensure_indexes(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type = 'index'")?;
+    let existing = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<HashSet<String>>>()?;
+
+    for (name, table, columns) in INDEXES {
+        if existing.contains(name) {
+            continue;
+        }
+        conn.execute(
+            &format!(
+                "CREATE INDEX IF NOT EXISTS {} ON {}({})",
+                name, table, columns
+            ),
+            [],
+        )?;
+        log::info!("Created missing index {} on {}({})", name, table, columns);
+    }
+    Ok(())
+}
+
+// 按 updated_at 条件更新没有命中任何行时，区分记录不存在和已被修改
+fn check_versioned_update(
+    conn: &Connection,
+    table: &str,
+    entity: &str,
+    id: &str,
+    updated: usize,
+) -> std::result::Result<(), DbError> {
+    if updated > 0 {
+        return Ok(());
+    }
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?1)", table),
+        params![id],
+        |row| row.get(0),
+    )?;
+    if exists {
+        Err(DbError::Conflict(format!(
+            "{} {} was modified since it was loaded",
+            entity, id
+        )))
+    } else {
+        Err(DbError::NotFound(format!("{} {}", entity, id)))
+    }
+}
+
+// 为旧版本创建的表补充新增的列
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let columns = table_columns(conn, table)?;
+
+    if !columns.iter().any(|name| name == column) {
         conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?.collect();
+    columns
+}
+
+// 为需要记录变更的表（重新）创建触发器，列有增减时触发器随之更新
+fn install_change_triggers(conn: &Connection) -> Result<()> {
+    for (table, key) in CHANGE_TRACKED_TABLES {
+        let columns = table_columns(conn, table)?;
+        let row_json = |row: &str| {
+            let fields: Vec<String> = columns
+                .iter()
+                .map(|column| format!("'{0}', {1}.{0}", column, row))
+                .collect();
+            format!("json_object({})", fields.join(", "))
+        };
+
+        for (operation, event, entity_id, old_data, new_data) in [
+            (
+                "insert",
+                "INSERT",
+                "NEW",
+                "NULL".to_string(),
+                row_json("NEW"),
+            ),
+            ("update", "UPDATE", "NEW", row_json("OLD"), row_json("NEW")),
+            (
+                "delete",
+                "DELETE",
+                "OLD",
+                row_json("OLD"),
+                "NULL".to_string(),
+            ),
+        ] {
+            conn.execute_batch(&format!(
+                "DROP TRIGGER IF EXISTS change_log_{table}_{operation};
+                CREATE TRIGGER change_log_{table}_{operation} AFTER {event} ON {table}
+                BEGIN
+                    INSERT INTO change_log (entity, entity_id, operation, old_data, new_data, changed_at)
+                    VALUES ('{table}', {entity_id}.{key}, '{operation}', {old_data}, {new_data},
+                        strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'));
+                END;"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+// 时间线每次刷新都会执行的范围查询
+const MATTER_TIME_RANGE_SQL: &str = "SELECT * FROM matter
+    WHERE deleted_at IS NULL
+    AND ((start_time BETWEEN ?1 AND ?2)
+    OR (end_time BETWEEN ?1 AND ?2)
+    OR (start_time <= ?1 AND end_time >= ?2))
+    ORDER BY start_time";
+
+impl Matter {
+    fn from_row(row: &rusqlite::Row) -> Result<Matter> {
+        Ok(Matter {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            description: row.get(2)?,
+            tags: row.get(3)?,
+            start_time: row.get(4)?,
+            end_time: row.get(5)?,
+            priority: row.get(6)?,
+            type_: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+            reserved_1: row.get(10)?,
+            reserved_2: row.get(11)?,
+            reserved_3: row.get(12)?,
+            reserved_4: row.get(13)?,
+            reserved_5: row.get(14)?,
+        })
+    }
+
+    // 未设置结束时间（仍在进行中）的事项
+    pub fn is_open_ended(&self) -> bool {
+        check_is_default_datetime(self.end_time)
+    }
+
+    // 相对 at 的阶段，未结束的事项开始后一直视为进行中
+    pub fn phase_at(&self, at: DateTime<Utc>) -> MatterPhase {
+        if self.start_time > at {
+            MatterPhase::Upcoming
+        } else if self.is_open_ended() || self.end_time > at {
+            MatterPhase::Ongoing
+        } else {
+            MatterPhase::Past
+        }
+    }
+
+    // 标题不能为空；结束时间不能早于开始时间，未结束（结束时间为默认值）的事项除外
+    pub fn validate(&self) -> std::result::Result<(), DbError> {
+        if self.title.trim().is_empty() {
+            return Err(DbError::InvalidArgument(format!(
+                "Matter {} has an empty title",
+                self.id
+            )));
+        }
+        if !self.is_open_ended() && self.end_time < self.start_time {
+            return Err(DbError::InvalidArgument(format!(
+                "Matter {} ends at {} before it starts at {}",
+                self.id,
+                self.end_time.to_rfc3339(),
+                self.start_time.to_rfc3339()
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn create(conn: &Arc<SafeConnection>, matter: &Matter) -> std::result::Result<(), DbError> {
+        matter.validate()?;
+
+        let conn = conn.conn.write().unwrap();
+        Matter::check_single_running(&conn, matter)?;
+        Matter::insert(&conn, matter)?;
+        Ok(())
+    }
+
+    // 在同一个事务中批量创建，任意一条无效或失败时全部回滚
+    pub fn create_batch(
+        conn: &Arc<SafeConnection>,
+        matters: &[Matter],
+    ) -> std::result::Result<(), DbError> {
+        for matter in matters {
+            matter.validate()?;
+        }
+
+        conn.with_transaction(|tx| {
+            for matter in matters {
+                Matter::check_single_running(tx, matter)?;
+                Matter::insert(tx, matter)?;
+            }
+            Ok(())
+        })
+    }
+
+    // 使用 KV 中的默认优先级/类型补全草稿中未设置的字段后创建
+    pub fn create_with_defaults(
+        conn: &Arc<SafeConnection>,
+        draft: MatterDraft,
+    ) -> std::result::Result<Matter, DbError> {
+        let priority = match draft.priority {
+            Some(priority) => priority,
+            None => KVStore::get(conn, DEFAULT_MATTER_PRIORITY_KEY, "0")?
+                .parse()
+                .unwrap_or(0),
+        };
+        let type_ = match draft.type_ {
+            Some(type_) => type_,
+            None => KVStore::get(conn, DEFAULT_MATTER_TYPE_KEY, "0")?
+                .parse()
+                .unwrap_or(0),
+        };
+
+        let now = Utc::now();
+        let matter = Matter {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: draft.title,
+            description: draft.description,
+            tags: draft.tags,
+            start_time: draft.start_time,
+            end_time: draft.end_time,
+            priority,
+            type_,
+            created_at: now,
+            updated_at: now,
+            reserved_1: None,
+            reserved_2: None,
+            reserved_3: draft.reserved_3,
+            reserved_4: None,
+            reserved_5: None,
+        };
+        Matter::create(conn, &matter)?;
+        Ok(matter)
+    }
+
+    // 由本地日期和开始/结束时刻创建事项，结束时刻早于开始时刻时视为次日
+    pub fn create_from_local(
+        conn: &Arc<SafeConnection>,
+        title: &str,
+        local_date: NaiveDate,
+        start: NaiveTime,
+        end: NaiveTime,
+        timezone: Tz,
+        tags: Option<String>,
+    ) -> std::result::Result<Matter, DbError> {
+        let end_date = if end < start {
+            local_date
+                .succ_opt()
+                .ok_or_else(|| DbError::InvalidArgument(format!("Invalid date: {}", local_date)))?
+        } else {
+            local_date
+        };
+        let start_time = utils::local_to_utc(local_date.and_time(start), timezone);
+        let end_time = utils::local_to_utc(end_date.and_time(end), timezone);
+        if end_time <= start_time {
+            return Err(DbError::InvalidArgument(format!(
+                "End time {} must be after start time {}",
+                end, start
+            )));
+        }
+
+        let draft = MatterDraft {
+            title: title.to_string(),
+            description: None,
+            tags,
+            start_time,
+            end_time,
+            priority: None,
+            type_: None,
+            reserved_3: None,
+        };
+        Matter::create_with_defaults(conn, draft)
+    }
+
+    // 使用缓存的语句，批量插入时只需准备一次
+    pub(crate) fn insert(conn: &Connection, matter: &Matter) -> Result<()> {
+        let mut stmt = conn.prepare_cached(
             "INSERT INTO matter (
                 id, title, description, tags, start_time, end_time,
                 priority, type, created_at, updated_at,
@@ -255,80 +1437,412 @@ impl Matter {
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15
             )",
-            params![
-                matter.id,
-                matter.title,
-                matter.description,
-                matter.tags,
-                matter.start_time,
-                matter.end_time,
-                matter.priority,
-                matter.type_,
-                matter.created_at,
-                matter.updated_at,
-                matter.reserved_1,
-                matter.reserved_2,
-                matter.reserved_3,
-                matter.reserved_4,
-                matter.reserved_5
-            ],
         )?;
+        stmt.execute(params![
+            matter.id,
+            matter.title,
+            matter.description,
+            matter.tags,
+            matter.start_time,
+            matter.end_time,
+            matter.priority,
+            matter.type_,
+            matter.created_at,
+            matter.updated_at,
+            matter.reserved_1,
+            matter.reserved_2,
+            matter.reserved_3,
+            matter.reserved_4,
+            matter.reserved_5
+        ])?;
         Ok(())
     }
 
     pub fn get_by_id(conn: &Arc<SafeConnection>, id: &str) -> Result<Option<Matter>> {
         let conn = conn.conn.read().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM matter WHERE id = ?1")?;
+        Matter::select_by_id(&conn, id)
+    }
 
-        let matter = stmt
-            .query_row(params![id], |row| {
-                Ok(Matter {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    tags: row.get(3)?,
-                    start_time: row.get(4)?,
-                    end_time: row.get(5)?,
-                    priority: row.get(6)?,
-                    type_: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
-                    reserved_1: row.get(10)?,
-                    reserved_2: row.get(11)?,
-                    reserved_3: row.get(12)?,
-                    reserved_4: row.get(13)?,
-                    reserved_5: row.get(14)?,
+    fn select_by_id(conn: &Connection, id: &str) -> Result<Option<Matter>> {
+        let mut stmt = conn.prepare_cached("SELECT * FROM matter WHERE id = ?1")?;
+        stmt.query_row(params![id], Matter::from_row).optional()
+    }
+
+    // 回收站中的事项视为不存在，用于修改事项的操作
+    fn select_active_by_id(conn: &Connection, id: &str) -> Result<Option<Matter>> {
+        let mut stmt =
+            conn.prepare_cached("SELECT * FROM matter WHERE id = ?1 AND deleted_at IS NULL")?;
+        stmt.query_row(params![id], Matter::from_row).optional()
+    }
+
+    pub fn get_with_local(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+        timezone: Tz,
+    ) -> Result<Option<LocalMatter>> {
+        let matter = match Matter::get_by_id(conn, id)? {
+            Some(matter) => matter,
+            None => return Ok(None),
+        };
+
+        let start_local = matter.start_time.with_timezone(&timezone);
+        let end_local = matter.end_time.with_timezone(&timezone);
+        let duration = (matter.end_time - matter.start_time).num_seconds();
+
+        Ok(Some(LocalMatter {
+            start_local: start_local.format(LOCAL_DATETIME_FORMAT).to_string(),
+            end_local: end_local.format(LOCAL_DATETIME_FORMAT).to_string(),
+            duration: utils::format_duration(duration),
+            matter,
+        }))
+    }
+
+    // 按区间图贪心着色为当天的事项分配列，未结束的事项视为持续到当天结束
+    pub fn day_layout(
+        conn: &Arc<SafeConnection>,
+        day: NaiveDate,
+        timezone: Tz,
+    ) -> Result<DayLayout> {
+        let day_start = utils::local_midnight(day, timezone);
+        let day_end = utils::local_midnight(day.succ_opt().unwrap_or(day), timezone);
+
+        let mut matters: Vec<(Matter, DateTime<Utc>)> =
+            Matter::get_by_time_range(conn, day_start, day_end)?
+                .into_iter()
+                .map(|matter| {
+                    let end = if matter.is_open_ended() {
+                        day_end.max(matter.start_time)
+                    } else {
+                        matter.end_time
+                    };
+                    (matter, end)
                 })
+                .filter(|(matter, end)| matter.start_time < day_end && *end > day_start)
+                .collect();
+        matters.sort_by(|(a, a_end), (b, b_end)| {
+            a.start_time.cmp(&b.start_time).then(a_end.cmp(b_end))
+        });
+
+        // 每一列最后一个事项的结束时间
+        let mut lane_ends: Vec<DateTime<Utc>> = Vec::new();
+        let matters = matters
+            .into_iter()
+            .map(|(matter, end)| {
+                let lane = match lane_ends
+                    .iter()
+                    .position(|lane_end| *lane_end <= matter.start_time)
+                {
+                    Some(lane) => {
+                        lane_ends[lane] = end;
+                        lane
+                    }
+                    None => {
+                        lane_ends.push(end);
+                        lane_ends.len() - 1
+                    }
+                };
+                MatterLane { matter, lane }
             })
-            .optional()?;
+            .collect();
 
-        Ok(matter)
+        Ok(DayLayout {
+            matters,
+            lane_count: lane_ends.len(),
+        })
     }
 
-    pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<Matter>> {
+    // 统计缺少标签、描述或时间字段为默认值的事项数量
+    pub fn completeness_report(conn: &Arc<SafeConnection>) -> Result<CompletenessReport> {
         let conn = conn.conn.read().unwrap();
-        let mut stmt = conn.prepare("SELECT * FROM matter ORDER BY start_time")?;
-        let matters = stmt
-            .query_map([], |row| {
-                Ok(Matter {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    tags: row.get(3)?,
-                    start_time: row.get(4)?,
-                    end_time: row.get(5)?,
-                    priority: row.get(6)?,
-                    type_: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
-                    reserved_1: row.get(10)?,
-                    reserved_2: row.get(11)?,
-                    reserved_3: row.get(12)?,
-                    reserved_4: row.get(13)?,
-                    reserved_5: row.get(14)?,
+        conn.query_row(
+            "SELECT
+                COUNT(*),
+                COALESCE(SUM(CASE WHEN tags IS NULL OR TRIM(tags, ', ') = '' THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN description IS NULL OR TRIM(description) = '' THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN start_time = ?1 OR created_at = ?1 OR updated_at = ?1 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN end_time = ?1 THEN 1 ELSE 0 END), 0)
+            FROM matter WHERE deleted_at IS NULL",
+            params![default_datetime()],
+            |row| {
+                Ok(CompletenessReport {
+                    total: row.get(0)?,
+                    missing_tags: row.get(1)?,
+                    missing_description: row.get(2)?,
+                    default_timestamps: row.get(3)?,
+                    open_ended: row.get(4)?,
                 })
-            })?
+            },
+        )
+    }
+
+    // 与 [start, end) 有重叠的事项，只相接的事项和未结束的事项不算重叠
+    pub fn find_overlaps(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        exclude_id: Option<&str>,
+    ) -> Result<Vec<Matter>> {
+        Ok(Matter::get_by_time_range(conn, start, end)?
+            .into_iter()
+            .filter(|matter| !matter.is_open_ended())
+            .filter(|matter| exclude_id != Some(matter.id.as_str()))
+            .filter(|matter| matter.start_time < end && start < matter.end_time)
+            .collect())
+    }
+
+    // 保存事项前用于冲突提示：每个重叠的事项及重叠的区间和时长
+    pub fn conflict_details(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        exclude_id: Option<&str>,
+    ) -> Result<Vec<MatterConflict>> {
+        Ok(Matter::find_overlaps(conn, start, end, exclude_id)?
+            .into_iter()
+            .map(|matter| {
+                let overlap_start = matter.start_time.max(start);
+                let overlap_end = matter.end_time.min(end);
+                MatterConflict {
+                    matter,
+                    overlap_start,
+                    overlap_end,
+                    overlap_seconds: (overlap_end - overlap_start).num_seconds(),
+                }
+            })
+            .collect())
+    }
+
+    /// 找出重叠区间与 [start, end) 相交的所有事项对，只相接的事项和未结束的事项不算重叠。
+    /// ignore_markers 为 true 时同时忽略零时长的标记和持续一整天及以上的全天事项
+    pub fn find_overlapping_pairs(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        ignore_markers: bool,
+    ) -> Result<Vec<MatterOverlap>> {
+        let is_marker = |matter: &Matter| {
+            let duration = matter.end_time - matter.start_time;
+            duration.is_zero() || duration >= chrono::Duration::days(1)
+        };
+        let matters: Vec<Matter> = Matter::get_by_time_range(conn, start, end)?
+            .into_iter()
+            .filter(|matter| {
+                !matter.is_open_ended() && !check_is_default_datetime(matter.start_time)
+            })
+            .filter(|matter| !(ignore_markers && is_marker(matter)))
+            .collect();
+
+        // 按开始时间扫描，active 中保存尚未结束的事项
+        let mut overlaps = Vec::new();
+        let mut active: Vec<&Matter> = Vec::new();
+        for matter in &matters {
+            active.retain(|other| other.end_time > matter.start_time);
+            for other in &active {
+                let overlap_start = matter.start_time;
+                let overlap_end = matter.end_time.min(other.end_time);
+                // 重叠区间需与查询范围相交
+                if overlap_start.max(start) >= overlap_end.min(end) {
+                    continue;
+                }
+                overlaps.push(MatterOverlap {
+                    first: (*other).clone(),
+                    second: matter.clone(),
+                    overlap_start,
+                    overlap_end,
+                    overlap_seconds: (overlap_end - overlap_start).num_seconds(),
+                });
+            }
+            active.push(matter);
+        }
+
+        Ok(overlaps)
+    }
+
+    // 时间范围内的事项，并标记与其他事项时间重叠的事项；未结束的事项不参与判断
+    pub fn get_range_with_conflicts(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<MatterWithConflict>> {
+        let mut matters: Vec<MatterWithConflict> = Matter::get_by_time_range(conn, start, end)?
+            .into_iter()
+            .map(|matter| MatterWithConflict {
+                matter,
+                has_conflict: false,
+            })
             .collect();
+
+        // 按开始时间扫描，active 中保存尚未结束的事项 (结束时间, 下标)
+        let mut active: Vec<(DateTime<Utc>, usize)> = Vec::new();
+        for index in 0..matters.len() {
+            let matter = &matters[index].matter;
+            if matter.is_open_ended() {
+                continue;
+            }
+            let (start_time, end_time) = (matter.start_time, matter.end_time);
+
+            active.retain(|(active_end, _)| *active_end > start_time);
+            if !active.is_empty() {
+                matters[index].has_conflict = true;
+                for (_, other) in &active {
+                    matters[*other].has_conflict = true;
+                }
+            }
+            active.push((end_time, index));
+        }
+
+        Ok(matters)
+    }
+
+    // 从 week_start 开始的 7 天，每天的事项片段、分列布局和总时长
+    pub fn week_grid(
+        conn: &Arc<SafeConnection>,
+        week_start: NaiveDate,
+        timezone: Tz,
+    ) -> Result<Vec<WeekGridDay>> {
+        week_start
+            .iter_days()
+            .take(7)
+            .map(|date| {
+                let day_start = utils::local_midnight(date, timezone);
+                let day_end = utils::local_midnight(date.succ_opt().unwrap_or(date), timezone);
+                let layout = Matter::day_layout(conn, date, timezone)?;
+
+                let mut total_seconds = 0;
+                let segments = layout
+                    .matters
+                    .into_iter()
+                    .map(|lane| {
+                        let start = lane.matter.start_time.max(day_start);
+                        let end = if lane.matter.is_open_ended() {
+                            day_end
+                        } else {
+                            total_seconds +=
+                                (lane.matter.end_time.min(day_end) - start).num_seconds();
+                            lane.matter.end_time.min(day_end)
+                        };
+                        WeekGridSegment {
+                            start_local: start
+                                .with_timezone(&timezone)
+                                .format(LOCAL_DATETIME_FORMAT)
+                                .to_string(),
+                            end_local: end
+                                .with_timezone(&timezone)
+                                .format(LOCAL_DATETIME_FORMAT)
+                                .to_string(),
+                            matter: lane,
+                        }
+                    })
+                    .collect();
+
+                Ok(WeekGridDay {
+                    date,
+                    segments,
+                    lane_count: layout.lane_count,
+                    total_seconds,
+                })
+            })
+            .collect()
+    }
+
+    // 距离最近一次活动的秒数：已结束的事项取结束时间，未结束或进行中的取开始时间
+    pub fn time_since_last(conn: &Arc<SafeConnection>, at: DateTime<Utc>) -> Result<Option<i64>> {
+        let conn = conn.conn.read().unwrap();
+        let last: Option<DateTime<Utc>> = conn.query_row(
+            "SELECT MAX(CASE WHEN end_time = ?2 OR end_time > ?1 THEN start_time ELSE end_time END)
+            FROM matter WHERE start_time <= ?1 AND deleted_at IS NULL",
+            params![at, default_datetime()],
+            |row| row.get(0),
+        )?;
+        Ok(last.map(|last| (at - last).num_seconds()))
+    }
+
+    // 进行中（未结束）的事项从开始到 at 的秒数，供前端计时器显示
+    pub fn running_elapsed(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+        at: DateTime<Utc>,
+    ) -> std::result::Result<i64, DbError> {
+        let matter = Matter::get_by_id(conn, id)?
+            .ok_or_else(|| DbError::NotFound(format!("Matter {}", id)))?;
+        if !matter.is_open_ended() {
+            return Err(DbError::InvalidArgument(format!(
+                "Matter {} is not running",
+                id
+            )));
+        }
+        Ok((at - matter.start_time).num_seconds().max(0))
+    }
+
+    // 进行中（未结束）的事项数量，正常情况下最多只有一个
+    pub fn running_count(conn: &Arc<SafeConnection>) -> Result<usize> {
+        let conn = conn.conn.read().unwrap();
+        Matter::count_running(&conn, None)
+    }
+
+    fn count_running(conn: &Connection, exclude_id: Option<&str>) -> Result<usize> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM matter
+            WHERE end_time = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR id != ?2)",
+            params![default_datetime(), exclude_id],
+            |row| row.get(0),
+        )
+    }
+
+    // 没有结束时间的事项表示开始计时，同一时间只允许一个事项在计时。
+    // 需要在持有写锁时检查，避免两个请求同时开始计时
+    fn check_single_running(
+        conn: &Connection,
+        matter: &Matter,
+    ) -> std::result::Result<(), DbError> {
+        if matter.is_open_ended() && Matter::count_running(conn, Some(&matter.id))? > 0 {
+            return Err(DbError::Conflict(
+                "Another matter is already running".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // 有多个进行中的事项时只保留最近开始的一个，其余的结束于下一个事项的开始时间
+    // （没有下一个事项时结束于现在），返回被结束的事项 id
+    pub fn fix_multiple_running(conn: &Arc<SafeConnection>) -> Result<Vec<String>> {
+        conn.with_transaction(|tx| {
+            let running: Vec<(String, DateTime<Utc>)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, start_time FROM matter
+                    WHERE end_time = ?1 AND deleted_at IS NULL
+                    ORDER BY start_time DESC, created_at DESC",
+                )?;
+                let rows = stmt.query_map(params![default_datetime()], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?;
+                rows.collect::<Result<_>>()?
+            };
+
+            let now = Utc::now();
+            let mut closed = Vec::new();
+            for (id, start_time) in running.into_iter().skip(1) {
+                let successor: Option<DateTime<Utc>> = tx.query_row(
+                    "SELECT MIN(start_time) FROM matter
+                    WHERE start_time > ?1 AND deleted_at IS NULL",
+                    params![start_time],
+                    |row| row.get(0),
+                )?;
+                tx.execute(
+                    "UPDATE matter SET end_time = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![successor.unwrap_or(now).max(start_time), now, id],
+                )?;
+                closed.push(id);
+            }
+            Ok(closed)
+        })
+    }
+
+    pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<Matter>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT * FROM matter WHERE deleted_at IS NULL ORDER BY start_time")?;
+        let matters = stmt.query_map([], Matter::from_row)?.collect();
         matters
     }
 
@@ -338,41 +1852,116 @@ impl Matter {
         end: DateTime<Utc>,
     ) -> Result<Vec<Matter>> {
         let conn = conn.conn.read().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT * FROM matter
-            WHERE (start_time BETWEEN ?1 AND ?2)
-            OR (end_time BETWEEN ?1 AND ?2)
-            OR (start_time <= ?1 AND end_time >= ?2)
-            ORDER BY start_time",
-        )?;
+        let mut stmt = conn.prepare_cached(MATTER_TIME_RANGE_SQL)?;
 
         let matters = stmt
-            .query_map(params![start, end], |row| {
-                Ok(Matter {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    tags: row.get(3)?,
-                    start_time: row.get(4)?,
-                    end_time: row.get(5)?,
-                    priority: row.get(6)?,
-                    type_: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
-                    reserved_1: row.get(10)?,
-                    reserved_2: row.get(11)?,
-                    reserved_3: row.get(12)?,
-                    reserved_4: row.get(13)?,
-                    reserved_5: row.get(14)?,
-                })
-            })?
+            .query_map(params![start, end], Matter::from_row)?
             .collect();
 
         matters
     }
 
-    pub fn update(&self, conn: &Arc<SafeConnection>) -> Result<()> {
+    // day 所在本地日（按相对 UTC 的偏移分钟数计算，东八区为 480）内的事项
+    pub fn get_for_day(
+        conn: &Arc<SafeConnection>,
+        day: DateTime<Utc>,
+        tz_offset_minutes: i32,
+    ) -> std::result::Result<Vec<Matter>, DbError> {
+        let offset = tz_offset_minutes
+            .checked_mul(60)
+            .and_then(FixedOffset::east_opt)
+            .ok_or_else(|| {
+                DbError::InvalidArgument(format!(
+                    "Invalid timezone offset: {} minutes",
+                    tz_offset_minutes
+                ))
+            })?;
+
+        let local_midnight = day
+            .with_timezone(&offset)
+            .date_naive()
+            .and_time(NaiveTime::MIN);
+        let day_start = (local_midnight - offset).and_utc();
+        let day_end = day_start + chrono::Duration::days(1);
+        Ok(Matter::get_by_time_range(conn, day_start, day_end)?)
+    }
+
+    // 按分组组装时间线数据，分组 id 保存在 reserved_3 中
+    pub fn get_grouped_timeline(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<TimelineData> {
+        let mut groups = TimelineGroup::get_all(conn)?;
+        let group_ids: HashSet<String> = groups.iter().map(|group| group.id.clone()).collect();
+
+        let items: Vec<TimelineItem> = Matter::get_by_time_range(conn, start, end)?
+            .into_iter()
+            .map(|matter| TimelineItem {
+                group: matter
+                    .reserved_3
+                    .filter(|group| group_ids.contains(group))
+                    .unwrap_or_else(|| DEFAULT_TIMELINE_GROUP.to_string()),
+                end: if check_is_default_datetime(matter.end_time) {
+                    None
+                } else {
+                    Some(matter.end_time)
+                },
+                tags: matter
+                    .tags
+                    .as_deref()
+                    .map(|tags| split_tags(tags).map(String::from).collect())
+                    .unwrap_or_default(),
+                id: matter.id,
+                content: matter.title,
+                start: matter.start_time,
+                class_name: matter.reserved_1,
+                created_at: matter.created_at,
+                priority: matter.priority,
+                matter_type: matter.type_,
+                description: matter.description,
+            })
+            .collect();
+
+        if items
+            .iter()
+            .any(|item| item.group == DEFAULT_TIMELINE_GROUP)
+        {
+            groups.push(TimelineGroup {
+                id: DEFAULT_TIMELINE_GROUP.to_string(),
+                content: "Default".to_string(),
+                sort_order: i32::MAX,
+            });
+        }
+
+        Ok(TimelineData { groups, items })
+    }
+
+    // expected_updated_at 为读取时的 updated_at，之后被其他窗口修改过时返回 Conflict
+    pub fn update(
+        &self,
+        conn: &Arc<SafeConnection>,
+        expected_updated_at: DateTime<Utc>,
+    ) -> std::result::Result<(), DbError> {
+        self.validate()?;
+
         let conn = conn.conn.write().unwrap();
+        Matter::check_single_running(&conn, self)?;
+        let updated = self.update_row_if_unchanged(&conn, Some(expected_updated_at))?;
+        check_versioned_update(&conn, "matter", "Matter", &self.id, updated)
+    }
+
+    fn update_row(&self, conn: &Connection) -> Result<()> {
+        self.update_row_if_unchanged(conn, None)?;
+        Ok(())
+    }
+
+    // expected_updated_at 不为空时只在 updated_at 未变化时更新，返回更新的行数
+    fn update_row_if_unchanged(
+        &self,
+        conn: &Connection,
+        expected_updated_at: Option<DateTime<Utc>>,
+    ) -> Result<usize> {
         conn.execute(
             "UPDATE matter SET
                 title = ?1, description = ?2, tags = ?3,
@@ -380,7 +1969,7 @@ impl Matter {
                 type = ?7, updated_at = ?8,
                 reserved_1 = ?9, reserved_2 = ?10, reserved_3 = ?11,
                 reserved_4 = ?12, reserved_5 = ?13
-            WHERE id = ?14",
+            WHERE id = ?14 AND (?15 IS NULL OR updated_at = ?15)",
             params![
                 self.title,
                 self.description,
@@ -395,35 +1984,289 @@ impl Matter {
                 self.reserved_3,
                 self.reserved_4,
                 self.reserved_5,
-                self.id
+                self.id,
+                expected_updated_at
             ],
+        )
+    }
+
+    // 移入回收站，可以通过 restore 恢复
+    pub fn delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "UPDATE matter SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![Utc::now(), id],
         )?;
         Ok(())
     }
 
-    pub fn delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
+    // 从数据库中彻底删除，不经过回收站
+    pub fn hard_delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
         let conn = conn.conn.write().unwrap();
         conn.execute("DELETE FROM matter WHERE id = ?1", params![id])?;
         Ok(())
     }
 
-    pub fn query_by_field(
-        conn: &Arc<SafeConnection>,
-        field: &str,
-        value: &str,
-        exact_match: bool,
-    ) -> Result<Vec<Matter>> {
-        let conn = conn.conn.read().unwrap();
-
-        // 构建查询语句
-        let query = if exact_match {
+    // 从回收站恢复事项
+    pub fn restore(conn: &Arc<SafeConnection>, id: &str) -> std::result::Result<(), DbError> {
+        let conn = conn.conn.write().unwrap();
+        let restored = conn.execute(
+            "UPDATE matter SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?;
+        if restored == 0 {
+            return Err(DbError::NotFound(format!("Trashed matter {}", id)));
+        }
+        Ok(())
+    }
+
+    // 回收站中的事项，最近删除的在前
+    pub fn get_trashed(conn: &Arc<SafeConnection>) -> Result<Vec<TrashedMatter>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM matter WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        )?;
+        let matters = stmt
+            .query_map([], |row| {
+                Ok(TrashedMatter {
+                    matter: Matter::from_row(row)?,
+                    deleted_at: row.get("deleted_at")?,
+                })
+            })?
+            .collect();
+        matters
+    }
+
+    // 彻底删除 older_than 之前移入回收站的事项，返回删除的数量
+    pub fn purge_deleted(conn: &Arc<SafeConnection>, older_than: DateTime<Utc>) -> Result<usize> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "DELETE FROM matter WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            params![older_than],
+        )
+    }
+
+    // 将事项移动到另一天，保持本地的开始时刻和时长不变，未结束的事项保持未结束
+    pub fn move_to_day(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+        target: NaiveDate,
+        timezone: Tz,
+    ) -> std::result::Result<Matter, DbError> {
+        let conn = conn.conn.write().unwrap();
+        let mut matter = Matter::select_active_by_id(&conn, id)?
+            .ok_or_else(|| DbError::NotFound(format!("Matter {}", id)))?;
+
+        let local_start = matter.start_time.with_timezone(&timezone).time();
+        let start = utils::local_to_utc(target.and_time(local_start), timezone);
+        if !matter.is_open_ended() {
+            matter.end_time = start + (matter.end_time - matter.start_time);
+        }
+        matter.start_time = start;
+        matter.updated_at = Utc::now();
+        matter.update_row(&conn)?;
+
+        Ok(matter)
+    }
+
+    // 在 at 时刻将事项拆分为前后两段，后一段使用新的 id
+    pub fn split(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+        at: DateTime<Utc>,
+    ) -> std::result::Result<(Matter, Matter), DbError> {
+        conn.with_transaction(|tx| {
+            let mut first = Matter::select_active_by_id(tx, id)?
+                .ok_or_else(|| DbError::NotFound(format!("Matter {}", id)))?;
+            if !(first.start_time < at && at < first.end_time) {
+                return Err(DbError::InvalidArgument(format!(
+                    "Split time {} is outside the matter span",
+                    at
+                )));
+            }
+
+            let now = Utc::now();
+            let second = Matter {
+                id: uuid::Uuid::new_v4().to_string(),
+                title: first.title.clone(),
+                description: first.description.clone(),
+                tags: first.tags.clone(),
+                start_time: at,
+                end_time: first.end_time,
+                priority: first.priority,
+                type_: first.type_,
+                created_at: now,
+                updated_at: now,
+                reserved_1: first.reserved_1.clone(),
+                reserved_2: first.reserved_2.clone(),
+                reserved_3: first.reserved_3.clone(),
+                reserved_4: first.reserved_4.clone(),
+                reserved_5: first.reserved_5.clone(),
+            };
+            first.end_time = at;
+            first.updated_at = now;
+
+            first.update_row(tx)?;
+            Matter::insert(tx, &second)?;
+            Ok((first, second))
+        })
+    }
+
+    // 将两个相邻或重叠的事项合并为一个，保留 id_a，删除 id_b
+    pub fn join(
+        conn: &Arc<SafeConnection>,
+        id_a: &str,
+        id_b: &str,
+    ) -> std::result::Result<Matter, DbError> {
+        if id_a == id_b {
+            return Err(DbError::InvalidArgument(
+                "Cannot join a matter with itself".into(),
+            ));
+        }
+
+        conn.with_transaction(|tx| {
+            let mut survivor = Matter::select_active_by_id(tx, id_a)?
+                .ok_or_else(|| DbError::NotFound(format!("Matter {}", id_a)))?;
+            let other = Matter::select_active_by_id(tx, id_b)?
+                .ok_or_else(|| DbError::NotFound(format!("Matter {}", id_b)))?;
+
+            if survivor.end_time < other.start_time || other.end_time < survivor.start_time {
+                return Err(DbError::InvalidArgument(
+                    "Only adjacent or overlapping matters can be joined".into(),
+                ));
+            }
+
+            let mut tags: Vec<&str> = Vec::new();
+            for tag in [survivor.tags.as_deref(), other.tags.as_deref()]
+                .into_iter()
+                .flatten()
+                .flat_map(split_tags)
+            {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            let tags = tags.join(",");
+
+            survivor.tags = Some(tags);
+            survivor.start_time = survivor.start_time.min(other.start_time);
+            survivor.end_time = survivor.end_time.max(other.end_time);
+            survivor.updated_at = Utc::now();
+
+            survivor.update_row(tx)?;
+            tx.execute("DELETE FROM matter WHERE id = ?1", params![other.id])?;
+            Ok(survivor)
+        })
+    }
+
+    // 给标题包含 query 的事项批量加上 tag，返回受影响的事项 id；dry_run 时只返回匹配结果
+    pub fn tag_by_search(
+        conn: &Arc<SafeConnection>,
+        query: &str,
+        tag: &str,
+        dry_run: bool,
+    ) -> std::result::Result<Vec<String>, DbError> {
+        let query = query.trim();
+        let tag = tag.trim();
+        if query.is_empty() || tag.is_empty() {
+            return Err(DbError::InvalidArgument(
+                "Search query and tag must not be empty".into(),
+            ));
+        }
+        if tag.contains(',') {
+            return Err(DbError::InvalidArgument(format!(
+                "Tag {} must not contain a comma",
+                tag
+            )));
+        }
+
+        conn.with_transaction(|tx| {
+            let matters: Vec<Matter> = {
+                let mut stmt = tx.prepare(
+                    "SELECT * FROM matter
+                    WHERE instr(lower(title), lower(?1)) > 0 AND deleted_at IS NULL
+                    ORDER BY start_time",
+                )?;
+                let matters = stmt.query_map(params![query], Matter::from_row)?;
+                matters.collect::<Result<_>>()?
+            };
+
+            let now = Utc::now();
+            let mut affected = Vec::new();
+            for mut matter in matters {
+                let existing = matter.tags.as_deref().unwrap_or("");
+                if split_tags(existing).any(|t| t == tag) {
+                    continue;
+                }
+                if !dry_run {
+                    let tags: Vec<&str> = split_tags(existing).chain([tag]).collect();
+                    matter.tags = Some(tags.join(","));
+                    matter.updated_at = now;
+                    matter.update_row(tx)?;
+                }
+                affected.push(matter.id);
+            }
+
+            if !dry_run && !affected.is_empty() {
+                tx.execute(
+                    "INSERT INTO tags (name, created_at, last_used_at) VALUES (?1, ?2, ?2)
+                    ON CONFLICT(name) DO UPDATE SET last_used_at = ?2",
+                    params![tag, now],
+                )?;
+            }
+            Ok(affected)
+        })
+    }
+
+    // 在标题、描述和标签中全文搜索，按相关度排序。每个词按前缀匹配，多个词需同时匹配
+    pub fn search(conn: &Arc<SafeConnection>, query: &str) -> Result<Vec<Matter>> {
+        // 每个词加引号，避免用户输入被当作 FTS5 查询语法
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT matter.* FROM matter_fts
+            JOIN matter ON matter.id = matter_fts.id
+            WHERE matter_fts MATCH ?1 AND matter.deleted_at IS NULL
+            ORDER BY matter_fts.rank",
+        )?;
+        let matters = stmt
+            .query_map(params![terms.join(" ")], Matter::from_row)?
+            .collect();
+        matters
+    }
+
+    pub fn query_by_field(
+        conn: &Arc<SafeConnection>,
+        field: &str,
+        value: &str,
+        exact_match: bool,
+    ) -> std::result::Result<Vec<Matter>, DbError> {
+        if !MATTER_QUERY_FIELDS.contains(&field) {
+            return Err(DbError::InvalidArgument(format!(
+                "Invalid field name: {}. Valid fields are: {}",
+                field,
+                MATTER_QUERY_FIELDS.join(", ")
+            )));
+        }
+
+        let conn = conn.conn.read().unwrap();
+
+        // 构建查询语句
+        let query = if exact_match {
             format!(
-                "SELECT * FROM matter WHERE {} = ?1 ORDER BY start_time",
+                "SELECT * FROM matter WHERE {} = ?1 AND deleted_at IS NULL ORDER BY start_time",
                 field
             )
         } else {
             format!(
-                "SELECT * FROM matter WHERE {} LIKE ?1 ORDER BY start_time",
+                "SELECT * FROM matter WHERE {} LIKE ?1 AND deleted_at IS NULL ORDER BY start_time",
                 field
             )
         };
@@ -438,51 +2281,136 @@ impl Matter {
         };
 
         let matters = stmt
-            .query_map([search_value], |row| {
-                Ok(Matter {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    tags: row.get(3)?,
-                    start_time: row.get(4)?,
-                    end_time: row.get(5)?,
-                    priority: row.get(6)?,
-                    type_: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
-                    reserved_1: row.get(10)?,
-                    reserved_2: row.get(11)?,
-                    reserved_3: row.get(12)?,
-                    reserved_4: row.get(13)?,
-                    reserved_5: row.get(14)?,
-                })
-            })?
-            .collect();
+            .query_map([search_value], Matter::from_row)?
+            .collect::<Result<_>>()?;
 
-        matters
+        Ok(matters)
     }
 }
 
+// 写入键值对，已存在时保留 created_at
+const KV_UPSERT_SQL: &str = "INSERT INTO kvstore (key, value, created_at, updated_at, expires_at)
+    VALUES (?1, ?2, ?3, ?3, ?4)
+    ON CONFLICT(key) DO UPDATE SET
+    value = ?2, updated_at = ?3, expires_at = ?4";
+
 // KVStore 相关操作
 impl KVStore {
     pub fn set(conn: &Arc<SafeConnection>, key: &str, value: &str) -> Result<()> {
+        KVStore::set_with_expiry(conn, key, value, None)
+    }
+
+    // 设置一个 ttl_seconds 秒后过期的值，适合缓存统计结果等数据
+    pub fn set_with_ttl(
+        conn: &Arc<SafeConnection>,
+        key: &str,
+        value: &str,
+        ttl_seconds: i64,
+    ) -> std::result::Result<(), DbError> {
+        if ttl_seconds <= 0 {
+            return Err(DbError::InvalidArgument(
+                "ttl_seconds must be positive".to_string(),
+            ));
+        }
+        let expires_at = Utc::now() + chrono::Duration::seconds(ttl_seconds);
+        KVStore::set_with_expiry(conn, key, value, Some(expires_at))?;
+        Ok(())
+    }
+
+    // 写入值并覆盖原有的过期时间
+    fn set_with_expiry(
+        conn: &Arc<SafeConnection>,
+        key: &str,
+        value: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
         let conn = conn.conn.write().unwrap();
-        let now = Utc::now();
+        conn.execute(KV_UPSERT_SQL, params![key, value, Utc::now(), expires_at])?;
+        Ok(())
+    }
+
+    // 在同一个事务中写入多个值，并清除它们的过期时间
+    pub fn set_many(conn: &Arc<SafeConnection>, entries: &[(&str, String)]) -> Result<()> {
+        conn.with_transaction(|tx| {
+            for (key, value) in entries {
+                KVStore::upsert(tx, key, value)?;
+            }
+            Ok(())
+        })
+    }
+
+    // 在调用方的连接或事务中写入值，并清除它的过期时间
+    pub(crate) fn upsert(conn: &Connection, key: &str, value: &str) -> Result<()> {
         conn.execute(
-            "INSERT INTO kvstore (key, value, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?3)
-            ON CONFLICT(key) DO UPDATE SET
-            value = ?2, updated_at = ?3",
-            params![key, value, now],
+            KV_UPSERT_SQL,
+            params![key, value, Utc::now(), None::<DateTime<Utc>>],
         )?;
         Ok(())
     }
 
+    // 已过期的值视为不存在，返回 default 并删除该行
     pub fn get(conn: &Arc<SafeConnection>, key: &str, default: &str) -> Result<String> {
-        let conn = conn.conn.read().unwrap();
-        let mut stmt = conn.prepare("SELECT value FROM kvstore WHERE key = ?1")?;
-        let value = stmt.query_row(params![key], |row| row.get(0)).optional()?;
-        Ok(value.unwrap_or(default.to_string()))
+        Ok(KVStore::get_optional(conn, key)?.unwrap_or_else(|| default.to_string()))
+    }
+
+    // 将值序列化为 JSON 字符串后写入
+    pub fn set_json<T: Serialize>(
+        conn: &Arc<SafeConnection>,
+        key: &str,
+        value: &T,
+    ) -> std::result::Result<(), DbError> {
+        let json = serde_json::to_string(value).map_err(|e| {
+            DbError::InvalidArgument(format!("Cannot serialize value of {}: {}", key, e))
+        })?;
+        KVStore::set(conn, key, &json)?;
+        Ok(())
+    }
+
+    // 不存在或已过期时返回 None，值不是 T 对应的 JSON 时返回 InvalidArgument
+    pub fn get_json<T: DeserializeOwned>(
+        conn: &Arc<SafeConnection>,
+        key: &str,
+    ) -> std::result::Result<Option<T>, DbError> {
+        let Some(json) = KVStore::get_optional(conn, key)? else {
+            return Ok(None);
+        };
+        let value = serde_json::from_str(&json).map_err(|e| {
+            DbError::InvalidArgument(format!("Value of {} is not valid JSON: {}", key, e))
+        })?;
+        Ok(Some(value))
+    }
+
+    fn get_optional(conn: &Arc<SafeConnection>, key: &str) -> Result<Option<String>> {
+        let now = Utc::now();
+        let row: Option<(String, Option<DateTime<Utc>>)> = {
+            let conn = conn.conn.read().unwrap();
+            let mut stmt =
+                conn.prepare_cached("SELECT value, expires_at FROM kvstore WHERE key = ?1")?;
+            stmt.query_row(params![key], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?
+        };
+
+        match row {
+            Some((_, Some(expires_at))) if expires_at <= now => {
+                let conn = conn.conn.write().unwrap();
+                conn.execute(
+                    "DELETE FROM kvstore WHERE key = ?1 AND expires_at <= ?2",
+                    params![key, now],
+                )?;
+                Ok(None)
+            }
+            Some((value, _)) => Ok(Some(value)),
+            None => Ok(None),
+        }
+    }
+
+    // 删除所有已过期的值，返回删除的行数
+    pub fn purge_expired(conn: &Arc<SafeConnection>) -> Result<usize> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "DELETE FROM kvstore WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+            params![Utc::now()],
+        )
     }
 
     pub fn delete(conn: &Arc<SafeConnection>, key: &str) -> Result<()> {
@@ -490,19 +2418,90 @@ impl KVStore {
         conn.execute("DELETE FROM kvstore WHERE key = ?1", params![key])?;
         Ok(())
     }
+
+    // 将当前所有设置序列化为 JSON 对象 {key: value}
+    pub fn snapshot_settings(conn: &Arc<SafeConnection>) -> Result<String> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM kvstore")?;
+        let rows: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+        let settings: BTreeMap<String, String> = rows
+            .into_iter()
+            .filter(|(key, _)| is_settings_key(key))
+            .collect();
+
+        Ok(serde_json::to_string(&settings).unwrap_or_default())
+    }
+
+    // 用快照替换当前设置，快照中没有的设置会被删除，返回恢复的设置数量
+    pub fn restore_settings(
+        conn: &Arc<SafeConnection>,
+        snapshot: &str,
+    ) -> std::result::Result<usize, DbError> {
+        let settings: BTreeMap<String, String> = serde_json::from_str(snapshot)
+            .map_err(|e| DbError::InvalidArgument(format!("Invalid settings snapshot: {}", e)))?;
+
+        conn.with_transaction(|tx| {
+            let existing: Vec<String> = {
+                let mut stmt = tx.prepare("SELECT key FROM kvstore")?;
+                let keys = stmt.query_map([], |row| row.get(0))?;
+                keys.collect::<Result<_>>()?
+            };
+            for key in existing {
+                if is_settings_key(&key) && !settings.contains_key(&key) {
+                    tx.execute("DELETE FROM kvstore WHERE key = ?1", params![key])?;
+                }
+            }
+
+            let now = Utc::now();
+            let mut restored = 0;
+            for (key, value) in settings.iter().filter(|(key, _)| is_settings_key(key)) {
+                tx.execute(
+                    "INSERT INTO kvstore (key, value, created_at, updated_at)
+                    VALUES (?1, ?2, ?3, ?3)
+                    ON CONFLICT(key) DO UPDATE SET
+                    value = ?2, updated_at = ?3",
+                    params![key, value, now],
+                )?;
+                restored += 1;
+            }
+            Ok(restored)
+        })
+    }
+
+    // 删除指定的设置，使其回到默认值，返回删除的数量
+    pub fn reset_settings_to_defaults(
+        conn: &Arc<SafeConnection>,
+        keys: &[String],
+    ) -> Result<usize> {
+        conn.with_transaction(|tx| {
+            let mut removed = 0;
+            for key in keys {
+                removed += tx.execute("DELETE FROM kvstore WHERE key = ?1", params![key])?;
+            }
+            Ok(removed)
+        })
+    }
 }
 
 // Tag 相关操作
 impl Tag {
     pub fn create(conn: &Arc<SafeConnection>, name: &str) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
-        conn.execute(
-            "INSERT OR IGNORE INTO tags (name, created_at, last_used_at) VALUES (?1, ?2, ?3)",
-            params![name, Utc::now(), Utc::now()],
-        )?;
+        Tag::insert_if_missing(&conn.conn.write().unwrap(), name)?;
         Ok(())
     }
 
+    // 标签已存在时不做修改，返回是否新建了标签
+    pub(crate) fn insert_if_missing(conn: &Connection, name: &str) -> Result<bool> {
+        let now = Utc::now();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO tags (name, created_at, last_used_at) VALUES (?1, ?2, ?2)",
+            params![name, now],
+        )?;
+        Ok(inserted > 0)
+    }
+
     pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<Tag>> {
         let conn = conn.conn.read().unwrap();
         let mut stmt = conn.prepare("SELECT * FROM tags ORDER BY name")?;
@@ -512,12 +2511,33 @@ impl Tag {
                     name: row.get(0)?,
                     created_at: row.get(1)?,
                     last_used_at: row.get(2)?,
+                    color: row.get(3)?,
                 })
             })?
             .collect();
         tags
     }
 
+    // 所有标签及其使用次数，按次数从多到少排序，次数相同时最近使用的在前
+    pub fn get_with_counts(conn: &Arc<SafeConnection>) -> Result<Vec<TagUsage>> {
+        let tags = Tag::get_all(conn)?;
+        let counts = Tag::usage_counts(&conn.conn.read().unwrap())?;
+
+        let mut usages: Vec<TagUsage> = tags
+            .into_iter()
+            .map(|tag| TagUsage {
+                usage_count: counts.get(&tag.name).copied().unwrap_or(0),
+                tag,
+            })
+            .collect();
+        usages.sort_by(|a, b| {
+            b.usage_count
+                .cmp(&a.usage_count)
+                .then(b.tag.last_used_at.cmp(&a.tag.last_used_at))
+        });
+        Ok(usages)
+    }
+
     pub fn update_last_used_at(conn: &Arc<SafeConnection>, name: &str) -> Result<()> {
         let conn = conn.conn.write().unwrap();
         conn.execute(
@@ -530,14 +2550,401 @@ impl Tag {
     pub fn delete(conn: &Arc<SafeConnection>, name: &str) -> Result<()> {
         let conn = conn.conn.write().unwrap();
         conn.execute("DELETE FROM tags WHERE name = ?1", params![name])?;
+        conn.execute("DELETE FROM tag_category WHERE tag = ?1", params![name])?;
+        Ok(())
+    }
+
+    // table 中 tags 字段包含 name 的行，返回 (id, tags)
+    fn referencing_rows(
+        conn: &Connection,
+        table: &str,
+        name: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, tags FROM {} WHERE tags IS NOT NULL AND tags != ''",
+            table
+        ))?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut referencing = Vec::new();
+        for row in rows {
+            let (id, tags): (String, String) = row?;
+            if split_tags(&tags).any(|tag| tag == name) {
+                referencing.push((id, tags));
+            }
+        }
+        Ok(referencing)
+    }
+
+    // 按外部标签列表导入：列表中没有的标签会被创建，prune_unlisted 为 true 时
+    // 删除不在列表中且没有被事项或周期任务引用的标签
+    pub fn import(
+        conn: &Arc<SafeConnection>,
+        names: &[String],
+        prune_unlisted: bool,
+    ) -> Result<TagImportReport> {
+        let listed: BTreeSet<&str> = names
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        conn.with_transaction(|tx| {
+            let existing: Vec<String> = {
+                let mut stmt = tx.prepare("SELECT name FROM tags")?;
+                let names = stmt.query_map([], |row| row.get(0))?;
+                names.collect::<Result<_>>()?
+            };
+
+            let now = Utc::now();
+            let mut report = TagImportReport::default();
+            for name in &listed {
+                if existing.iter().any(|tag| tag == name) {
+                    report.kept += 1;
+                } else {
+                    tx.execute(
+                        "INSERT INTO tags (name, created_at, last_used_at) VALUES (?1, ?2, ?2)",
+                        params![name, now],
+                    )?;
+                    report.added += 1;
+                }
+            }
+
+            for name in existing
+                .iter()
+                .filter(|name| !listed.contains(name.as_str()))
+            {
+                let referenced = !Tag::referencing_rows(tx, "matter", name)?.is_empty()
+                    || !Tag::referencing_rows(tx, "repeat_task", name)?.is_empty();
+                if prune_unlisted && !referenced {
+                    tx.execute("DELETE FROM tags WHERE name = ?1", params![name])?;
+                    tx.execute("DELETE FROM tag_category WHERE tag = ?1", params![name])?;
+                    report.pruned += 1;
+                } else {
+                    report.kept += 1;
+                }
+            }
+            Ok(report)
+        })
+    }
+
+    // 统计删除标签会影响的事项和周期任务，供界面确认
+    pub fn delete_impact(conn: &Arc<SafeConnection>, name: &str) -> Result<TagDeleteImpact> {
+        let conn = conn.conn.read().unwrap();
+        Ok(TagDeleteImpact {
+            tag: name.to_string(),
+            matters: Tag::referencing_rows(&conn, "matter", name)?.len(),
+            repeat_tasks: Tag::referencing_rows(&conn, "repeat_task", name)?.len(),
+        })
+    }
+
+    // 删除标签，strip_refs 为 true 时同时从引用它的事项和周期任务中移除该标签
+    pub fn delete_with_refs(
+        conn: &Arc<SafeConnection>,
+        name: &str,
+        strip_refs: bool,
+    ) -> Result<TagDeleteImpact> {
+        conn.with_transaction(|tx| {
+            let now = Utc::now();
+            let mut counts = [0; 2];
+            for (count, table) in counts.iter_mut().zip(["matter", "repeat_task"]) {
+                let rows = Tag::referencing_rows(tx, table, name)?;
+                *count = rows.len();
+                if !strip_refs {
+                    continue;
+                }
+                for (id, tags) in rows {
+                    let kept: Vec<&str> = split_tags(&tags).filter(|tag| *tag != name).collect();
+                    tx.execute(
+                        &format!(
+                            "UPDATE {} SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                            table
+                        ),
+                        params![kept.join(","), now, id],
+                    )?;
+                }
+            }
+
+            tx.execute("DELETE FROM tags WHERE name = ?1", params![name])?;
+            tx.execute("DELETE FROM tag_category WHERE tag = ?1", params![name])?;
+
+            let [matters, repeat_tasks] = counts;
+            Ok(TagDeleteImpact {
+                tag: name.to_string(),
+                matters,
+                repeat_tasks,
+            })
+        })
+    }
+
+    // 找出事项和周期任务中引用了 tags 表中不存在的标签，dry_run 为 false 时将这些标签移除
+    pub fn cleanup_orphan_refs(
+        conn: &Arc<SafeConnection>,
+        dry_run: bool,
+    ) -> Result<Vec<OrphanTagRef>> {
+        conn.with_transaction(|tx| {
+            let known: HashSet<String> = {
+                let mut stmt = tx.prepare("SELECT name FROM tags")?;
+                let names = stmt.query_map([], |row| row.get(0))?;
+                names.collect::<Result<_>>()?
+            };
+
+            let now = Utc::now();
+            let mut refs = Vec::new();
+            for table in ["matter", "repeat_task"] {
+                let rows: Vec<(String, String)> = {
+                    let mut stmt = tx.prepare(&format!(
+                        "SELECT id, tags FROM {} WHERE tags IS NOT NULL AND tags != ''",
+                        table
+                    ))?;
+                    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    rows.collect::<Result<_>>()?
+                };
+
+                for (id, tags) in rows {
+                    let (kept, orphan_tags): (Vec<&str>, Vec<&str>) =
+                        split_tags(&tags).partition(|tag| known.contains(*tag));
+                    if orphan_tags.is_empty() {
+                        continue;
+                    }
+                    if !dry_run {
+                        tx.execute(
+                            &format!(
+                                "UPDATE {} SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                                table
+                            ),
+                            params![kept.join(","), now, id],
+                        )?;
+                    }
+                    refs.push(OrphanTagRef {
+                        entity: table.to_string(),
+                        id,
+                        orphan_tags: orphan_tags.into_iter().map(String::from).collect(),
+                    });
+                }
+            }
+            Ok(refs)
+        })
+    }
+
+    pub fn set_color(conn: &Arc<SafeConnection>, name: &str, color: Option<&str>) -> Result<()> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "UPDATE tags SET color = ?1 WHERE name = ?2",
+            params![color, name],
+        )?;
+        Ok(())
+    }
+
+    // 设置标签所属的分类（如工作、健康），category 为空时取消分类
+    pub fn set_category(
+        conn: &Arc<SafeConnection>,
+        name: &str,
+        category: Option<&str>,
+    ) -> Result<()> {
+        let conn = conn.conn.write().unwrap();
+        match category
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+        {
+            Some(category) => conn.execute(
+                "INSERT INTO tag_category (tag, category) VALUES (?1, ?2)
+                ON CONFLICT(tag) DO UPDATE SET category = ?2",
+                params![name, category],
+            )?,
+            None => conn.execute("DELETE FROM tag_category WHERE tag = ?1", params![name])?,
+        };
+        Ok(())
+    }
+
+    // 标签到分类的映射，没有分类的标签不包含在内
+    pub fn categories(conn: &Arc<SafeConnection>) -> Result<HashMap<String, String>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare("SELECT tag, category FROM tag_category")?;
+        let categories = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect();
+        categories
+    }
+
+    // 为每个标签分配颜色，优先使用手动指定的颜色，否则根据名称生成固定颜色
+    pub fn palette(
+        conn: &Arc<SafeConnection>,
+        names: &[String],
+    ) -> Result<HashMap<String, String>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare("SELECT color FROM tags WHERE name = ?1")?;
+
+        let mut palette = HashMap::new();
+        for name in names {
+            let color: Option<String> = stmt
+                .query_row(params![name], |row| row.get(0))
+                .optional()?
+                .flatten();
+            let color = color.unwrap_or_else(|| utils::color_for_name(name));
+            palette.insert(name.clone(), color);
+        }
+        Ok(palette)
+    }
+
+    // 统计每个标签被 matter 引用的次数，回收站中的事项不计入
+    fn usage_counts(conn: &Connection) -> Result<HashMap<String, i64>> {
+        let mut stmt =
+            conn.prepare("SELECT tags FROM matter WHERE tags IS NOT NULL AND deleted_at IS NULL")?;
+        let mut counts = HashMap::new();
+        for tags in stmt.query_map([], |row| row.get::<_, String>(0))? {
+            for tag in split_tags(&tags?) {
+                *counts.entry(tag.to_string()).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    // 根据标题中的词匹配已有标签（忽略大小写，支持部分匹配），按使用次数排序
+    pub fn suggest_for_title(
+        conn: &Arc<SafeConnection>,
+        title: &str,
+    ) -> Result<Vec<TagSuggestion>> {
+        let tokens: Vec<String> = title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let tags = Tag::get_all(conn)?;
+        let conn = conn.conn.read().unwrap();
+        let counts = Tag::usage_counts(&conn)?;
+
+        let mut matched: Vec<(Tag, i64)> = tags
+            .into_iter()
+            .filter(|tag| {
+                let name = tag.name.to_lowercase();
+                tokens.iter().any(|token| {
+                    token.contains(&name) || (token.chars().count() > 1 && name.contains(token))
+                })
+            })
+            .map(|tag| {
+                let count = counts.get(&tag.name).copied().unwrap_or(0);
+                (tag, count)
+            })
+            .collect();
+
+        matched.sort_by(|(a, a_count), (b, b_count)| {
+            b_count
+                .cmp(a_count)
+                .then_with(|| b.last_used_at.cmp(&a.last_used_at))
+        });
+
+        Ok(matched
+            .into_iter()
+            .map(|(tag, usage_count)| TagSuggestion {
+                name: tag.name,
+                usage_count,
+            })
+            .collect())
+    }
+}
+
+// TimelineGroup 相关操作
+impl TimelineGroup {
+    pub fn create(conn: &Arc<SafeConnection>, group: &TimelineGroup) -> Result<()> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "INSERT INTO timeline_group (id, content, sort_order) VALUES (?1, ?2, ?3)
+            ON CONFLICT(id) DO UPDATE SET content = ?2, sort_order = ?3",
+            params![group.id, group.content, group.sort_order],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<TimelineGroup>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt =
+            conn.prepare_cached("SELECT * FROM timeline_group ORDER BY sort_order, id")?;
+        let groups = stmt
+            .query_map([], |row| {
+                Ok(TimelineGroup {
+                    id: row.get(0)?,
+                    content: row.get(1)?,
+                    sort_order: row.get(2)?,
+                })
+            })?
+            .collect();
+        groups
+    }
+
+    pub fn delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute("DELETE FROM timeline_group WHERE id = ?1", params![id])?;
         Ok(())
     }
 }
 
 // RepeatTask 相关操作
 impl RepeatTask {
-    pub fn create(conn: &Arc<SafeConnection>, task: &RepeatTask) -> Result<()> {
+    pub fn create(
+        conn: &Arc<SafeConnection>,
+        task: &RepeatTask,
+    ) -> std::result::Result<(), DbError> {
         let conn = conn.conn.write().unwrap();
+
+        let unique: Option<String> = conn
+            .query_row(
+                "SELECT value FROM kvstore WHERE key = ?1",
+                params![UNIQUE_REPEAT_TITLES_KEY],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if unique.as_deref() == Some("true") {
+            let title = task.title.trim().to_lowercase();
+            let mut stmt = conn.prepare("SELECT title FROM repeat_task")?;
+            let titles = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            for existing in titles {
+                if existing?.trim().to_lowercase() == title {
+                    return Err(DbError::Conflict(format!(
+                        "Repeat task {} already exists",
+                        task.title
+                    )));
+                }
+            }
+        }
+
+        RepeatTask::insert(&conn, task)?;
+        Ok(())
+    }
+
+    // 查找标题重复（不区分大小写）的周期任务
+    pub fn find_duplicate_titles(conn: &Arc<SafeConnection>) -> Result<Vec<DuplicateRepeatTitle>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare("SELECT id, title FROM repeat_task ORDER BY created_at")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut groups: Vec<DuplicateRepeatTitle> = Vec::new();
+        for (id, title) in rows {
+            let key = title.trim().to_lowercase();
+            match groups
+                .iter_mut()
+                .find(|group| group.title.trim().to_lowercase() == key)
+            {
+                Some(group) => group.ids.push(id),
+                None => groups.push(DuplicateRepeatTitle {
+                    title,
+                    ids: vec![id],
+                }),
+            }
+        }
+        groups.retain(|group| group.ids.len() > 1);
+
+        Ok(groups)
+    }
+
+    pub(crate) fn insert(conn: &Connection, task: &RepeatTask) -> Result<()> {
         conn.execute(
             "INSERT INTO repeat_task (
                 id, title, tags, repeat_time, status,
@@ -604,12 +3011,13 @@ impl RepeatTask {
         tasks
     }
 
-    pub fn get_active_tasks(conn: &Arc<SafeConnection>) -> Result<Vec<RepeatTask>> {
+    // 指定状态的任务，例如 -1 列出已归档的任务
+    pub fn get_by_status(conn: &Arc<SafeConnection>, status: i32) -> Result<Vec<RepeatTask>> {
         let conn = conn.conn.read().unwrap();
         let mut stmt =
-            conn.prepare("SELECT * FROM repeat_task WHERE status = 1 ORDER BY created_at DESC")?;
+            conn.prepare("SELECT * FROM repeat_task WHERE status = ?1 ORDER BY created_at DESC")?;
         let tasks = stmt
-            .query_map([], |row| {
+            .query_map(params![status], |row| {
                 Ok(RepeatTask {
                     id: row.get(0)?,
                     title: row.get(1)?,
@@ -626,18 +3034,46 @@ impl RepeatTask {
         tasks
     }
 
-    pub fn update(&self, conn: &Arc<SafeConnection>) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
-        conn.execute(
-            "UPDATE repeat_task SET
-                title = ?1,
-                tags = ?2,
-                repeat_time = ?3,
-                status = ?4,
-                updated_at = ?5,
-                priority = ?6,
+    pub fn get_active_tasks(conn: &Arc<SafeConnection>) -> Result<Vec<RepeatTask>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM repeat_task WHERE status = 1 ORDER BY created_at DESC",
+        )?;
+        let tasks = stmt
+            .query_map([], |row| {
+                Ok(RepeatTask {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    tags: row.get(2)?,
+                    repeat_time: row.get(3)?,
+                    status: row.get(4)?,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    priority: row.get(7)?,
+                    description: row.get(8)?,
+                })
+            })?
+            .collect();
+        tasks
+    }
+
+    // expected_updated_at 为读取时的 updated_at，之后被修改过时返回 Conflict
+    pub fn update(
+        &self,
+        conn: &Arc<SafeConnection>,
+        expected_updated_at: DateTime<Utc>,
+    ) -> std::result::Result<(), DbError> {
+        let conn = conn.conn.write().unwrap();
+        let updated = conn.execute(
+            "UPDATE repeat_task SET
+                title = ?1,
+                tags = ?2,
+                repeat_time = ?3,
+                status = ?4,
+                updated_at = ?5,
+                priority = ?6,
                 description = ?7
-            WHERE id = ?8",
+            WHERE id = ?8 AND updated_at = ?9",
             params![
                 self.title,
                 self.tags,
@@ -646,88 +3082,336 @@ impl RepeatTask {
                 self.updated_at,
                 self.priority,
                 self.description,
-                self.id
+                self.id,
+                expected_updated_at
             ],
         )?;
-        Ok(())
+        check_versioned_update(&conn, "repeat_task", "Repeat task", &self.id, updated)
     }
 
     pub fn delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
-        conn.execute("DELETE FROM repeat_task WHERE id = ?1", params![id])?;
-        Ok(())
+        conn.with_transaction(|tx| {
+            tx.execute("DELETE FROM repeat_task WHERE id = ?1", params![id])?;
+            tx.execute(
+                "DELETE FROM repeat_task_exceptions WHERE task_id = ?1",
+                params![id],
+            )?;
+            Ok(())
+        })
+    }
+
+    // 从 JSON 数组批量导入重复任务，校验每一项的 repeat_time，dry_run 时只校验不写入
+    pub fn import(
+        conn: &Arc<SafeConnection>,
+        json: &str,
+        dry_run: bool,
+    ) -> std::result::Result<ImportReport, DbError> {
+        let items: Vec<serde_json::Value> = serde_json::from_str(json)
+            .map_err(|e| DbError::InvalidArgument(format!("Invalid JSON array: {}", e)))?;
+
+        let mut conn = conn.conn.write().unwrap();
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+
+        let mut report = ImportReport {
+            imported: Vec::new(),
+            errors: Vec::new(),
+            dry_run,
+        };
+        for (index, item) in items.into_iter().enumerate() {
+            let result = serde_json::from_value::<RepeatTask>(item)
+                .map_err(|e| e.to_string())
+                .and_then(|mut task| {
+                    if task.title.trim().is_empty() {
+                        return Err("Title must not be empty".to_string());
+                    }
+                    RepeatTime::parse(&task.repeat_time)?;
+                    if task.id.trim().is_empty() {
+                        task.id = uuid::Uuid::new_v4().to_string();
+                    }
+                    task.created_at = now;
+                    task.updated_at = now;
+                    RepeatTask::insert(&tx, &task).map_err(|e| e.to_string())?;
+                    Ok(task.id)
+                });
+
+            match result {
+                Ok(id) => report.imported.push(id),
+                Err(message) => report.errors.push(ImportItemError { index, message }),
+            }
+        }
+
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+        Ok(report)
     }
 
-    pub fn update_status(conn: &Arc<SafeConnection>, id: &str, new_status: i32) -> Result<()> {
+    // 启用任务（status = 1）前检查 repeat_time 能否解析，避免启用后无法生成事项
+    pub fn update_status(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+        new_status: i32,
+    ) -> std::result::Result<(), DbError> {
         let conn = conn.conn.write().unwrap();
+        let repeat_time: Option<String> = conn
+            .query_row(
+                "SELECT repeat_time FROM repeat_task WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(repeat_time) = repeat_time else {
+            return Err(DbError::NotFound(format!("Repeat task {}", id)));
+        };
+        if new_status == 1 {
+            RepeatTime::parse(&repeat_time).map_err(|e| {
+                DbError::InvalidArgument(format!("Cannot activate repeat task: {}", e))
+            })?;
+        }
+
         conn.execute(
             "UPDATE repeat_task SET status = ?1, updated_at = ?2 WHERE id = ?3",
             params![new_status, Utc::now(), id],
         )?;
         Ok(())
     }
+
+    pub fn activate(conn: &Arc<SafeConnection>, id: &str) -> std::result::Result<(), DbError> {
+        RepeatTask::update_status(conn, id, 1)
+    }
+
+    // 归档后不再展开，也不出现在 get_active_tasks 中，可以通过 activate 恢复
+    pub fn archive(conn: &Arc<SafeConnection>, id: &str) -> std::result::Result<(), DbError> {
+        RepeatTask::update_status(conn, id, -1)
+    }
+}
+
+impl RepeatTaskException {
+    fn from_row(row: &rusqlite::Row) -> Result<RepeatTaskException> {
+        Ok(RepeatTaskException {
+            task_id: row.get(0)?,
+            occurrence_date: row.get(1)?,
+            action: row.get(2)?,
+            override_json: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
+
+    fn validate(&self) -> std::result::Result<RepeatExceptionAction, DbError> {
+        let action = RepeatExceptionAction::try_from(self.action.as_str())
+            .map_err(DbError::InvalidArgument)?;
+        if action == RepeatExceptionAction::Override {
+            let json = self.override_json.as_deref().ok_or_else(|| {
+                DbError::InvalidArgument("override_json is required to override".to_string())
+            })?;
+            MatterOverride::parse(json).map_err(DbError::InvalidArgument)?;
+        }
+        Ok(action)
+    }
+
+    // 同一任务同一天已有例外时替换，skip 不保存 override_json
+    pub fn add(
+        conn: &Arc<SafeConnection>,
+        exception: &RepeatTaskException,
+    ) -> std::result::Result<(), DbError> {
+        let action = exception.validate()?;
+
+        let conn = conn.conn.write().unwrap();
+        let task_exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM repeat_task WHERE id = ?1)",
+            params![exception.task_id],
+            |row| row.get(0),
+        )?;
+        if !task_exists {
+            return Err(DbError::NotFound(format!(
+                "Repeat task {}",
+                exception.task_id
+            )));
+        }
+
+        let override_json = match action {
+            RepeatExceptionAction::Skip => None,
+            RepeatExceptionAction::Override => exception.override_json.as_deref(),
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO repeat_task_exceptions (
+                task_id, occurrence_date, action, override_json, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                exception.task_id,
+                exception.occurrence_date,
+                action.as_str(),
+                override_json,
+                exception.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    // 删除某一天的例外，恢复按规则展开，返回是否存在该例外
+    pub fn remove(conn: &Arc<SafeConnection>, task_id: &str, date: NaiveDate) -> Result<bool> {
+        let conn = conn.conn.write().unwrap();
+        let removed = conn.execute(
+            "DELETE FROM repeat_task_exceptions WHERE task_id = ?1 AND occurrence_date = ?2",
+            params![task_id, date],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_by_task(
+        conn: &Arc<SafeConnection>,
+        task_id: &str,
+    ) -> Result<Vec<RepeatTaskException>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM repeat_task_exceptions WHERE task_id = ?1 ORDER BY occurrence_date",
+        )?;
+        let exceptions = stmt
+            .query_map(params![task_id], RepeatTaskException::from_row)?
+            .collect();
+        exceptions
+    }
+
+    pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<RepeatTaskException>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT * FROM repeat_task_exceptions ORDER BY task_id, occurrence_date")?;
+        let exceptions = stmt.query_map([], RepeatTaskException::from_row)?.collect();
+        exceptions
+    }
 }
 
 impl Todo {
+    fn from_row(row: &rusqlite::Row) -> Result<Todo> {
+        Ok(Todo {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            status: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            position: row.get(5)?,
+        })
+    }
+
     pub fn create(conn: &Arc<SafeConnection>, todo: &Todo) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
+        Todo::insert(&conn.conn.write().unwrap(), todo)
+    }
+
+    pub(crate) fn insert(conn: &Connection, todo: &Todo) -> Result<()> {
         conn.execute(
-            "INSERT INTO todo (id, title, status, created_at, updated_at)
-    VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO todo (id, title, status, created_at, updated_at, position)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
                 todo.id,
                 todo.title,
                 todo.status,
                 todo.created_at,
-                todo.updated_at
+                todo.updated_at,
+                todo.position
             ],
         )?;
         Ok(())
     }
+
+    // 从清单文本批量创建待办，每个非空行一条，返回创建的 id
+    pub fn import_checklist(conn: &Arc<SafeConnection>, text: &str) -> Result<Vec<String>> {
+        conn.with_transaction(|tx| {
+            let mut position: i64 =
+                tx.query_row("SELECT COALESCE(MAX(position), -1) FROM todo", [], |row| {
+                    row.get(0)
+                })?;
+            let now = Utc::now();
+            let mut ids = Vec::new();
+            for title in text.lines().filter_map(checklist_item_title) {
+                position += 1;
+                let id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO todo (id, title, status, created_at, updated_at, position)
+                    VALUES (?1, ?2, 'todo', ?3, ?3, ?4)",
+                    params![id, title, now, position],
+                )?;
+                ids.push(id);
+            }
+            Ok(ids)
+        })
+    }
+
     pub fn get_by_id(conn: &Arc<SafeConnection>, id: &str) -> Result<Option<Todo>> {
         let conn = conn.conn.read().unwrap();
-        let mut stmt = conn.prepare("SELECT FROM todo WHERE id = ?1")?;
-        let todo = stmt
-            .query_row(params![id], |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    status: row.get(2)?,
-                    created_at: row.get(3)?,
-                    updated_at: row.get(4)?,
-                })
-            })
-            .optional()?;
-        Ok(todo)
+        conn.query_row(
+            "SELECT * FROM todo WHERE id = ?1",
+            params![id],
+            Todo::from_row,
+        )
+        .optional()
     }
     pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<Todo>> {
         let conn = conn.conn.read().unwrap();
         let mut stmt = conn.prepare("SELECT * FROM todo ORDER BY created_at DESC")?;
-        let todos = stmt
-            .query_map([], |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    status: row.get(2)?,
-                    created_at: row.get(3)?,
-                    updated_at: row.get(4)?,
-                })
-            })?
-            .collect();
+        let todos = stmt.query_map([], Todo::from_row)?.collect();
         todos
     }
 
-    pub fn update(&self, conn: &Arc<SafeConnection>) -> Result<()> {
+    // 今天之前最后更新且未完成的待办；bump 为 true 时将其更新时间改为现在，并排到列表最前
+    pub fn carry_over(conn: &Arc<SafeConnection>, timezone: Tz, bump: bool) -> Result<Vec<Todo>> {
+        let today = Utc::now().with_timezone(&timezone).date_naive();
+        let today_start = utils::local_midnight(today, timezone);
+
+        conn.with_transaction(|tx| {
+            let mut todos: Vec<Todo> = {
+                let mut stmt = tx.prepare(
+                    "SELECT * FROM todo WHERE status != 'completed' AND updated_at < ?1
+                    ORDER BY position, created_at",
+                )?;
+                let todos = stmt.query_map(params![today_start], Todo::from_row)?;
+                todos.collect::<Result<_>>()?
+            };
+
+            if bump && !todos.is_empty() {
+                let first: i64 =
+                    tx.query_row("SELECT COALESCE(MIN(position), 0) FROM todo", [], |row| {
+                        row.get(0)
+                    })?;
+                let now = Utc::now();
+                let start = first - todos.len() as i64;
+                for (todo, position) in todos.iter_mut().zip(start..) {
+                    todo.position = position;
+                    todo.updated_at = now;
+                    tx.execute(
+                        "UPDATE todo SET position = ?1, updated_at = ?2 WHERE id = ?3",
+                        params![todo.position, todo.updated_at, todo.id],
+                    )?;
+                }
+            }
+            Ok(todos)
+        })
+    }
+
+    // expected_updated_at 为读取时的 updated_at，之后被修改过时返回 Conflict
+    pub fn update(
+        &self,
+        conn: &Arc<SafeConnection>,
+        expected_updated_at: DateTime<Utc>,
+    ) -> std::result::Result<(), DbError> {
         let conn = conn.conn.write().unwrap();
-        conn.execute(
+        let updated = conn.execute(
             "UPDATE todo SET
         title = ?1,
         status = ?2,
         updated_at = ?3
-        WHERE id = ?4",
-            params![self.title, self.status, self.updated_at, self.id],
+        WHERE id = ?4 AND updated_at = ?5",
+            params![
+                self.title,
+                self.status,
+                self.updated_at,
+                self.id,
+                expected_updated_at
+            ],
         )?;
-        Ok(())
+        check_versioned_update(&conn, "todo", "Todo", &self.id, updated)
     }
 
     pub fn delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
@@ -735,18 +3419,328 @@ impl Todo {
         conn.execute("DELETE FROM todo WHERE id = ?1", params![id])?;
         Ok(())
     }
+
+    // 修改状态并校验转换是否合法；当前状态无法识别时允许改为任意合法状态
+    pub fn set_status(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+        new_status: &str,
+    ) -> std::result::Result<(), DbError> {
+        let next = TodoStatus::try_from(new_status).map_err(DbError::InvalidArgument)?;
+
+        conn.with_transaction(|tx| {
+            let current: String = tx
+                .query_row(
+                    "SELECT status FROM todo WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| DbError::NotFound(format!("Todo {}", id)))?;
+            if let Ok(current) = TodoStatus::try_from(current.as_str()) {
+                if !current.can_transition_to(next) {
+                    return Err(DbError::InvalidTransition {
+                        from: current.as_str().to_string(),
+                        to: next.as_str().to_string(),
+                    });
+                }
+            }
+
+            tx.execute(
+                "UPDATE todo SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![next.as_str(), Utc::now(), id],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+impl ChangeLogEntry {
+    fn from_row(row: &rusqlite::Row) -> Result<ChangeLogEntry> {
+        Ok(ChangeLogEntry {
+            seq: row.get(0)?,
+            entity: row.get(1)?,
+            entity_id: row.get(2)?,
+            operation: row.get(3)?,
+            old_data: row.get(4)?,
+            new_data: row.get(5)?,
+            changed_at: row.get(6)?,
+            undone: row.get(7)?,
+            reverts_seq: row.get(8)?,
+        })
+    }
+
+    // 撤销最近一条未撤销的变更，没有可撤销的变更时返回 None
+    pub fn undo_last(
+        conn: &Arc<SafeConnection>,
+    ) -> std::result::Result<Option<ChangeLogEntry>, DbError> {
+        conn.with_transaction(|tx| {
+            let entry = tx
+                .query_row(
+                    "SELECT * FROM change_log WHERE undone = 0 AND reverts_seq IS NULL
+                    ORDER BY seq DESC LIMIT 1",
+                    [],
+                    ChangeLogEntry::from_row,
+                )
+                .optional()?;
+            let Some(mut entry) = entry else {
+                return Ok(None);
+            };
+
+            ChangeLogEntry::revert(tx, &entry, true)?;
+            entry.undone = true;
+            Ok(Some(entry))
+        })
+    }
+
+    // 重做最早一条被撤销的变更，之后已有新变更时不再重做
+    pub fn redo_last(
+        conn: &Arc<SafeConnection>,
+    ) -> std::result::Result<Option<ChangeLogEntry>, DbError> {
+        conn.with_transaction(|tx| {
+            let entry = tx
+                .query_row(
+                    "SELECT * FROM change_log WHERE undone = 1 AND seq > (
+                        SELECT COALESCE(MAX(seq), 0) FROM change_log
+                        WHERE undone = 0 AND reverts_seq IS NULL
+                    )
+                    ORDER BY seq LIMIT 1",
+                    [],
+                    ChangeLogEntry::from_row,
+                )
+                .optional()?;
+            let Some(mut entry) = entry else {
+                return Ok(None);
+            };
+
+            ChangeLogEntry::revert(tx, &entry, false)?;
+            entry.undone = false;
+            Ok(Some(entry))
+        })
+    }
+
+    // 撤销（undo 为 true）或重新应用一条变更，并标记由此产生的变更记录
+    fn revert(
+        conn: &Connection,
+        entry: &ChangeLogEntry,
+        undo: bool,
+    ) -> std::result::Result<(), DbError> {
+        let last_seq: i64 =
+            conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM change_log", [], |row| {
+                row.get(0)
+            })?;
+
+        if undo {
+            entry.inverse().apply(conn)?;
+        } else {
+            entry.apply(conn)?;
+        }
+
+        conn.execute(
+            "UPDATE change_log SET reverts_seq = ?1 WHERE seq > ?2",
+            params![entry.seq, last_seq],
+        )?;
+        conn.execute(
+            "UPDATE change_log SET undone = ?1 WHERE seq = ?2",
+            params![undo, entry.seq],
+        )?;
+        Ok(())
+    }
+
+    // 与本条变更作用相反的变更
+    fn inverse(&self) -> ChangeLogEntry {
+        let operation = match self.operation.as_str() {
+            "insert" => "delete",
+            "delete" => "insert",
+            operation => operation,
+        };
+        ChangeLogEntry {
+            operation: operation.to_string(),
+            old_data: self.new_data.clone(),
+            new_data: self.old_data.clone(),
+            ..self.clone()
+        }
+    }
+
+    pub fn get_since(conn: &Arc<SafeConnection>, seq: i64) -> Result<Vec<ChangeLogEntry>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare("SELECT * FROM change_log WHERE seq > ?1 ORDER BY seq")?;
+        let entries = stmt
+            .query_map(params![seq], ChangeLogEntry::from_row)?
+            .collect();
+        entries
+    }
+
+    // [start, end) 内记录的变更，按 seq 排序
+    pub fn get_between(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ChangeLogEntry>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM change_log WHERE changed_at >= ?1 AND changed_at < ?2 ORDER BY seq",
+        )?;
+        let entries = stmt
+            .query_map(params![start, end], ChangeLogEntry::from_row)?
+            .collect();
+        entries
+    }
+
+    // 导出 seq 之后的变更，序列化为 JSON 后用 gzip 压缩
+    pub fn export_since(
+        conn: &Arc<SafeConnection>,
+        seq: i64,
+    ) -> std::result::Result<Vec<u8>, DbError> {
+        let entries = ChangeLogEntry::get_since(conn, seq)?;
+        let json = serde_json::to_vec(&entries)
+            .map_err(|e| DbError::InvalidArgument(format!("Invalid change entry: {}", e)))?;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json)?;
+        Ok(encoder.finish()?)
+    }
+
+    // 解压并按顺序应用 export_since 导出的变更，返回应用的条数
+    pub fn import(
+        conn: &Arc<SafeConnection>,
+        compressed: &[u8],
+    ) -> std::result::Result<usize, DbError> {
+        let mut json = Vec::new();
+        GzDecoder::new(compressed)
+            .read_to_end(&mut json)
+            .map_err(|e| DbError::InvalidArgument(format!("Invalid gzip payload: {}", e)))?;
+        let entries: Vec<ChangeLogEntry> = serde_json::from_slice(&json)
+            .map_err(|e| DbError::InvalidArgument(format!("Invalid change payload: {}", e)))?;
+
+        conn.with_transaction(|tx| {
+            for entry in &entries {
+                entry.apply(tx)?;
+            }
+            Ok(entries.len())
+        })
+    }
+
+    fn apply(&self, conn: &Connection) -> std::result::Result<(), DbError> {
+        let (table, key) = CHANGE_TRACKED_TABLES
+            .into_iter()
+            .find(|(table, _)| *table == self.entity)
+            .ok_or_else(|| DbError::InvalidArgument(format!("Unknown entity {}", self.entity)))?;
+
+        if self.operation == "delete" {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE {} = ?1", table, key),
+                params![self.entity_id],
+            )?;
+            return Ok(());
+        }
+
+        let data = self.new_data.as_deref().ok_or_else(|| {
+            DbError::InvalidArgument(format!("Change {} has no row data", self.seq))
+        })?;
+        let row: serde_json::Map<String, serde_json::Value> = serde_json::from_str(data)
+            .map_err(|e| DbError::InvalidArgument(format!("Invalid row data: {}", e)))?;
+
+        // 只写入本地表中存在的列
+        let columns: Vec<String> = table_columns(conn, table)?
+            .into_iter()
+            .filter(|column| row.contains_key(column))
+            .collect();
+        let values: Vec<rusqlite::types::Value> = columns
+            .iter()
+            .map(|column| match &row[column] {
+                serde_json::Value::Null => rusqlite::types::Value::Null,
+                serde_json::Value::Bool(value) => rusqlite::types::Value::Integer(*value as i64),
+                serde_json::Value::Number(value) => match value.as_i64() {
+                    Some(value) => rusqlite::types::Value::Integer(value),
+                    None => rusqlite::types::Value::Real(value.as_f64().unwrap_or_default()),
+                },
+                serde_json::Value::String(value) => rusqlite::types::Value::Text(value.clone()),
+                value => rusqlite::types::Value::Text(value.to_string()),
+            })
+            .collect();
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+
+        conn.execute(
+            &format!(
+                "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                table,
+                columns.join(", "),
+                placeholders.join(", ")
+            ),
+            rusqlite::params_from_iter(values),
+        )?;
+        Ok(())
+    }
+}
+
+// CSV 字段转义：包含逗号、引号或换行时用双引号包裹，内部引号加倍
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 将 [start, end) 内的变更导出为审计用的 CSV，包含表头
+pub fn export_changelog_csv(
+    conn: &Arc<SafeConnection>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<String> {
+    let mut csv = String::from("seq,timestamp,entity,entity_id,operation\n");
+    for entry in ChangeLogEntry::get_between(conn, start, end)? {
+        let row = [
+            entry.seq.to_string(),
+            entry.changed_at.to_rfc3339(),
+            entry.entity.clone(),
+            entry.entity_id.clone(),
+            entry.operation.clone(),
+        ];
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
 }
 
 impl NotificationRecord {
+    fn from_row(row: &rusqlite::Row) -> Result<NotificationRecord> {
+        Ok(NotificationRecord {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            type_: row.get(3)?,
+            status: row.get(4)?,
+            related_task_id: row.get(5)?,
+            created_at: row.get(6)?,
+            read_at: row.get(7)?,
+            expire_at: row.get(8)?,
+            action_url: row.get(9)?,
+            reserved_1: row.get(10)?,
+            reserved_2: row.get(11)?,
+            reserved_3: row.get(12)?,
+            reserved_4: row.get(13)?,
+            reserved_5: row.get(14)?,
+            fire_at: row.get(15)?,
+        })
+    }
+
     pub fn create(conn: &Arc<SafeConnection>, notification: &NotificationRecord) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
+        NotificationRecord::insert(&conn.conn.write().unwrap(), notification)?;
+        conn.notify(NotificationChange::Created(notification));
+        Ok(())
+    }
+
+    pub(crate) fn insert(conn: &Connection, notification: &NotificationRecord) -> Result<()> {
         conn.execute(
             "INSERT INTO notification_records (
                 id, title, content, type, status, related_task_id,
                 created_at, read_at, expire_at, action_url,
-                reserved_1, reserved_2, reserved_3, reserved_4, reserved_5
+                reserved_1, reserved_2, reserved_3, reserved_4, reserved_5, fire_at
             ) VALUES (
-                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16
             )",
             params![
                 notification.id,
@@ -763,7 +3757,8 @@ impl NotificationRecord {
                 notification.reserved_2,
                 notification.reserved_3,
                 notification.reserved_4,
-                notification.reserved_5
+                notification.reserved_5,
+                notification.fire_at
             ],
         )?;
         Ok(())
@@ -771,58 +3766,166 @@ impl NotificationRecord {
 
     pub fn get_unread(conn: &Arc<SafeConnection>) -> Result<Vec<NotificationRecord>> {
         let conn = conn.conn.read().unwrap();
-        let mut stmt = conn.prepare(
+        let mut stmt = conn.prepare_cached(
             "SELECT * FROM notification_records
-            WHERE status = 0
+            WHERE status = 0 AND (fire_at IS NULL OR fire_at <= ?1)
+            AND (expire_at IS NULL OR expire_at > ?1)
             ORDER BY created_at DESC",
         )?;
 
         let notifications = stmt
-            .query_map([], |row| {
-                Ok(NotificationRecord {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    type_: row.get(3)?,
-                    status: row.get(4)?,
-                    related_task_id: row.get(5)?,
-                    created_at: row.get(6)?,
-                    read_at: row.get(7)?,
-                    expire_at: row.get(8)?,
-                    action_url: row.get(9)?,
-                    reserved_1: row.get(10)?,
-                    reserved_2: row.get(11)?,
-                    reserved_3: row.get(12)?,
-                    reserved_4: row.get(13)?,
-                    reserved_5: row.get(14)?,
-                })
+            .query_map(params![Utc::now()], NotificationRecord::from_row)?
+            .collect();
+
+        notifications
+    }
+
+    // 按状态和类型筛选当前可见（已生效且未过期）的通知，最新的在前，为空的条件不筛选
+    pub fn get_filtered(
+        conn: &Arc<SafeConnection>,
+        status: Option<i32>,
+        type_: Option<i32>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<NotificationRecord>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT * FROM notification_records
+            WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR type = ?2)
+            AND (fire_at IS NULL OR fire_at <= ?3)
+            AND (expire_at IS NULL OR expire_at > ?3)
+            ORDER BY COALESCE(fire_at, created_at) DESC, id
+            LIMIT ?4 OFFSET ?5",
+        )?;
+
+        let notifications = stmt
+            .query_map(
+                params![status, type_, Utc::now(), limit, offset],
+                NotificationRecord::from_row,
+            )?
+            .collect();
+
+        notifications
+    }
+
+    // 与 get_filtered 条件相同的通知总数
+    pub fn count_filtered(
+        conn: &Arc<SafeConnection>,
+        status: Option<i32>,
+        type_: Option<i32>,
+    ) -> Result<i64> {
+        let conn = conn.conn.read().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM notification_records
+            WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR type = ?2)
+            AND (fire_at IS NULL OR fire_at <= ?3)
+            AND (expire_at IS NULL OR expire_at > ?3)",
+            params![status, type_, Utc::now()],
+            |row| row.get(0),
+        )
+    }
+
+    // 按类型统计当前可见（已生效且未过期）的通知总数和未读数
+    pub fn summary_by_type(conn: &Arc<SafeConnection>) -> Result<Vec<NotificationTypeSummary>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT type, COUNT(*), SUM(CASE WHEN status = 0 THEN 1 ELSE 0 END)
+            FROM notification_records
+            WHERE (fire_at IS NULL OR fire_at <= ?1)
+            AND (expire_at IS NULL OR expire_at > ?1)
+            GROUP BY type",
+        )?;
+        let counts: HashMap<i32, (i64, i64)> = stmt
+            .query_map(params![Utc::now()], |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
             })?
+            .collect::<Result<_>>()?;
+
+        let summary = [
+            NotificationType::System,
+            NotificationType::Reminder,
+            NotificationType::Custom,
+        ]
+        .into_iter()
+        .map(|notification_type| {
+            let (total, unread) = counts
+                .get(&(notification_type as i32))
+                .copied()
+                .unwrap_or((0, 0));
+            NotificationTypeSummary {
+                notification_type,
+                total,
+                unread,
+            }
+        })
+        .collect();
+
+        Ok(summary)
+    }
+
+    // 获取 since 之后生效的未读通知，定时通知以触发时间为准
+    pub fn get_recent_unread(
+        conn: &Arc<SafeConnection>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<NotificationRecord>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM notification_records
+            WHERE status = 0
+            AND COALESCE(fire_at, created_at) > ?1
+            AND (fire_at IS NULL OR fire_at <= ?2)
+            AND (expire_at IS NULL OR expire_at > ?2)
+            ORDER BY COALESCE(fire_at, created_at)",
+        )?;
+
+        let notifications = stmt
+            .query_map(params![since, Utc::now()], NotificationRecord::from_row)?
             .collect();
 
         notifications
     }
 
-    pub fn mark_as_read(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
+    // 删除 now 之前已过期的通知，返回删除的数量
+    pub fn purge_expired(conn: &Arc<SafeConnection>, now: DateTime<Utc>) -> Result<usize> {
         let conn = conn.conn.write().unwrap();
         conn.execute(
+            "DELETE FROM notification_records WHERE expire_at IS NOT NULL AND expire_at <= ?1",
+            params![now],
+        )
+    }
+
+    pub fn mark_as_read(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
+        let updated = conn.conn.write().unwrap().execute(
             "UPDATE notification_records
             SET status = ?1, read_at = ?2
             WHERE id = ?3",
             params![NotificationStatus::Read as i32, Utc::now(), id],
         )?;
+        if updated > 0 {
+            conn.notify(NotificationChange::Read {
+                id: Some(id),
+                type_: None,
+                related_task_id: None,
+            });
+        }
         Ok(())
     }
     pub fn mark_as_read_by_type(conn: &Arc<SafeConnection>, type_: i32) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
-        conn.execute(
+        let updated = conn.conn.write().unwrap().execute(
             "UPDATE notification_records SET status = ?1, read_at = ?2 WHERE type = ?3",
             params![NotificationStatus::Read as i32, Utc::now(), type_],
         )?;
+        if updated > 0 {
+            conn.notify(NotificationChange::Read {
+                id: None,
+                type_: Some(type_),
+                related_task_id: None,
+            });
+        }
         Ok(())
     }
     pub fn mark_all_as_read(conn: &Arc<SafeConnection>) -> Result<()> {
-        let conn = conn.conn.write().unwrap();
-        conn.execute(
+        let updated = conn.conn.write().unwrap().execute(
             "UPDATE notification_records
             SET status = ?1, read_at = ?2
             WHERE status = ?3",
@@ -832,33 +3935,45 @@ impl NotificationRecord {
                 NotificationStatus::Unread as i32
             ],
         )?;
+        if updated > 0 {
+            conn.notify(NotificationChange::Read {
+                id: None,
+                type_: None,
+                related_task_id: None,
+            });
+        }
         Ok(())
     }
 
+    // 将关联到某个任务的未读通知全部标记为已读，例如任务完成时清除它的提醒，返回更新的条数
+    pub fn mark_as_read_by_task(conn: &Arc<SafeConnection>, task_id: &str) -> Result<usize> {
+        let updated = conn.conn.write().unwrap().execute(
+            "UPDATE notification_records
+            SET status = ?1, read_at = ?2
+            WHERE related_task_id = ?3 AND status = ?4",
+            params![
+                NotificationStatus::Read as i32,
+                Utc::now(),
+                task_id,
+                NotificationStatus::Unread as i32
+            ],
+        )?;
+        if updated > 0 {
+            conn.notify(NotificationChange::Read {
+                id: None,
+                type_: None,
+                related_task_id: Some(task_id),
+            });
+        }
+        Ok(updated)
+    }
+
     pub fn get_by_id(conn: &Arc<SafeConnection>, id: &str) -> Result<Option<NotificationRecord>> {
         let conn = conn.conn.read().unwrap();
         let mut stmt = conn.prepare("SELECT * FROM notification_records WHERE id = ?1")?;
 
         let notification = stmt
-            .query_row(params![id], |row| {
-                Ok(NotificationRecord {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    content: row.get(2)?,
-                    type_: row.get(3)?,
-                    status: row.get(4)?,
-                    related_task_id: row.get(5)?,
-                    created_at: row.get(6)?,
-                    read_at: row.get(7)?,
-                    expire_at: row.get(8)?,
-                    action_url: row.get(9)?,
-                    reserved_1: row.get(10)?,
-                    reserved_2: row.get(11)?,
-                    reserved_3: row.get(12)?,
-                    reserved_4: row.get(13)?,
-                    reserved_5: row.get(14)?,
-                })
-            })
+            .query_row(params![id], NotificationRecord::from_row)
             .optional()?;
 
         Ok(notification)
@@ -879,8 +3994,9 @@ impl NotificationRecord {
                 reserved_2 = ?9,
                 reserved_3 = ?10,
                 reserved_4 = ?11,
-                reserved_5 = ?12
-            WHERE id = ?13",
+                reserved_5 = ?12,
+                fire_at = ?13
+            WHERE id = ?14",
             params![
                 self.title,
                 self.content,
@@ -894,6 +4010,7 @@ impl NotificationRecord {
                 self.reserved_3,
                 self.reserved_4,
                 self.reserved_5,
+                self.fire_at,
                 self.id
             ],
         )?;
@@ -908,4 +4025,2494 @@ impl NotificationRecord {
         )?;
         Ok(())
     }
+
+    // 按类型批量删除通知，only_read 为 true 时只删除已读通知，返回删除的条数
+    pub fn delete_by_type(
+        conn: &Arc<SafeConnection>,
+        type_: NotificationType,
+        only_read: bool,
+    ) -> Result<usize> {
+        let conn = conn.conn.write().unwrap();
+        let count = if only_read {
+            conn.execute(
+                "DELETE FROM notification_records WHERE type = ?1 AND status = ?2",
+                params![type_ as i32, NotificationStatus::Read as i32],
+            )?
+        } else {
+            conn.execute(
+                "DELETE FROM notification_records WHERE type = ?1",
+                params![type_ as i32],
+            )?
+        };
+        Ok(count)
+    }
+
+    // 创建一条在 fire_at 时刻才生效的自定义通知
+    pub fn schedule(
+        conn: &Arc<SafeConnection>,
+        title: &str,
+        content: &str,
+        fire_at: DateTime<Utc>,
+    ) -> Result<NotificationRecord> {
+        let notification = NotificationRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            type_: NotificationType::Custom as i32,
+            status: NotificationStatus::Unread as i32,
+            related_task_id: None,
+            created_at: Utc::now(),
+            read_at: None,
+            expire_at: None,
+            action_url: None,
+            reserved_1: None,
+            reserved_2: None,
+            reserved_3: None,
+            reserved_4: None,
+            reserved_5: None,
+            fire_at: Some(fire_at),
+        };
+        NotificationRecord::create(conn, &notification)?;
+        Ok(notification)
+    }
+
+    // 获取触发时间落在 (after, until] 区间内的未读定时通知
+    pub fn get_due(
+        conn: &Arc<SafeConnection>,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<NotificationRecord>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM notification_records
+            WHERE status = 0 AND fire_at > ?1 AND fire_at <= ?2
+            ORDER BY fire_at",
+        )?;
+
+        let notifications = stmt
+            .query_map(params![after, until], NotificationRecord::from_row)?
+            .collect();
+
+        notifications
+    }
+
+    // 获取 at 之后才会触发的未读定时通知
+    pub fn get_scheduled(
+        conn: &Arc<SafeConnection>,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<NotificationRecord>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM notification_records
+            WHERE status = 0 AND fire_at > ?1
+            ORDER BY fire_at",
+        )?;
+
+        let notifications = stmt
+            .query_map(params![at], NotificationRecord::from_row)?
+            .collect();
+
+        notifications
+    }
+
+    // 在触发之前取消一条定时通知，已经触发或不存在时返回 NotFound
+    pub fn cancel_scheduled(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+    ) -> std::result::Result<(), DbError> {
+        let conn = conn.conn.write().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM notification_records
+            WHERE id = ?1 AND status = 0 AND fire_at > ?2",
+            params![id, Utc::now()],
+        )?;
+        if deleted == 0 {
+            return Err(DbError::NotFound(format!("Scheduled notification {}", id)));
+        }
+        Ok(())
+    }
+
+    // 为 (now, now + 提前量] 内开始的事项生成提醒通知，每个事项只提醒一次，已开始的事项不提醒。
+    // 提醒立即触发（fire_at = now），提前量为 0 或无效时不生成
+    pub fn schedule_matter_reminders(
+        conn: &Arc<SafeConnection>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<NotificationRecord>> {
+        let lead_minutes: i64 = KVStore::get(conn, REMINDER_LEAD_MINUTES_KEY, "10")?
+            .parse()
+            .unwrap_or(0);
+        if lead_minutes <= 0 {
+            return Ok(Vec::new());
+        }
+
+        // 查询和写入在同一个事务中，手动扫描与调度器同时执行时也不会重复提醒
+        let reminders = conn.with_transaction(|tx| -> Result<Vec<NotificationRecord>> {
+            let matters = {
+                let mut stmt = tx.prepare_cached(
+                    "SELECT * FROM matter
+                    WHERE deleted_at IS NULL AND start_time > ?1 AND start_time <= ?2
+                    AND NOT EXISTS (
+                        SELECT 1 FROM notification_records
+                        WHERE type = ?3 AND related_task_id = matter.id
+                    )
+                    ORDER BY start_time",
+                )?;
+                let matters = stmt
+                    .query_map(
+                        params![
+                            now,
+                            now + chrono::Duration::minutes(lead_minutes),
+                            NotificationType::Reminder as i32
+                        ],
+                        Matter::from_row,
+                    )?
+                    .collect::<Result<Vec<Matter>>>()?;
+                matters
+            };
+
+            let mut reminders = Vec::new();
+            for matter in matters {
+                // 向上取整，避免显示“0 分钟后开始”
+                let minutes = ((matter.start_time - now).num_seconds() + 59) / 60;
+                let notification = NotificationRecord {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    title: matter.title,
+                    content: format!("将在 {} 分钟后开始", minutes),
+                    type_: NotificationType::Reminder as i32,
+                    status: NotificationStatus::Unread as i32,
+                    related_task_id: Some(matter.id),
+                    created_at: now,
+                    read_at: None,
+                    expire_at: None,
+                    action_url: None,
+                    reserved_1: None,
+                    reserved_2: None,
+                    reserved_3: None,
+                    reserved_4: None,
+                    reserved_5: None,
+                    fire_at: Some(now),
+                };
+                NotificationRecord::insert(tx, &notification)?;
+                reminders.push(notification);
+            }
+            Ok(reminders)
+        })?;
+
+        for reminder in &reminders {
+            conn.notify(NotificationChange::Created(reminder));
+        }
+        Ok(reminders)
+    }
+}
+
+impl RecurringNotification {
+    fn from_row(row: &rusqlite::Row) -> Result<RecurringNotification> {
+        Ok(RecurringNotification {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            repeat_time: row.get(3)?,
+            interval_minutes: row.get(4)?,
+            type_: row.get(5)?,
+            active: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }
+
+    fn validate(&self) -> std::result::Result<RepeatTime, DbError> {
+        if self.interval_minutes.is_some_and(|minutes| minutes <= 0) {
+            return Err(DbError::InvalidArgument(
+                "interval_minutes must be positive".to_string(),
+            ));
+        }
+        RepeatTime::parse(&self.repeat_time).map_err(DbError::InvalidArgument)
+    }
+
+    pub fn create(
+        conn: &Arc<SafeConnection>,
+        notification: &RecurringNotification,
+    ) -> std::result::Result<(), DbError> {
+        notification.validate()?;
+
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "INSERT INTO recurring_notification (
+                id, title, content, repeat_time, interval_minutes,
+                type, active, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                notification.id,
+                notification.title,
+                notification.content,
+                notification.repeat_time,
+                notification.interval_minutes,
+                notification.type_,
+                notification.active,
+                notification.created_at,
+                notification.updated_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_by_id(
+        conn: &Arc<SafeConnection>,
+        id: &str,
+    ) -> Result<Option<RecurringNotification>> {
+        let conn = conn.conn.read().unwrap();
+        conn.query_row(
+            "SELECT * FROM recurring_notification WHERE id = ?1",
+            params![id],
+            RecurringNotification::from_row,
+        )
+        .optional()
+    }
+
+    pub fn get_all(conn: &Arc<SafeConnection>) -> Result<Vec<RecurringNotification>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare("SELECT * FROM recurring_notification ORDER BY created_at")?;
+        let notifications = stmt
+            .query_map([], RecurringNotification::from_row)?
+            .collect();
+        notifications
+    }
+
+    pub fn get_active(conn: &Arc<SafeConnection>) -> Result<Vec<RecurringNotification>> {
+        let conn = conn.conn.read().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM recurring_notification WHERE active = 1 ORDER BY created_at",
+        )?;
+        let notifications = stmt
+            .query_map([], RecurringNotification::from_row)?
+            .collect();
+        notifications
+    }
+
+    pub fn update(&self, conn: &Arc<SafeConnection>) -> std::result::Result<(), DbError> {
+        self.validate()?;
+
+        let conn = conn.conn.write().unwrap();
+        let updated = conn.execute(
+            "UPDATE recurring_notification SET
+                title = ?1,
+                content = ?2,
+                repeat_time = ?3,
+                interval_minutes = ?4,
+                type = ?5,
+                active = ?6,
+                updated_at = ?7
+            WHERE id = ?8",
+            params![
+                self.title,
+                self.content,
+                self.repeat_time,
+                self.interval_minutes,
+                self.type_,
+                self.active,
+                self.updated_at,
+                self.id
+            ],
+        )?;
+        if updated == 0 {
+            return Err(DbError::NotFound(format!(
+                "Recurring notification {}",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn delete(conn: &Arc<SafeConnection>, id: &str) -> Result<()> {
+        let conn = conn.conn.write().unwrap();
+        conn.execute(
+            "DELETE FROM recurring_notification WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    // after 之后的下一次提醒时间，按 timezone 的本地时间计算；repeat_time 无效时返回 None
+    pub fn next_fire_after<T: TimeZone>(
+        &self,
+        after: DateTime<Utc>,
+        timezone: &T,
+    ) -> Option<DateTime<Utc>> {
+        let repeat_time = self.validate().ok()?;
+        let step = chrono::Duration::minutes(self.interval_minutes.unwrap_or(0));
+
+        let first_day = after.with_timezone(timezone).date_naive();
+        // 至少选择了一天，所以八天之内必然有下一次提醒
+        for day in first_day.iter_days().take(8) {
+            if !repeat_time.includes(day.weekday()) {
+                continue;
+            }
+            let mut time = day.and_time(repeat_time.start);
+            let end = day.and_time(repeat_time.end);
+            while time <= end {
+                // 夏令时跳过的本地时间不提醒
+                if let Some(fire_at) = timezone.from_local_datetime(&time).earliest() {
+                    let fire_at = fire_at.with_timezone(&Utc);
+                    if fire_at > after {
+                        return Some(fire_at);
+                    }
+                }
+                if step.is_zero() {
+                    break;
+                }
+                time += step;
+            }
+        }
+        None
+    }
+
+    // 为 (after, until] 内到期的提醒生成一条定时通知，错过多次时只补一条
+    pub fn schedule_due<T: TimeZone>(
+        conn: &Arc<SafeConnection>,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        timezone: &T,
+    ) -> Result<Vec<NotificationRecord>> {
+        let mut scheduled = Vec::new();
+        for recurring in RecurringNotification::get_active(conn)? {
+            let Some(fire_at) = recurring.next_fire_after(after, timezone) else {
+                continue;
+            };
+            if fire_at > until {
+                continue;
+            }
+            let notification = NotificationRecord {
+                id: uuid::Uuid::new_v4().to_string(),
+                title: recurring.title,
+                content: recurring.content,
+                type_: recurring.type_,
+                status: NotificationStatus::Unread as i32,
+                related_task_id: Some(recurring.id),
+                created_at: Utc::now(),
+                read_at: None,
+                expire_at: None,
+                action_url: None,
+                reserved_1: None,
+                reserved_2: None,
+                reserved_3: None,
+                reserved_4: None,
+                reserved_5: None,
+                fire_at: Some(fire_at),
+            };
+            NotificationRecord::create(conn, &notification)?;
+            scheduled.push(notification);
+        }
+        Ok(scheduled)
+    }
+}
+
+// 用户数据所在的表，设置、分组等配置类数据不算在内
+const USER_DATA_TABLES: [&str; 5] = [
+    "matter",
+    "tags",
+    "repeat_task",
+    "todo",
+    "notification_records",
+];
+
+// 数据库中是否已有事项、标签、周期任务、待办或通知
+pub fn has_user_data(conn: &Arc<SafeConnection>) -> Result<bool> {
+    let conn = conn.conn.read().unwrap();
+    for table in USER_DATA_TABLES {
+        let exists: bool = conn.query_row(
+            &format!("SELECT EXISTS(SELECT 1 FROM {})", table),
+            [],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// 全文索引的检查结果，indexed 为已建立索引的未删除事项数
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SearchIndexStatus {
+    pub matters: i64,
+    pub indexed: i64,
+    pub consistent: bool,
+}
+
+// 从 matter 表重建全文索引，用于绕过触发器直接写入数据之后，返回索引的行数。
+// matter_fts 自己保存内容，先重新填充内容，再用 rebuild 重建倒排索引
+pub fn rebuild_search_index(conn: &Arc<SafeConnection>) -> Result<i64> {
+    conn.with_transaction(|tx| {
+        tx.execute_batch(
+            "DELETE FROM matter_fts;
+            INSERT INTO matter_fts (id, title, description, tags)
+            SELECT id, title, description, tags FROM matter;
+            INSERT INTO matter_fts (matter_fts) VALUES ('rebuild');",
+        )?;
+        tx.query_row("SELECT COUNT(*) FROM matter_fts", [], |row| row.get(0))
+    })
+}
+
+// 对比全文索引与未删除事项的数量，不一致时需要 rebuild_search_index
+pub fn verify_search_index(conn: &Arc<SafeConnection>) -> Result<SearchIndexStatus> {
+    let conn = conn.conn.read().unwrap();
+    let matters: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM matter WHERE deleted_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    let indexed: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM matter_fts
+        JOIN matter ON matter.id = matter_fts.id
+        WHERE matter.deleted_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(SearchIndexStatus {
+        matters,
+        indexed,
+        consistent: matters == indexed,
+    })
+}
+
+// 扫描事项、任务和通知中由时钟错误导致的时间异常，auto_correct 时修正能明确判断的记录：
+// 默认值取另一个时间，未来的创建时间改为当前时间，更新时间早于创建时间时改为创建时间
+pub fn detect_time_anomalies(
+    conn: &Arc<SafeConnection>,
+    auto_correct: bool,
+) -> Result<Vec<TimeAnomaly>> {
+    let now = Utc::now();
+    let future_limit = now + chrono::Duration::seconds(FUTURE_TIMESTAMP_TOLERANCE_SECONDS);
+    conn.with_transaction(|tx| {
+        let mut anomalies = Vec::new();
+        for (table, has_updated_at) in TIME_ANOMALY_TABLES {
+            let updated_column = if has_updated_at { "updated_at" } else { "NULL" };
+            let rows: Vec<(String, DateTime<Utc>, Option<DateTime<Utc>>)> = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT id, created_at, {} FROM {}",
+                    updated_column, table
+                ))?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+                rows.collect::<Result<_>>()?
+            };
+
+            for (id, created_at, updated_at) in rows {
+                let mut kinds = Vec::new();
+                let mut fixable = true;
+                let (mut created, mut updated) = (created_at, updated_at);
+
+                let created_is_default = check_is_default_datetime(created);
+                if created_is_default || updated.is_some_and(check_is_default_datetime) {
+                    kinds.push(TimeAnomalyKind::DefaultTimestamp);
+                    match updated {
+                        Some(u) if created_is_default && !check_is_default_datetime(u) => {
+                            created = u
+                        }
+                        Some(_) if !created_is_default => updated = Some(created),
+                        _ => fixable = false,
+                    }
+                }
+                if created > future_limit {
+                    kinds.push(TimeAnomalyKind::FutureCreatedAt);
+                    created = now;
+                    updated = updated.map(|_| now);
+                }
+                if updated.is_some_and(|u| u < created) {
+                    kinds.push(TimeAnomalyKind::UpdatedBeforeCreated);
+                    updated = Some(created);
+                }
+                if kinds.is_empty() {
+                    continue;
+                }
+
+                let corrected = auto_correct && fixable;
+                if corrected {
+                    match updated {
+                        Some(updated) => tx.execute(
+                            &format!(
+                                "UPDATE {} SET created_at = ?1, updated_at = ?2 WHERE id = ?3",
+                                table
+                            ),
+                            params![created, updated, id],
+                        )?,
+                        None => tx.execute(
+                            &format!("UPDATE {} SET created_at = ?1 WHERE id = ?2", table),
+                            params![created, id],
+                        )?,
+                    };
+                }
+                anomalies.push(TimeAnomaly {
+                    entity: table.to_string(),
+                    id,
+                    kinds,
+                    created_at,
+                    updated_at,
+                    corrected,
+                });
+            }
+        }
+        Ok(anomalies)
+    })
+}
+
+// 将所有数据以 MessagePack 格式导出到文件，比 JSON 更小更快，用于自动备份，返回导出的行数
+pub fn export_binary(
+    conn: &Arc<SafeConnection>,
+    path: &Path,
+) -> std::result::Result<usize, DbError> {
+    let dumps = {
+        let conn = conn.conn.read().unwrap();
+        let mut dumps = Vec::new();
+        for table in BINARY_EXPORT_TABLES {
+            let columns = table_columns(&conn, table)?;
+            let mut stmt = conn.prepare(&format!("SELECT * FROM {}", table))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    (0..columns.len())
+                        .map(|index| Ok(row.get::<_, rusqlite::types::Value>(index)?.into()))
+                        .collect()
+                })?
+                .collect::<Result<Vec<Vec<CellValue>>>>()?;
+            dumps.push(TableDump {
+                table: table.to_string(),
+                columns,
+                rows,
+            });
+        }
+        dumps
+    };
+
+    let payload = rmp_serde::to_vec(&dumps)
+        .map_err(|e| DbError::InvalidArgument(format!("Failed to encode export: {}", e)))?;
+    let mut file = File::create(path)?;
+    file.write_all(BINARY_EXPORT_MAGIC)?;
+    file.write_all(&BINARY_EXPORT_VERSION.to_le_bytes())?;
+    file.write_all(&payload)?;
+
+    Ok(dumps.iter().map(|dump| dump.rows.len()).sum())
+}
+
+// 开启自动备份时，在数据库目录的 backups 中为即将执行的 operation 创建一份二进制备份
+pub fn auto_backup(
+    conn: &Arc<SafeConnection>,
+    operation: &str,
+) -> std::result::Result<Option<PathBuf>, DbError> {
+    if KVStore::get(conn, AUTO_BACKUP_KEY, "true")? == "false" {
+        return Ok(None);
+    }
+
+    let db_path = conn
+        .conn
+        .read()
+        .unwrap()
+        .path()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            DbError::InvalidArgument("Database has no file to back up next to".to_string())
+        })?;
+    let dir = db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(AUTO_BACKUP_DIR);
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!(
+        "before-{}-{}.bin",
+        operation,
+        Utc::now().format("%Y%m%d%H%M%S%3f")
+    ));
+    export_binary(conn, &path)?;
+    log::info!("Backed up data to {} before {}", path.display(), operation);
+
+    Ok(Some(path))
+}
+
+// 先自动备份再执行破坏性操作，备份失败时不执行操作
+pub fn with_auto_backup<T, E>(
+    conn: &Arc<SafeConnection>,
+    operation: &str,
+    run: impl FnOnce() -> std::result::Result<T, E>,
+) -> std::result::Result<BackedUp<T>, DbError>
+where
+    DbError: From<E>,
+{
+    let backup_path = auto_backup(conn, operation)?;
+    let result = run()?;
+
+    Ok(BackedUp {
+        result,
+        backup_path: backup_path.map(|path| path.display().to_string()),
+    })
+}
+
+// 用 export_binary 导出的文件替换当前数据，返回恢复的行数
+pub fn import_binary(
+    conn: &Arc<SafeConnection>,
+    path: &Path,
+) -> std::result::Result<usize, DbError> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    let header_len = BINARY_EXPORT_MAGIC.len() + 4;
+    if bytes.len() < header_len || &bytes[..BINARY_EXPORT_MAGIC.len()] != BINARY_EXPORT_MAGIC {
+        return Err(DbError::InvalidArgument(
+            "Not a binary export file".to_string(),
+        ));
+    }
+    let mut version = [0u8; 4];
+    version.copy_from_slice(&bytes[BINARY_EXPORT_MAGIC.len()..header_len]);
+    let version = u32::from_le_bytes(version);
+    if version > BINARY_EXPORT_VERSION {
+        return Err(DbError::InvalidArgument(format!(
+            "Unsupported binary export version {}",
+            version
+        )));
+    }
+    let dumps: Vec<TableDump> = rmp_serde::from_slice(&bytes[header_len..])
+        .map_err(|e| DbError::InvalidArgument(format!("Invalid binary export: {}", e)))?;
+
+    conn.with_transaction(|tx| {
+        let mut restored = 0;
+        for dump in dumps {
+            if !BINARY_EXPORT_TABLES.contains(&dump.table.as_str()) {
+                return Err(DbError::InvalidArgument(format!(
+                    "Unknown table {}",
+                    dump.table
+                )));
+            }
+            tx.execute(&format!("DELETE FROM {}", dump.table), [])?;
+
+            // 只写入本地表中存在的列
+            let local_columns = table_columns(tx, &dump.table)?;
+            let indexes: Vec<usize> = (0..dump.columns.len())
+                .filter(|index| local_columns.contains(&dump.columns[*index]))
+                .collect();
+            let columns: Vec<&str> = indexes
+                .iter()
+                .map(|index| dump.columns[*index].as_str())
+                .collect();
+            let placeholders: Vec<String> =
+                (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+            let mut stmt = tx.prepare(&format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                dump.table,
+                columns.join(", "),
+                placeholders.join(", ")
+            ))?;
+
+            for row in dump.rows {
+                let row: Vec<rusqlite::types::Value> = row.into_iter().map(Into::into).collect();
+                let values: Vec<rusqlite::types::Value> = indexes
+                    .iter()
+                    .map(|index| {
+                        row.get(*index)
+                            .cloned()
+                            .unwrap_or(rusqlite::types::Value::Null)
+                    })
+                    .collect();
+                stmt.execute(rusqlite::params_from_iter(values))?;
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    })
+}
+
+/// 使用 SQLite 在线备份 API 将整个数据库复制到 dest，应用运行中也能得到一致的副本。
+/// dest 已存在时会被覆盖
+pub fn backup_database(
+    conn: &Arc<SafeConnection>,
+    dest: &Path,
+) -> std::result::Result<(), DbError> {
+    let conn = conn.conn.read().unwrap();
+    conn.backup(rusqlite::DatabaseName::Main, dest, None)?;
+    Ok(())
+}
+
+// 检查 path 是否为本应用的数据库：版本号在支持范围内，且包含所有数据表
+fn validate_database_file(path: &Path) -> std::result::Result<(), DbError> {
+    let invalid = |reason: String| {
+        DbError::InvalidArgument(format!(
+            "{} is not a Fates database: {}",
+            path.display(),
+            reason
+        ))
+    };
+    if !path.is_file() {
+        return Err(invalid("file not found".to_string()));
+    }
+    let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| invalid(e.to_string()))?;
+    let version: u32 = source
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| invalid(e.to_string()))?;
+    if version == 0 || version > CURRENT_DB_VERSION {
+        return Err(invalid(format!("unsupported version {}", version)));
+    }
+    let added_later = |table: &str| {
+        MIGRATED_TABLES
+            .iter()
+            .any(|(name, since)| *name == table && *since > version)
+    };
+    for table in BINARY_EXPORT_TABLES
+        .into_iter()
+        .filter(|table| !added_later(table))
+    {
+        let exists: bool = source.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(invalid(format!("missing table {}", table)));
+        }
+    }
+    Ok(())
+}
+
+/// 用 backup_database 生成的数据库文件替换当前数据库，校验通过后才会改动当前数据。
+/// 替换后重新执行迁移并安装触发器和索引，相当于重新连接；调用方需要重新加载所有数据
+pub fn restore_database(
+    conn: &Arc<SafeConnection>,
+    src: &Path,
+) -> std::result::Result<(), DbError> {
+    validate_database_file(src)?;
+
+    let mut conn = conn.conn.write().unwrap();
+    conn.restore(
+        rusqlite::DatabaseName::Main,
+        src,
+        None::<fn(rusqlite::backup::Progress)>,
+    )?;
+    run_migrations(&mut conn)?;
+    install_change_triggers(&conn)?;
+    ensure_indexes(&conn)?;
+    log::info!("Restored database from {}", src.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn matter(id: &str, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Matter {
+        let now = Utc::now();
+        Matter {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: None,
+            tags: None,
+            start_time,
+            end_time,
+            priority: 0,
+            type_: 0,
+            created_at: now,
+            updated_at: now,
+            reserved_1: None,
+            reserved_2: None,
+            reserved_3: None,
+            reserved_4: None,
+            reserved_5: None,
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 2, hour, 0, 0).unwrap()
+    }
+
+    fn repeat_task(id: &str, status: i32) -> RepeatTask {
+        let now = Utc::now();
+        RepeatTask {
+            id: id.to_string(),
+            title: id.to_string(),
+            tags: None,
+            repeat_time: "2|09:00|10:00".to_string(),
+            status,
+            created_at: now,
+            updated_at: now,
+            priority: 0,
+            description: None,
+        }
+    }
+
+    fn todo(id: &str) -> Todo {
+        let now = Utc::now();
+        Todo {
+            id: id.to_string(),
+            title: id.to_string(),
+            status: "todo".to_string(),
+            created_at: now,
+            updated_at: now,
+            position: 0,
+        }
+    }
+
+    // 基准测试和多连接测试需要真实文件，内存数据库没有 WAL 和只读连接
+    fn temp_database() -> (PathBuf, Arc<SafeConnection>) {
+        let dir = std::env::temp_dir().join(format!("fates-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = open_database(&dir.join(DB_NAME)).unwrap();
+        (dir, db)
+    }
+
+    fn ids<'a>(tasks: impl IntoIterator<Item = &'a RepeatTask>) -> Vec<&'a str> {
+        let mut ids: Vec<&str> = tasks.into_iter().map(|task| task.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    fn notification(id: &str, type_: NotificationType, status: i32) -> NotificationRecord {
+        NotificationRecord {
+            id: id.to_string(),
+            title: id.to_string(),
+            content: id.to_string(),
+            type_: type_ as i32,
+            status,
+            related_task_id: None,
+            created_at: Utc::now(),
+            read_at: None,
+            expire_at: None,
+            action_url: None,
+            reserved_1: None,
+            reserved_2: None,
+            reserved_3: None,
+            reserved_4: None,
+            reserved_5: None,
+            fire_at: None,
+        }
+    }
+
+    fn tagged(id: &str, start_time: DateTime<Utc>, end_time: DateTime<Utc>, tags: &str) -> Matter {
+        Matter {
+            tags: Some(tags.to_string()),
+            ..matter(id, start_time, end_time)
+        }
+    }
+
+    #[test]
+    fn only_one_matter_can_be_running() {
+        let db = open_in_memory();
+        let open = default_datetime();
+        Matter::create(&db, &matter("running", at(9), open)).unwrap();
+        Matter::create(&db, &matter("done", at(10), at(11))).unwrap();
+
+        let second = matter("second", at(12), open);
+        assert!(matches!(
+            Matter::create(&db, &second),
+            Err(DbError::Conflict(_))
+        ));
+        assert!(matches!(
+            Matter::create_batch(&db, &[second]),
+            Err(DbError::Conflict(_))
+        ));
+
+        let mut done = Matter::get_by_id(&db, "done").unwrap().unwrap();
+        let expected_updated_at = done.updated_at;
+        done.end_time = open;
+        assert!(matches!(
+            done.update(&db, expected_updated_at),
+            Err(DbError::Conflict(_))
+        ));
+
+        // 修改进行中的事项本身不受限制
+        let mut running = Matter::get_by_id(&db, "running").unwrap().unwrap();
+        let expected_updated_at = running.updated_at;
+        running.title = "renamed".to_string();
+        running.update(&db, expected_updated_at).unwrap();
+        assert_eq!(Matter::running_count(&db).unwrap(), 1);
+    }
+
+    #[test]
+    fn trashed_matters_are_ignored() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("kept", at(9), at(10))).unwrap();
+        Matter::create(&db, &matter("trashed", at(10), at(12))).unwrap();
+        Matter::delete(&db, "trashed").unwrap();
+
+        assert_eq!(Matter::completeness_report(&db).unwrap().total, 1);
+        assert_eq!(
+            Matter::time_since_last(&db, at(13)).unwrap(),
+            Some(3 * 3600)
+        );
+        let found = Matter::query_by_field(&db, "title", "trashed", true).unwrap();
+        assert!(found.is_empty());
+        assert_eq!(
+            Matter::tag_by_search(&db, "e", "work", false).unwrap(),
+            vec!["kept"]
+        );
+
+        let not_found =
+            |result: std::result::Result<_, DbError>| matches!(result, Err(DbError::NotFound(_)));
+        assert!(not_found(Matter::split(&db, "trashed", at(11)).map(|_| ())));
+        assert!(not_found(Matter::join(&db, "kept", "trashed").map(|_| ())));
+        let day = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        assert!(not_found(
+            Matter::move_to_day(&db, "trashed", day, chrono_tz::UTC).map(|_| ())
+        ));
+    }
+
+    #[test]
+    fn with_transaction_rolls_back_on_error() {
+        let db = open_in_memory();
+        let result: std::result::Result<(), DbError> = db.with_transaction(|tx| {
+            Matter::insert(tx, &matter("inserted", at(9), at(10)))?;
+            Err(DbError::InvalidArgument("abort".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(Matter::get_by_id(&db, "inserted").unwrap().is_none());
+
+        db.with_transaction(|tx| Matter::insert(tx, &matter("inserted", at(9), at(10))))
+            .unwrap();
+        assert!(Matter::get_by_id(&db, "inserted").unwrap().is_some());
+    }
+
+    #[test]
+    fn todo_can_be_read_back_by_id() {
+        let db = open_in_memory();
+        Todo::create(&db, &todo("first")).unwrap();
+
+        let found = Todo::get_by_id(&db, "first").unwrap().unwrap();
+        assert_eq!(found.title, "first");
+        assert_eq!(found.status, "todo");
+        assert!(Todo::get_by_id(&db, "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn migrations_upgrade_a_pre_versioning_database() {
+        // 迁移框架之前的表结构：没有 user_version，也缺少后来新增的列
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE matter (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT DEFAULT '',
+                tags TEXT DEFAULT '',
+                start_time DATETIME NOT NULL,
+                end_time DATETIME NOT NULL,
+                priority INTEGER DEFAULT 0,
+                type INTEGER DEFAULT 0,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                reserved_1 TEXT DEFAULT '',
+                reserved_2 TEXT DEFAULT '',
+                reserved_3 TEXT DEFAULT '',
+                reserved_4 TEXT DEFAULT '',
+                reserved_5 TEXT DEFAULT ''
+            );
+            CREATE TABLE kvstore (
+                key TEXT PRIMARY KEY,
+                value TEXT DEFAULT '',
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL
+            );
+            CREATE TABLE tags (
+                name TEXT PRIMARY KEY,
+                created_at DATETIME NOT NULL,
+                last_used_at DATETIME NOT NULL
+            );
+            CREATE TABLE todo (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                status TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL
+            );",
+        )
+        .unwrap();
+        Matter::insert(&conn, &matter("old", at(9), at(10))).unwrap();
+
+        run_migrations(&mut conn).unwrap();
+
+        let version: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, CURRENT_DB_VERSION);
+        for (table, column) in [
+            ("tags", "color"),
+            ("todo", "position"),
+            ("kvstore", "expires_at"),
+            ("matter", "deleted_at"),
+        ] {
+            assert!(table_columns(&conn, table)
+                .unwrap()
+                .contains(&column.to_string()));
+        }
+        let db = Arc::new(SafeConnection::new(conn));
+        assert!(Matter::get_by_id(&db, "old").unwrap().is_some());
+        // 已有事项在迁移时建立了全文索引
+        assert_eq!(Matter::search(&db, "old").unwrap().len(), 1);
+        assert!(RepeatTaskException::get_all(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn query_by_field_rejects_fields_outside_the_allowlist() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("kept", at(9), at(10))).unwrap();
+
+        for field in [
+            "title = title OR 1",
+            "id; DROP TABLE matter; --",
+            "deleted_at",
+            "TITLE",
+            "",
+        ] {
+            assert!(matches!(
+                Matter::query_by_field(&db, field, "x", true),
+                Err(DbError::InvalidArgument(_))
+            ));
+        }
+        assert_eq!(Matter::get_all(&db).unwrap().len(), 1);
+        assert_eq!(
+            Matter::query_by_field(&db, "title", "kep", false)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn stale_updates_are_rejected() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("matter", at(9), at(10))).unwrap();
+        RepeatTask::create(&db, &repeat_task("task", 1)).unwrap();
+        Todo::create(&db, &todo("todo")).unwrap();
+        let later = |time: DateTime<Utc>| time + chrono::Duration::seconds(1);
+
+        // 两个窗口读到同一版本，第一个保存成功后第二个返回 Conflict
+        let mut first = Matter::get_by_id(&db, "matter").unwrap().unwrap();
+        let read_at = first.updated_at;
+        first.title = "first".to_string();
+        first.updated_at = later(read_at);
+        first.update(&db, read_at).unwrap();
+        let mut second = matter("matter", at(9), at(10));
+        second.title = "second".to_string();
+        assert!(matches!(
+            second.update(&db, read_at),
+            Err(DbError::Conflict(_))
+        ));
+        assert_eq!(
+            Matter::get_by_id(&db, "matter").unwrap().unwrap().title,
+            "first"
+        );
+
+        let mut task = RepeatTask::get_by_id(&db, "task").unwrap().unwrap();
+        let read_at = task.updated_at;
+        task.updated_at = later(read_at);
+        task.update(&db, read_at).unwrap();
+        assert!(matches!(
+            task.update(&db, read_at),
+            Err(DbError::Conflict(_))
+        ));
+
+        let mut item = Todo::get_by_id(&db, "todo").unwrap().unwrap();
+        let read_at = item.updated_at;
+        item.updated_at = later(read_at);
+        item.update(&db, read_at).unwrap();
+        assert!(matches!(
+            item.update(&db, read_at),
+            Err(DbError::Conflict(_))
+        ));
+
+        // 不存在的记录返回 NotFound 而不是 Conflict
+        assert!(matches!(
+            matter("missing", at(9), at(10)).update(&db, read_at),
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn readers_are_not_blocked_by_an_open_write_transaction() {
+        let (dir, db) = temp_database();
+        assert_eq!(db.conn.readers.len(), READER_CONNECTIONS);
+        Matter::create(&db, &matter("committed", at(9), at(10))).unwrap();
+
+        db.with_transaction(|tx| {
+            Matter::insert(tx, &matter("pending", at(10), at(11)))?;
+            // 写连接被占用时，其他线程仍能从只读连接读到已提交的数据
+            let ids: Vec<String> = std::thread::scope(|scope| {
+                let readers: Vec<_> = (0..READER_CONNECTIONS * 2)
+                    .map(|_| scope.spawn(|| Matter::get_all(&db).unwrap()))
+                    .collect();
+                readers
+                    .into_iter()
+                    .flat_map(|reader| reader.join().unwrap())
+                    .map(|matter| matter.id)
+                    .collect()
+            });
+            assert!(ids.iter().all(|id| id == "committed"));
+            assert_eq!(ids.len(), READER_CONNECTIONS * 2);
+            Ok::<_, DbError>(())
+        })
+        .unwrap();
+        assert_eq!(Matter::get_all(&db).unwrap().len(), 2);
+
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn create_batch_is_faster_than_creating_one_by_one() {
+        let matters = |prefix: &str| -> Vec<Matter> {
+            (0..500)
+                .map(|i| {
+                    let start = at(0) + chrono::Duration::minutes(i);
+                    matter(
+                        &format!("{prefix}-{i}"),
+                        start,
+                        start + chrono::Duration::minutes(1),
+                    )
+                })
+                .collect()
+        };
+        // 两种方式各用一个新数据库，避免后一种在更大的表和索引上插入
+        let (single_dir, single_db) = temp_database();
+        let started = std::time::Instant::now();
+        for matter in &matters("single") {
+            Matter::create(&single_db, matter).unwrap();
+        }
+        let one_by_one = started.elapsed();
+
+        let (dir, db) = temp_database();
+        let started = std::time::Instant::now();
+        Matter::create_batch(&db, &matters("batch")).unwrap();
+        let batch = started.elapsed();
+
+        assert_eq!(Matter::get_all(&db).unwrap().len(), 500);
+        assert!(batch < one_by_one);
+
+        drop((single_db, db));
+        std::fs::remove_dir_all(&single_dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn cached_statements_speed_up_repeated_queries() {
+        let (dir, db) = temp_database();
+        let matters: Vec<Matter> = (0..24)
+            .map(|hour| {
+                let start = at(hour);
+                matter(
+                    &format!("m{hour}"),
+                    start,
+                    start + chrono::Duration::minutes(30),
+                )
+            })
+            .collect();
+        Matter::create_batch(&db, &matters).unwrap();
+
+        let conn = db.conn.read().unwrap();
+        let query = |stmt: &mut rusqlite::Statement, i: u32| {
+            let start = at(i % 22);
+            let end = start + chrono::Duration::hours(2);
+            let rows = stmt
+                .query_map(params![start, end], Matter::from_row)
+                .unwrap();
+            assert!(rows.count() > 0);
+        };
+
+        let started = std::time::Instant::now();
+        for i in 0..2000 {
+            query(&mut conn.prepare(MATTER_TIME_RANGE_SQL).unwrap(), i);
+        }
+        let prepared = started.elapsed();
+
+        let started = std::time::Instant::now();
+        for i in 0..2000 {
+            query(&mut conn.prepare_cached(MATTER_TIME_RANGE_SQL).unwrap(), i);
+        }
+        let cached = started.elapsed();
+
+        assert!(cached < prepared);
+
+        drop(conn);
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn repeat_tasks_are_partitioned_by_status() {
+        let db = open_in_memory();
+        for (id, status) in [("active", 1), ("stopped", 0), ("archived", -1)] {
+            RepeatTask::create(&db, &repeat_task(id, status)).unwrap();
+        }
+
+        for (status, expected) in [(1, "active"), (0, "stopped"), (-1, "archived")] {
+            let tasks = RepeatTask::get_by_status(&db, status).unwrap();
+            assert_eq!(ids(&tasks), vec![expected]);
+        }
+        assert!(RepeatTask::get_by_status(&db, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn archived_repeat_task_can_be_activated_again() {
+        let db = open_in_memory();
+        RepeatTask::create(&db, &repeat_task("task", 1)).unwrap();
+
+        RepeatTask::archive(&db, "task").unwrap();
+        assert_eq!(
+            ids(&RepeatTask::get_by_status(&db, -1).unwrap()),
+            vec!["task"]
+        );
+        assert!(RepeatTask::get_active_tasks(&db).unwrap().is_empty());
+
+        RepeatTask::activate(&db, "task").unwrap();
+        assert_eq!(
+            ids(&RepeatTask::get_active_tasks(&db).unwrap()),
+            vec!["task"]
+        );
+    }
+
+    #[test]
+    fn matter_time_ranges_are_validated() {
+        let db = open_in_memory();
+        let inverted = matter("inverted", at(10), at(9));
+        assert!(matches!(
+            Matter::create(&db, &inverted),
+            Err(DbError::InvalidArgument(_))
+        ));
+        let mut blank = matter("blank", at(9), at(10));
+        blank.title = "   ".to_string();
+        assert!(matches!(
+            Matter::create(&db, &blank),
+            Err(DbError::InvalidArgument(_))
+        ));
+        // 一批中有一条无效时整批都不写入
+        assert!(matches!(
+            Matter::create_batch(&db, &[matter("valid", at(9), at(10)), inverted]),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert!(Matter::get_by_id(&db, "valid").unwrap().is_none());
+
+        // 未结束的事项和起止相同的事项都允许
+        Matter::create(&db, &matter("open", at(9), default_datetime())).unwrap();
+        Matter::create(&db, &matter("instant", at(11), at(11))).unwrap();
+
+        let mut instant = Matter::get_by_id(&db, "instant").unwrap().unwrap();
+        let read_at = instant.updated_at;
+        instant.end_time = at(10);
+        assert!(matches!(
+            instant.update(&db, read_at),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert_eq!(
+            Matter::get_by_id(&db, "instant").unwrap().unwrap().end_time,
+            at(11)
+        );
+    }
+
+    #[test]
+    fn todo_status_transitions_follow_the_validator() {
+        let db = open_in_memory();
+        let cases = [
+            ("todo", "todo", true),
+            ("todo", "in_progress", true),
+            ("todo", "completed", false),
+            ("in_progress", "todo", true),
+            ("in_progress", "in_progress", true),
+            ("in_progress", "completed", true),
+            ("completed", "todo", false),
+            ("completed", "in_progress", true),
+            ("completed", "completed", true),
+        ];
+        let with_status = |id: &str, status: &str| Todo {
+            status: status.to_string(),
+            ..todo(id)
+        };
+        for (from, to, legal) in cases {
+            let id = format!("{from}-{to}");
+            Todo::create(&db, &with_status(&id, from)).unwrap();
+
+            let result = Todo::set_status(&db, &id, to);
+            let status = Todo::get_by_id(&db, &id).unwrap().unwrap().status;
+            if legal {
+                assert!(result.is_ok(), "{from} -> {to}: {result:?}");
+                assert_eq!(status, to);
+            } else {
+                match result {
+                    Err(DbError::InvalidTransition { from: f, to: t }) => {
+                        assert_eq!((f.as_str(), t.as_str()), (from, to))
+                    }
+                    other => panic!("{from} -> {to}: {other:?}"),
+                }
+                assert_eq!(status, from);
+            }
+        }
+
+        assert!(matches!(
+            Todo::set_status(&db, "todo-todo", "done"),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            Todo::set_status(&db, "missing", "todo"),
+            Err(DbError::NotFound(_))
+        ));
+        // 无法识别的旧状态可以改为任意合法状态
+        Todo::create(&db, &with_status("legacy", "blocked")).unwrap();
+        Todo::set_status(&db, "legacy", "completed").unwrap();
+    }
+
+    #[test]
+    fn repeat_task_with_malformed_repeat_time_cannot_be_activated() {
+        let db = open_in_memory();
+        RepeatTask::create(&db, &repeat_task("broken", 0)).unwrap();
+        // 直接写入损坏的 repeat_time，模拟旧数据
+        db.with_transaction(|tx| {
+            tx.execute(
+                "UPDATE repeat_task SET repeat_time = '0|25:00|xx' WHERE id = 'broken'",
+                [],
+            )
+        })
+        .unwrap();
+
+        assert!(matches!(
+            RepeatTask::activate(&db, "broken"),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            RepeatTask::update_status(&db, "broken", 1),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert_eq!(
+            RepeatTask::get_by_id(&db, "broken")
+                .unwrap()
+                .unwrap()
+                .status,
+            0
+        );
+        // 归档不需要校验
+        RepeatTask::archive(&db, "broken").unwrap();
+        assert!(matches!(
+            RepeatTask::activate(&db, "missing"),
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn notifications_are_deleted_by_type() {
+        let db = open_in_memory();
+        NotificationRecord::create(&db, &notification("unread", NotificationType::Reminder, 0))
+            .unwrap();
+        NotificationRecord::create(&db, &notification("read", NotificationType::Reminder, 1))
+            .unwrap();
+        NotificationRecord::create(&db, &notification("system", NotificationType::System, 1))
+            .unwrap();
+
+        // 只删除已读的
+        assert_eq!(
+            NotificationRecord::delete_by_type(&db, NotificationType::Reminder, true).unwrap(),
+            1
+        );
+        assert!(NotificationRecord::get_by_id(&db, "unread")
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            NotificationRecord::delete_by_type(&db, NotificationType::Reminder, false).unwrap(),
+            1
+        );
+        assert!(NotificationRecord::get_by_id(&db, "unread")
+            .unwrap()
+            .is_none());
+        assert!(NotificationRecord::get_by_id(&db, "system")
+            .unwrap()
+            .is_some());
+        assert!(NotificationType::try_from(9).is_err());
+    }
+
+    #[test]
+    fn matter_is_returned_with_local_time_fields() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("local", at(4), at(4) + Duration::minutes(90))).unwrap();
+
+        let local = Matter::get_with_local(&db, "local", chrono_tz::Asia::Shanghai)
+            .unwrap()
+            .unwrap();
+        assert_eq!(local.start_local, "2024-01-02 12:00:00");
+        assert_eq!(local.end_local, "2024-01-02 13:30:00");
+        assert_eq!(local.duration, "1h 30m");
+        assert!(Matter::get_with_local(&db, "missing", chrono_tz::UTC)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn scheduled_notifications_stay_hidden_until_due() {
+        let db = open_in_memory();
+        let now = Utc::now();
+        let scheduled =
+            NotificationRecord::schedule(&db, "later", "content", now + Duration::hours(1))
+                .unwrap();
+        NotificationRecord::create(&db, &notification("now", NotificationType::System, 0)).unwrap();
+
+        let unread = NotificationRecord::get_unread(&db).unwrap();
+        assert_eq!(unread.len(), 1);
+        assert_eq!(unread[0].id, "now");
+        let due = NotificationRecord::get_due(&db, now, now + Duration::hours(2)).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, scheduled.id);
+        assert_eq!(
+            NotificationRecord::get_by_id(&db, &scheduled.id)
+                .unwrap()
+                .unwrap()
+                .fire_at,
+            scheduled.fire_at
+        );
+    }
+
+    #[test]
+    fn tags_are_suggested_from_title_words() {
+        let db = open_in_memory();
+        for name in ["meeting", "team", "other"] {
+            Tag::create(&db, name).unwrap();
+        }
+        Matter::create(&db, &tagged("used", at(9), at(10), "meeting,x")).unwrap();
+
+        let suggested = Tag::suggest_for_title(&db, "Team meeting").unwrap();
+        let names: Vec<&str> = suggested.iter().map(|tag| tag.name.as_str()).collect();
+        assert_eq!(names, vec!["meeting", "team"]);
+        assert_eq!(suggested[0].usage_count, 1);
+        assert!(Tag::suggest_for_title(&db, "lunch").unwrap().is_empty());
+    }
+
+    #[test]
+    fn grouped_timeline_orders_groups_and_falls_back_to_default() {
+        let db = open_in_memory();
+        for (id, sort_order) in [("b", 2), ("a", 1)] {
+            let group = TimelineGroup {
+                id: id.to_string(),
+                content: id.to_uppercase(),
+                sort_order,
+            };
+            TimelineGroup::create(&db, &group).unwrap();
+        }
+        let mut grouped = tagged("grouped", at(9), at(10), "x,y");
+        grouped.reserved_3 = Some("b".to_string());
+        Matter::create(&db, &grouped).unwrap();
+        Matter::create(&db, &matter("ungrouped", at(10), at(11))).unwrap();
+
+        let timeline = Matter::get_grouped_timeline(&db, at(0), at(0) + Duration::days(1)).unwrap();
+        let groups: Vec<&str> = timeline.groups.iter().map(|g| g.id.as_str()).collect();
+        assert_eq!(groups, vec!["a", "b", DEFAULT_TIMELINE_GROUP]);
+        let item = |id: &str| timeline.items.iter().find(|item| item.id == id).unwrap();
+        assert_eq!(item("grouped").group, "b");
+        assert_eq!(item("grouped").tags, vec!["x", "y"]);
+        assert_eq!(item("ungrouped").group, DEFAULT_TIMELINE_GROUP);
+    }
+
+    #[test]
+    fn tag_palette_is_deterministic_and_honours_overrides() {
+        let db = open_in_memory();
+        Tag::create(&db, "work").unwrap();
+        Tag::create(&db, "red").unwrap();
+        Tag::set_color(&db, "red", Some("#ff0000")).unwrap();
+
+        let names = vec!["work".to_string(), "red".to_string(), "unknown".to_string()];
+        let palette = Tag::palette(&db, &names).unwrap();
+        assert_eq!(palette, Tag::palette(&db, &names).unwrap());
+        assert_eq!(palette["red"], "#ff0000");
+        assert_eq!(palette["work"], utils::color_for_name("work"));
+        assert_eq!(palette["unknown"], utils::color_for_name("unknown"));
+        assert!(utils::is_hex_color(&palette["work"]));
+    }
+
+    #[test]
+    fn matter_is_split_into_two_at_the_given_time() {
+        let db = open_in_memory();
+        Matter::create(&db, &tagged("whole", at(8), at(12), "a,b")).unwrap();
+
+        let (first, second) = Matter::split(&db, "whole", at(9)).unwrap();
+        assert_eq!((first.id.as_str(), first.end_time), ("whole", at(9)));
+        assert_eq!((second.start_time, second.end_time), (at(9), at(12)));
+        assert_eq!(second.tags.as_deref(), Some("a,b"));
+        assert_eq!(
+            Matter::get_by_id(&db, "whole").unwrap().unwrap().end_time,
+            at(9)
+        );
+        assert!(Matter::get_by_id(&db, &second.id).unwrap().is_some());
+
+        // 切分点必须在事项内部
+        assert!(matches!(
+            Matter::split(&db, "whole", at(11)),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            Matter::split(&db, "missing", at(9)),
+            Err(DbError::NotFound(_))
+        ));
+        assert_eq!(Matter::get_all(&db).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn adjacent_matters_are_joined() {
+        let db = open_in_memory();
+        Matter::create(&db, &tagged("first", at(8), at(9), "x,y")).unwrap();
+        Matter::create(&db, &tagged("second", at(9), at(10), "y,z")).unwrap();
+        Matter::create(&db, &matter("far", at(13), at(14))).unwrap();
+
+        assert!(matches!(
+            Matter::join(&db, "first", "far"),
+            Err(DbError::InvalidArgument(_))
+        ));
+        let joined = Matter::join(&db, "first", "second").unwrap();
+        assert_eq!(joined.tags.as_deref(), Some("x,y,z"));
+        assert_eq!((joined.start_time, joined.end_time), (at(8), at(10)));
+        assert!(Matter::get_by_id(&db, "second").unwrap().is_none());
+    }
+
+    #[test]
+    fn recent_unread_notifications_are_replayed() {
+        let db = open_in_memory();
+        let mut old = notification("old", NotificationType::System, 0);
+        old.created_at = Utc::now() - Duration::hours(3);
+        NotificationRecord::create(&db, &old).unwrap();
+        NotificationRecord::create(&db, &notification("new", NotificationType::System, 0)).unwrap();
+        NotificationRecord::create(&db, &notification("read", NotificationType::System, 1))
+            .unwrap();
+
+        let recent =
+            NotificationRecord::get_recent_unread(&db, Utc::now() - Duration::hours(1)).unwrap();
+        let ids: Vec<&str> = recent.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["new"]);
+    }
+
+    #[test]
+    fn repeat_tasks_are_imported_with_per_item_errors() {
+        let db = open_in_memory();
+        let json = r#"[
+            {"title": "valid", "repeat_time": "62|09:00|09:30"},
+            {"id": "no-days", "title": "b", "repeat_time": "0|09:00|10:00"},
+            {"id": "backwards", "title": "c", "repeat_time": "127|10:00|09:00"},
+            {"title": "no repeat time"}
+        ]"#;
+
+        let dry_run = RepeatTask::import(&db, json, true).unwrap();
+        assert_eq!(dry_run.imported.len(), 1);
+        let failed: Vec<usize> = dry_run.errors.iter().map(|e| e.index).collect();
+        assert_eq!(failed, vec![1, 2, 3]);
+        assert!(RepeatTask::get_all(&db).unwrap().is_empty());
+
+        let report = RepeatTask::import(&db, json, false).unwrap();
+        let tasks = RepeatTask::get_all(&db).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(
+            (tasks[0].id.as_str(), tasks[0].status),
+            (report.imported[0].as_str(), 1)
+        );
+        // 已存在的 id 作为单条错误返回
+        let again = RepeatTask::import(
+            &db,
+            &format!(
+                r#"[{{"id": "{}", "title": "a", "repeat_time": "62|09:00|09:30"}}]"#,
+                tasks[0].id
+            ),
+            false,
+        )
+        .unwrap();
+        assert_eq!(again.errors.len(), 1);
+        assert!(RepeatTask::import(&db, "{", false).is_err());
+    }
+
+    #[test]
+    fn matter_defaults_fill_unset_priority_and_type() {
+        let db = open_in_memory();
+        KVStore::set(&db, DEFAULT_MATTER_PRIORITY_KEY, "3").unwrap();
+        KVStore::set(&db, DEFAULT_MATTER_TYPE_KEY, "2").unwrap();
+
+        let draft: MatterDraft = serde_json::from_str(r#"{"title": "a", "priority": 1}"#).unwrap();
+        let created = Matter::create_with_defaults(&db, draft).unwrap();
+        let stored = Matter::get_by_id(&db, &created.id).unwrap().unwrap();
+        assert_eq!((stored.priority, stored.type_), (1, 2));
+    }
+
+    #[test]
+    fn matters_are_tagged_by_title_search() {
+        let db = open_in_memory();
+        let titled = |id: &str, title: &str, tags: &str| Matter {
+            title: title.to_string(),
+            ..tagged(id, at(9), at(10), tags)
+        };
+        Matter::create(&db, &titled("standup", "Daily Standup", "work")).unwrap();
+        Matter::create(&db, &titled("tagged", "standup 2", "meeting")).unwrap();
+        Matter::create(&db, &titled("lunch", "lunch", "")).unwrap();
+
+        // 预览不修改数据，已有该标签的事项不重复添加
+        assert_eq!(
+            Matter::tag_by_search(&db, "STANDUP", "meeting", true).unwrap(),
+            vec!["standup"]
+        );
+        let tags = |id: &str| Matter::get_by_id(&db, id).unwrap().unwrap().tags;
+        assert_eq!(tags("standup").as_deref(), Some("work"));
+        assert_eq!(
+            Matter::tag_by_search(&db, "standup", "meeting", false).unwrap(),
+            vec!["standup"]
+        );
+        assert_eq!(tags("standup").as_deref(), Some("work,meeting"));
+        assert!(Tag::get_all(&db)
+            .unwrap()
+            .iter()
+            .any(|tag| tag.name == "meeting"));
+    }
+
+    #[test]
+    fn overlapping_matters_get_separate_lanes() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("a", at(1), at(4))).unwrap();
+        Matter::create(&db, &matter("b", at(2), at(4))).unwrap();
+        Matter::create(&db, &matter("c", at(3), at(5))).unwrap();
+        // a 结束后 d 复用第一条车道
+        Matter::create(&db, &matter("d", at(4), at(6))).unwrap();
+        Matter::create(
+            &db,
+            &matter(
+                "next-day",
+                at(1) + Duration::days(1),
+                at(2) + Duration::days(1),
+            ),
+        )
+        .unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let layout = Matter::day_layout(&db, day, chrono_tz::UTC).unwrap();
+        let lanes: Vec<(&str, usize)> = layout
+            .matters
+            .iter()
+            .map(|m| (m.matter.id.as_str(), m.lane))
+            .collect();
+        assert_eq!(lanes, vec![("a", 0), ("b", 1), ("c", 2), ("d", 0)]);
+        assert_eq!(layout.lane_count, 3);
+    }
+
+    #[test]
+    fn time_since_last_activity_ignores_future_matters() {
+        let db = open_in_memory();
+        let now = at(12);
+        assert_eq!(Matter::time_since_last(&db, now).unwrap(), None);
+        Matter::create(&db, &matter("a", at(7), at(9))).unwrap();
+        Matter::create(&db, &matter("b", at(8), now - Duration::minutes(90))).unwrap();
+        Matter::create(&db, &matter("future", at(13), at(14))).unwrap();
+        assert_eq!(Matter::time_since_last(&db, now).unwrap(), Some(90 * 60));
+
+        // 正在进行的事项从开始时间算起
+        let ongoing = matter("ongoing", now - Duration::minutes(10), at(14));
+        Matter::create(&db, &ongoing).unwrap();
+        assert_eq!(Matter::time_since_last(&db, now).unwrap(), Some(10 * 60));
+    }
+
+    #[test]
+    fn checklist_lines_become_todos_in_order() {
+        let db = open_in_memory();
+        let text = "- [ ] milk\n\n  * eggs \n-\n[ ]\nplain line\n- [x] done one\n";
+        assert_eq!(Todo::import_checklist(&db, text).unwrap().len(), 4);
+
+        let mut todos = Todo::get_all(&db).unwrap();
+        todos.sort_by_key(|todo| todo.position);
+        let titles: Vec<(&str, i64)> = todos
+            .iter()
+            .map(|todo| (todo.title.as_str(), todo.position))
+            .collect();
+        let expected = vec![("milk", 0), ("eggs", 1), ("plain line", 2), ("done one", 3)];
+        assert_eq!(titles, expected);
+        assert!(todos.iter().all(|todo| todo.status == "todo"));
+
+        // 再次导入时追加到列表末尾
+        Todo::import_checklist(&db, "more").unwrap();
+        let more = Todo::get_all(&db)
+            .unwrap()
+            .into_iter()
+            .find(|t| t.title == "more");
+        assert_eq!(more.unwrap().position, 4);
+    }
+
+    #[test]
+    fn moved_matter_keeps_local_time_across_dst() {
+        let db = open_in_memory();
+        let new_york = chrono_tz::America::New_York;
+        let start = new_york
+            .with_ymd_and_hms(2024, 3, 8, 9, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        Matter::create(&db, &matter("a", start, start + Duration::hours(2))).unwrap();
+        Matter::create(&db, &matter("open", start, default_datetime())).unwrap();
+
+        // 3 月 10 日开始夏令时，本地时间不变时 UTC 时间提前一小时
+        let day = NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let moved = Matter::move_to_day(&db, "a", day, new_york).unwrap();
+        let local_start = moved.start_time.with_timezone(&new_york);
+        assert_eq!(
+            local_start.format("%Y-%m-%d %H:%M").to_string(),
+            "2024-03-12 09:30"
+        );
+        assert_eq!(moved.end_time - moved.start_time, Duration::hours(2));
+        assert_eq!(
+            moved.start_time - start,
+            Duration::days(4) - Duration::hours(1)
+        );
+        // 未结束的事项移动后仍未结束
+        let open = Matter::move_to_day(&db, "open", day, new_york).unwrap();
+        assert!(open.is_open_ended());
+        assert!(matches!(
+            Matter::move_to_day(&db, "missing", day, new_york),
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn change_log_export_replays_into_another_database() {
+        let db = open_in_memory();
+        for i in 0..50 {
+            Matter::create(&db, &tagged(&format!("m{i}"), at(9), at(10), "work")).unwrap();
+        }
+        let mut renamed = Matter::get_by_id(&db, "m1").unwrap().unwrap();
+        renamed.title = "renamed".to_string();
+        renamed.update(&db, renamed.updated_at).unwrap();
+        Matter::delete(&db, "m2").unwrap();
+        Tag::create(&db, "work").unwrap();
+
+        let entries = ChangeLogEntry::get_since(&db, 0).unwrap();
+        assert_eq!(entries.len(), 53);
+        assert_eq!(entries[50].operation, "update");
+        let payload = ChangeLogEntry::export_since(&db, 0).unwrap();
+        // 同一结构的记录重复很多，压缩后应明显变小
+        assert!(payload.len() * 4 < serde_json::to_vec(&entries).unwrap().len());
+
+        let replica = open_in_memory();
+        assert_eq!(ChangeLogEntry::import(&replica, &payload).unwrap(), 53);
+        assert_eq!(Matter::get_all(&replica).unwrap().len(), 49);
+        assert_eq!(
+            Matter::get_by_id(&replica, "m1").unwrap().unwrap().title,
+            "renamed"
+        );
+        assert_eq!(Matter::get_trashed(&replica).unwrap()[0].matter.id, "m2");
+        assert_eq!(Tag::get_all(&replica).unwrap().len(), 1);
+        assert!(ChangeLogEntry::import(&replica, b"junk").is_err());
+    }
+
+    #[test]
+    fn week_grid_splits_matters_at_local_midnight() {
+        let db = open_in_memory();
+        let shanghai = chrono_tz::Asia::Shanghai;
+        let night = shanghai
+            .with_ymd_and_hms(2024, 1, 2, 22, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        Matter::create(&db, &matter("night", night, night + Duration::hours(4))).unwrap();
+        let day = night - Duration::hours(3);
+        Matter::create(&db, &matter("day", day, day + Duration::hours(1))).unwrap();
+
+        let week = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let grid = Matter::week_grid(&db, week, shanghai).unwrap();
+        assert_eq!(grid.len(), 7);
+        assert!(grid[0].segments.is_empty());
+        assert_eq!(grid[1].segments.len(), 2);
+        assert_eq!(grid[1].total_seconds, 3 * 3600);
+        assert_eq!(grid[1].segments[1].end_local, "2024-01-03 00:00:00");
+        let carried = &grid[2].segments[0];
+        assert_eq!(carried.matter.matter.id, "night");
+        assert_eq!(carried.start_local, "2024-01-03 00:00:00");
+        assert_eq!(carried.end_local, "2024-01-03 02:00:00");
+        assert_eq!(grid[2].total_seconds, 2 * 3600);
+    }
+
+    #[test]
+    fn unique_repeat_titles_are_enforced_when_enabled() {
+        let db = open_in_memory();
+        let titled = |id: &str, title: &str| RepeatTask {
+            title: title.to_string(),
+            ..repeat_task(id, 1)
+        };
+        RepeatTask::create(&db, &titled("a", "Standup")).unwrap();
+        RepeatTask::create(&db, &titled("b", "standup ")).unwrap();
+        let duplicates = RepeatTask::find_duplicate_titles(&db).unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].ids, vec!["a", "b"]);
+
+        KVStore::set(&db, UNIQUE_REPEAT_TITLES_KEY, "true").unwrap();
+        assert!(matches!(
+            RepeatTask::create(&db, &titled("c", "STANDUP")),
+            Err(DbError::Conflict(_))
+        ));
+        RepeatTask::create(&db, &titled("d", "Gym")).unwrap();
+        KVStore::set(&db, UNIQUE_REPEAT_TITLES_KEY, "false").unwrap();
+        RepeatTask::create(&db, &titled("e", "gym")).unwrap();
+    }
+
+    #[test]
+    fn unfinished_todos_from_earlier_days_are_carried_over() {
+        let db = open_in_memory();
+        let two_days_ago = Utc::now() - Duration::days(2);
+        let dated = |id: &str, status: &str, at: DateTime<Utc>, position: i64| Todo {
+            status: status.to_string(),
+            created_at: at,
+            updated_at: at,
+            position,
+            ..todo(id)
+        };
+        Todo::create(&db, &dated("stale", "in_progress", two_days_ago, 5)).unwrap();
+        Todo::create(&db, &dated("done", "completed", two_days_ago, 1)).unwrap();
+        Todo::create(&db, &dated("fresh", "todo", Utc::now(), 0)).unwrap();
+        let shanghai = chrono_tz::Asia::Shanghai;
+
+        let preview = Todo::carry_over(&db, shanghai, false).unwrap();
+        let ids: Vec<&str> = preview.iter().map(|todo| todo.id.as_str()).collect();
+        assert_eq!(ids, vec!["stale"]);
+        // 移到列表最前面后今天不再需要顺延
+        let moved = Todo::carry_over(&db, shanghai, true).unwrap();
+        assert_eq!(moved[0].position, -1);
+        assert!(Todo::carry_over(&db, shanghai, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn overlapping_matters_are_flagged_in_range() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("a", at(2), at(5))).unwrap();
+        Matter::create(&db, &matter("b", at(3), at(4))).unwrap();
+        // 首尾相接不算冲突
+        Matter::create(&db, &matter("c", at(5), at(6))).unwrap();
+
+        let range = Matter::get_range_with_conflicts(&db, at(0), at(23)).unwrap();
+        let flags: Vec<(&str, bool)> = range
+            .iter()
+            .map(|m| (m.matter.id.as_str(), m.has_conflict))
+            .collect();
+        assert_eq!(flags, vec![("a", true), ("b", true), ("c", false)]);
+    }
+
+    #[test]
+    fn missing_indexes_are_recreated() {
+        let db = open_in_memory();
+        let conn = db.conn.write().unwrap();
+        let index_count = || -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        let expected = index_count();
+        assert!(expected >= INDEXES.len() as i64);
+
+        conn.execute(&format!("DROP INDEX {}", INDEXES[0].0), [])
+            .unwrap();
+        assert_eq!(index_count(), expected - 1);
+        ensure_indexes(&conn).unwrap();
+        assert_eq!(index_count(), expected);
+    }
+
+    #[test]
+    fn references_to_deleted_tags_are_cleaned_up() {
+        let db = open_in_memory();
+        Tag::create(&db, "work").unwrap();
+        Tag::create(&db, "old").unwrap();
+        Matter::create(&db, &tagged("a", at(9), at(10), "work,old")).unwrap();
+        Matter::create(&db, &tagged("b", at(10), at(11), "work")).unwrap();
+        let task = RepeatTask {
+            tags: Some("old".to_string()),
+            ..repeat_task("r", 1)
+        };
+        RepeatTask::create(&db, &task).unwrap();
+        Tag::delete(&db, "old").unwrap();
+
+        // 预览只报告，不修改
+        assert_eq!(Tag::cleanup_orphan_refs(&db, true).unwrap().len(), 2);
+        let tags = |id: &str| Matter::get_by_id(&db, id).unwrap().unwrap().tags;
+        assert_eq!(tags("a").as_deref(), Some("work,old"));
+
+        let cleaned = Tag::cleanup_orphan_refs(&db, false).unwrap();
+        assert_eq!(cleaned[0].entity, "matter");
+        assert_eq!(cleaned[0].orphan_tags, vec!["old"]);
+        assert_eq!(tags("a").as_deref(), Some("work"));
+        let task = RepeatTask::get_by_id(&db, "r").unwrap().unwrap();
+        assert_eq!(task.tags.as_deref(), Some(""));
+        assert!(Tag::cleanup_orphan_refs(&db, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn changes_can_be_undone_and_redone() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("a", at(9), at(10))).unwrap();
+        Matter::create(&db, &matter("b", at(10), at(11))).unwrap();
+        let mut a = Matter::get_by_id(&db, "a").unwrap().unwrap();
+        a.title = "changed".to_string();
+        a.update(&db, a.updated_at).unwrap();
+        Matter::delete(&db, "b").unwrap();
+        let title = |id: &str| Matter::get_by_id(&db, id).unwrap().map(|m| m.title);
+
+        // 软删除记录为 update
+        let undone = ChangeLogEntry::undo_last(&db).unwrap().unwrap();
+        assert_eq!(undone.operation, "update");
+        assert_eq!(Matter::get_all(&db).unwrap().len(), 2);
+        ChangeLogEntry::undo_last(&db).unwrap();
+        assert_eq!(title("a").as_deref(), Some("a"));
+        ChangeLogEntry::undo_last(&db).unwrap();
+        assert!(title("b").is_none());
+
+        let redone = ChangeLogEntry::redo_last(&db).unwrap().unwrap();
+        assert_eq!(redone.operation, "insert");
+        assert!(title("b").is_some());
+        ChangeLogEntry::redo_last(&db).unwrap();
+        assert_eq!(title("a").as_deref(), Some("changed"));
+
+        // 新的修改会清空重做栈
+        Matter::create(&db, &matter("z", at(11), at(12))).unwrap();
+        assert!(ChangeLogEntry::redo_last(&db).unwrap().is_none());
+    }
+
+    #[test]
+    fn matter_is_created_from_local_date_and_times() {
+        let db = open_in_memory();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let create = |title: &str, start: &str, end: &str| {
+            let start = utils::parse_time_of_day(start).unwrap();
+            let end = utils::parse_time_of_day(end).unwrap();
+            Matter::create_from_local(&db, title, day, start, end, chrono_tz::Asia::Shanghai, None)
+        };
+        let utc = |hour| Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap();
+
+        let morning = create("morning", "09:00", "10:30:00").unwrap();
+        assert_eq!(morning.start_time, utc(1));
+        assert_eq!(morning.end_time - morning.start_time, Duration::minutes(90));
+        // 结束时间早于开始时间时视为次日
+        let night = create("night", "22:00", "02:00").unwrap();
+        assert_eq!((night.start_time, night.end_time), (utc(14), utc(18)));
+        assert!(Matter::get_by_id(&db, &night.id).unwrap().is_some());
+        assert!(matches!(
+            create("empty", "09:00", "09:00"),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert!(utils::parse_time_of_day("9am").is_err());
+    }
+
+    #[test]
+    fn notifications_are_summarized_by_type() {
+        let db = open_in_memory();
+        for (id, status) in [("a", 0), ("b", 1), ("c", 0)] {
+            let reminder = notification(id, NotificationType::Reminder, status);
+            NotificationRecord::create(&db, &reminder).unwrap();
+        }
+        NotificationRecord::create(&db, &notification("s", NotificationType::System, 1)).unwrap();
+        // 已过期的不计入
+        let mut expired = notification("e", NotificationType::Custom, 0);
+        expired.expire_at = Some(Utc::now() - Duration::hours(1));
+        NotificationRecord::create(&db, &expired).unwrap();
+
+        let summary = NotificationRecord::summary_by_type(&db).unwrap();
+        let counts: Vec<(i64, i64)> = summary.iter().map(|s| (s.total, s.unread)).collect();
+        assert_eq!(counts, vec![(1, 0), (3, 2), (0, 0)]);
+        assert_eq!(
+            serde_json::to_value(&summary[1]).unwrap()["notification_type"],
+            "Reminder"
+        );
+    }
+
+    #[test]
+    fn completeness_report_counts_missing_fields() {
+        let db = open_in_memory();
+        let epoch = default_datetime();
+        let complete = Matter {
+            description: Some("desc".to_string()),
+            ..tagged("complete", at(9), at(10), "w")
+        };
+        Matter::create(&db, &complete).unwrap();
+        Matter::create(&db, &tagged("open", at(9), epoch, "")).unwrap();
+        let blank = Matter {
+            description: Some("  ".to_string()),
+            ..tagged("blank", epoch, at(10), " , ")
+        };
+        Matter::create(&db, &blank).unwrap();
+        Matter::create(&db, &matter("untagged", at(10), at(11))).unwrap();
+
+        let report = Matter::completeness_report(&db).unwrap();
+        assert_eq!(report.total, 4);
+        assert_eq!((report.missing_tags, report.missing_description), (3, 3));
+        assert_eq!((report.default_timestamps, report.open_ended), (1, 1));
+    }
+
+    #[test]
+    fn settings_snapshot_restores_user_keys_only() {
+        let db = open_in_memory();
+        KVStore::set(&db, "theme", "dark").unwrap();
+        KVStore::set(&db, NOTIFICATION_LAST_SEEN_KEY, "t0").unwrap();
+        let snapshot = KVStore::snapshot_settings(&db).unwrap();
+        assert!(!snapshot.contains(NOTIFICATION_LAST_SEEN_KEY));
+
+        KVStore::set(&db, "theme", "light").unwrap();
+        KVStore::set(&db, "extra", "1").unwrap();
+        KVStore::set(&db, NOTIFICATION_LAST_SEEN_KEY, "t1").unwrap();
+        assert_eq!(KVStore::restore_settings(&db, &snapshot).unwrap(), 1);
+        assert_eq!(KVStore::get(&db, "theme", "").unwrap(), "dark");
+        assert_eq!(KVStore::get(&db, "extra", "none").unwrap(), "none");
+        // 运行状态不受恢复影响
+        assert_eq!(
+            KVStore::get(&db, NOTIFICATION_LAST_SEEN_KEY, "").unwrap(),
+            "t1"
+        );
+        assert!(KVStore::restore_settings(&db, "not json").is_err());
+
+        let keys = ["theme".to_string(), "missing".to_string()];
+        assert_eq!(KVStore::reset_settings_to_defaults(&db, &keys).unwrap(), 1);
+        assert_eq!(KVStore::get(&db, "theme", "default").unwrap(), "default");
+    }
+
+    #[test]
+    fn clock_skewed_timestamps_are_detected_and_corrected() {
+        let db = open_in_memory();
+        let stamped = |id: &str, created_at, updated_at| Matter {
+            created_at,
+            updated_at,
+            ..matter(id, at(9), at(10))
+        };
+        let future = Utc::now() + Duration::days(30);
+        Matter::create(&db, &stamped("backwards", at(9), at(8))).unwrap();
+        Matter::create(&db, &stamped("default", default_datetime(), at(9))).unwrap();
+        Matter::create(&db, &stamped("future", future, future)).unwrap();
+        Matter::create(&db, &stamped("ok", at(9), at(9))).unwrap();
+        // 两个时间都是默认值时无法推断，只报告不修正
+        let task = RepeatTask {
+            created_at: default_datetime(),
+            updated_at: default_datetime(),
+            ..repeat_task("task", 1)
+        };
+        RepeatTask::create(&db, &task).unwrap();
+
+        let found = detect_time_anomalies(&db, false).unwrap();
+        let kinds = |id: &str| found.iter().find(|a| a.id == id).map(|a| a.kinds.clone());
+        use TimeAnomalyKind::*;
+        assert_eq!(kinds("backwards"), Some(vec![UpdatedBeforeCreated]));
+        assert_eq!(kinds("default"), Some(vec![DefaultTimestamp]));
+        assert_eq!(kinds("future"), Some(vec![FutureCreatedAt]));
+        assert_eq!(kinds("task"), Some(vec![DefaultTimestamp]));
+        assert_eq!(kinds("ok"), None);
+        assert!(found.iter().all(|a| !a.corrected));
+
+        let fixed = detect_time_anomalies(&db, true).unwrap();
+        assert_eq!(fixed.iter().filter(|a| a.corrected).count(), 3);
+        let left = detect_time_anomalies(&db, false).unwrap();
+        assert_eq!(
+            left.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(),
+            vec!["task"]
+        );
+        let stored = |id: &str| Matter::get_by_id(&db, id).unwrap().unwrap();
+        assert_eq!(stored("default").created_at, at(9));
+        assert_eq!(stored("backwards").updated_at, at(9));
+    }
+
+    #[test]
+    fn binary_export_round_trips_all_tables() {
+        let db = open_in_memory();
+        let described = Matter {
+            description: Some("d".to_string()),
+            ..tagged("m", at(9), at(10), "w,x")
+        };
+        Matter::create(&db, &described).unwrap();
+        RepeatTask::create(&db, &repeat_task("r", 1)).unwrap();
+        KVStore::set(&db, "theme", "dark").unwrap();
+        Tag::create(&db, "w").unwrap();
+        NotificationRecord::create(&db, &notification("n", NotificationType::Reminder, 0)).unwrap();
+        let snapshot = |db: &Arc<SafeConnection>| {
+            serde_json::json!({
+                "matters": Matter::get_all(db).unwrap(),
+                "repeat_tasks": RepeatTask::get_all(db).unwrap(),
+                "tags": Tag::get_all(db).unwrap(),
+                "theme": KVStore::get(db, "theme", "").unwrap(),
+                "notification": NotificationRecord::get_by_id(db, "n").unwrap(),
+            })
+        };
+        let path = std::env::temp_dir().join(format!("fates-test-{}.bin", uuid::Uuid::new_v4()));
+        assert_eq!(export_binary(&db, &path).unwrap(), 5);
+
+        // 导入会替换已有数据
+        let restored = open_in_memory();
+        Matter::create(&restored, &matter("other", at(11), at(12))).unwrap();
+        assert_eq!(import_binary(&restored, &path).unwrap(), 5);
+        assert_eq!(snapshot(&restored), snapshot(&db));
+        assert!(Matter::get_by_id(&restored, "other").unwrap().is_none());
+
+        std::fs::write(&path, b"nope").unwrap();
+        assert!(matches!(
+            import_binary(&restored, &path),
+            Err(DbError::InvalidArgument(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn recurring_notifications_fire_within_their_window() {
+        let db = open_in_memory();
+        let shanghai = chrono_tz::Asia::Shanghai;
+        let local = |day, hour, minute| {
+            shanghai
+                .with_ymd_and_hms(2024, 1, day, hour, minute, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        // 工作日 09:00-18:00 每小时一次，2024-01-01 是周一
+        let rule: RecurringNotification = serde_json::from_value(serde_json::json!({
+            "id": "water",
+            "title": "water",
+            "content": "drink",
+            "repeat_time": "62|09:00|18:00",
+            "interval_minutes": 60
+        }))
+        .unwrap();
+        assert_eq!(
+            (rule.type_, rule.active),
+            (NotificationType::Custom as i32, true)
+        );
+        let next = |after| rule.next_fire_after(after, &shanghai);
+        assert_eq!(next(local(1, 8, 0)), Some(local(1, 9, 0)));
+        assert_eq!(next(local(1, 9, 30)), Some(local(1, 10, 0)));
+        assert_eq!(next(local(1, 10, 0)), Some(local(1, 11, 0)));
+        assert_eq!(next(local(1, 18, 0)), Some(local(2, 9, 0)));
+        // 周五晚上之后跳到下周一
+        assert_eq!(next(local(5, 18, 30)), Some(local(8, 9, 0)));
+        let once = RecurringNotification {
+            interval_minutes: None,
+            ..rule.clone()
+        };
+        assert_eq!(
+            once.next_fire_after(local(1, 9, 30), &shanghai),
+            Some(local(2, 9, 0))
+        );
+
+        RecurringNotification::create(&db, &rule).unwrap();
+        let bad = RecurringNotification {
+            id: "bad".to_string(),
+            interval_minutes: Some(0),
+            ..rule.clone()
+        };
+        assert!(matches!(
+            RecurringNotification::create(&db, &bad),
+            Err(DbError::InvalidArgument(_))
+        ));
+
+        // 错过多次只补一条，同一窗口不会重复生成
+        let scheduled =
+            RecurringNotification::schedule_due(&db, local(1, 9, 30), local(1, 12, 5), &shanghai)
+                .unwrap();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].fire_at, Some(local(1, 10, 0)));
+        assert!(RecurringNotification::schedule_due(
+            &db,
+            local(1, 12, 5),
+            local(1, 12, 35),
+            &shanghai
+        )
+        .unwrap()
+        .is_empty());
+
+        let mut paused = RecurringNotification::get_by_id(&db, "water")
+            .unwrap()
+            .unwrap();
+        paused.active = false;
+        paused.update(&db).unwrap();
+        assert!(RecurringNotification::get_active(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn conflict_details_report_overlap_with_neighbours() {
+        let db = open_in_memory();
+        let half_past = |hour| at(hour) + Duration::minutes(30);
+        Matter::create(&db, &matter("partial", at(9), at(10))).unwrap();
+        // 首尾相接不算重叠
+        Matter::create(&db, &matter("touching", at(11), at(12))).unwrap();
+        Matter::create(&db, &matter("self", half_past(9), at(11))).unwrap();
+        let open = matter("open", at(9) + Duration::minutes(45), default_datetime());
+        Matter::create(&db, &open).unwrap();
+
+        let details = Matter::conflict_details(&db, half_past(9), at(11), Some("self")).unwrap();
+        assert_eq!(details.len(), 1);
+        let partial = &details[0];
+        assert_eq!(partial.matter.id, "partial");
+        assert_eq!(
+            (partial.overlap_start, partial.overlap_end),
+            (half_past(9), at(10))
+        );
+        assert_eq!(partial.overlap_seconds, 1800);
+        // 不排除自身时包含 self
+        let all = Matter::conflict_details(&db, half_past(9), at(11), None).unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn running_elapsed_counts_from_start() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("running", at(9), default_datetime())).unwrap();
+        Matter::create(&db, &matter("done", at(9), at(10))).unwrap();
+
+        let elapsed = |id: &str, now| Matter::running_elapsed(&db, id, now);
+        assert_eq!(
+            elapsed("running", at(9) + Duration::minutes(95)).unwrap(),
+            5700
+        );
+        // 时钟早于开始时间时不返回负数
+        assert_eq!(elapsed("running", at(8)).unwrap(), 0);
+        assert!(matches!(
+            elapsed("done", at(9)),
+            Err(DbError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            elapsed("missing", at(9)),
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn destructive_operations_are_backed_up_first() {
+        let (dir, db) = temp_database();
+        let backups = dir.join(AUTO_BACKUP_DIR);
+        // 空的导出文件，导入后会清空数据
+        let empty = dir.join("empty.bin");
+        export_binary(&db, &empty).unwrap();
+        Matter::create(&db, &matter("keep", at(9), at(10))).unwrap();
+
+        let done = with_auto_backup(&db, "import_binary", || import_binary(&db, &empty)).unwrap();
+        let backup = PathBuf::from(done.backup_path.unwrap());
+        assert!(backup.starts_with(&backups));
+        assert!(Matter::get_by_id(&db, "keep").unwrap().is_none());
+        import_binary(&db, &backup).unwrap();
+        assert!(Matter::get_by_id(&db, "keep").unwrap().is_some());
+
+        // 操作失败时仍保留备份
+        let missing = dir.join("missing.bin");
+        assert!(with_auto_backup(&db, "import_binary", || import_binary(&db, &missing)).is_err());
+        assert_eq!(std::fs::read_dir(&backups).unwrap().count(), 2);
+        // 关闭设置后不再备份
+        KVStore::set(&db, AUTO_BACKUP_KEY, "false").unwrap();
+        let done = with_auto_backup(&db, "import_binary", || import_binary(&db, &empty)).unwrap();
+        assert!(done.backup_path.is_none());
+        assert_eq!(std::fs::read_dir(&backups).unwrap().count(), 2);
+
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pending_scheduled_notifications_can_be_cancelled() {
+        let db = open_in_memory();
+        let now = Utc::now();
+        let later =
+            NotificationRecord::schedule(&db, "later", "c", now + Duration::hours(2)).unwrap();
+        let sooner =
+            NotificationRecord::schedule(&db, "sooner", "c", now + Duration::hours(1)).unwrap();
+        NotificationRecord::create(&db, &notification("plain", NotificationType::Custom, 0))
+            .unwrap();
+
+        let pending = NotificationRecord::get_scheduled(&db, now).unwrap();
+        let ids: Vec<&str> = pending.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec![sooner.id.as_str(), later.id.as_str()]);
+        NotificationRecord::cancel_scheduled(&db, &later.id).unwrap();
+        assert_eq!(
+            NotificationRecord::get_scheduled(&db, now).unwrap().len(),
+            1
+        );
+        // 已取消的和不是定时的通知都返回 NotFound
+        assert!(matches!(
+            NotificationRecord::cancel_scheduled(&db, &later.id),
+            Err(DbError::NotFound(_))
+        ));
+        assert!(matches!(
+            NotificationRecord::cancel_scheduled(&db, "plain"),
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn tag_delete_impact_counts_and_strips_references() {
+        let db = open_in_memory();
+        Tag::create(&db, "work").unwrap();
+        Matter::create(&db, &tagged("m1", at(9), at(10), "work,deep")).unwrap();
+        Matter::create(&db, &tagged("m2", at(10), at(11), " work ")).unwrap();
+        // 只匹配完整的标签名
+        Matter::create(&db, &tagged("m3", at(11), at(12), "workout")).unwrap();
+        let task = RepeatTask {
+            tags: Some("deep,work".to_string()),
+            ..repeat_task("r1", 1)
+        };
+        RepeatTask::create(&db, &task).unwrap();
+
+        let impact = Tag::delete_impact(&db, "work").unwrap();
+        assert_eq!((impact.matters, impact.repeat_tasks), (2, 1));
+        let deleted = Tag::delete_with_refs(&db, "work", true).unwrap();
+        assert_eq!((deleted.matters, deleted.repeat_tasks), (2, 1));
+        let tags = |id: &str| Matter::get_by_id(&db, id).unwrap().unwrap().tags;
+        assert_eq!(tags("m1").as_deref(), Some("deep"));
+        assert_eq!(tags("m3").as_deref(), Some("workout"));
+        assert_eq!(Tag::delete_impact(&db, "work").unwrap().matters, 0);
+        assert!(Tag::get_all(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn change_log_is_exported_as_audit_csv() {
+        let db = open_in_memory();
+        let before = Utc::now() - Duration::seconds(1);
+        let mut m = matter("m,1", at(9), at(10));
+        Matter::create(&db, &m).unwrap();
+        m.title = "renamed".to_string();
+        m.update(&db, m.updated_at).unwrap();
+        Matter::hard_delete(&db, "m,1").unwrap();
+        let after = Utc::now() + Duration::seconds(1);
+
+        let csv = export_changelog_csv(&db, before, after).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "seq,timestamp,entity,entity_id,operation");
+        let operations: Vec<&str> = lines[1..]
+            .iter()
+            .map(|line| line.rsplit(',').next().unwrap())
+            .collect();
+        assert_eq!(operations, vec!["insert", "update", "delete"]);
+        // 含逗号的 id 加引号
+        assert!(lines[1].ends_with(",matter,\"m,1\",insert"));
+        let empty = export_changelog_csv(&db, after, after + Duration::hours(1)).unwrap();
+        assert_eq!(empty.lines().count(), 1);
+    }
+
+    #[test]
+    fn approved_tag_list_is_imported_and_pruned() {
+        let db = open_in_memory();
+        for name in ["work", "stray", "used"] {
+            Tag::create(&db, name).unwrap();
+        }
+        Matter::create(&db, &tagged("m", at(9), at(10), "used")).unwrap();
+        let names: Vec<String> = ["work", " study ", "", "study"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let report = Tag::import(&db, &names, false).unwrap();
+        assert_eq!((report.added, report.kept, report.pruned), (1, 3, 0));
+        // 只删除不在列表中且没有被引用的标签
+        let report = Tag::import(&db, &names, true).unwrap();
+        assert_eq!((report.added, report.kept, report.pruned), (0, 3, 1));
+        let left: Vec<String> = Tag::get_all(&db)
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        assert_eq!(left, vec!["study", "used", "work"]);
+    }
+
+    #[test]
+    fn notifications_are_marked_read_by_related_task() {
+        let db = open_in_memory();
+        for (id, task, status) in [
+            ("a1", Some("task-a"), 0),
+            ("a2", Some("task-a"), 0),
+            ("a3", Some("task-a"), 1),
+            ("b1", Some("task-b"), 0),
+            ("none", None, 0),
+        ] {
+            let record = NotificationRecord {
+                related_task_id: task.map(String::from),
+                ..notification(id, NotificationType::Reminder, status)
+            };
+            NotificationRecord::create(&db, &record).unwrap();
+        }
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let sink = changes.clone();
+        db.set_notification_listener(move |change| {
+            sink.lock()
+                .unwrap()
+                .push(serde_json::to_value(change).unwrap())
+        });
+
+        assert_eq!(
+            NotificationRecord::mark_as_read_by_task(&db, "task-a").unwrap(),
+            2
+        );
+        assert_eq!(
+            NotificationRecord::mark_as_read_by_task(&db, "task-a").unwrap(),
+            0
+        );
+        assert_eq!(
+            NotificationRecord::mark_as_read_by_task(&db, "missing").unwrap(),
+            0
+        );
+        let stored = |id: &str| NotificationRecord::get_by_id(&db, id).unwrap().unwrap();
+        assert!(["a1", "a2", "a3"].iter().all(|id| stored(id).status == 1));
+        assert!(stored("a1").read_at.is_some());
+        assert_eq!((stored("b1").status, stored("none").status), (0, 0));
+        // 只有实际修改了记录时才发出一次变更
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![serde_json::json!({"id": null, "type_": null, "related_task_id": "task-a"})]
+        );
+    }
+
+    #[test]
+    fn search_index_is_rebuilt_after_writes_that_bypass_triggers() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("indexed", at(9), at(10))).unwrap();
+        Matter::create(&db, &matter("trashed", at(10), at(11))).unwrap();
+        Matter::delete(&db, "trashed").unwrap();
+        let status = verify_search_index(&db).unwrap();
+        assert_eq!(
+            (status.matters, status.indexed, status.consistent),
+            (1, 1, true)
+        );
+
+        // 模拟直接用 SQL 批量导入：没有触发器时新事项不会进入索引
+        db.conn
+            .write()
+            .unwrap()
+            .execute("DROP TRIGGER matter_fts_insert", [])
+            .unwrap();
+        let imported = Matter {
+            title: "bulk imported".to_string(),
+            ..matter("bulk", at(11), at(12))
+        };
+        Matter::create(&db, &imported).unwrap();
+        assert!(Matter::search(&db, "bulk").unwrap().is_empty());
+        assert!(!verify_search_index(&db).unwrap().consistent);
+
+        assert_eq!(rebuild_search_index(&db).unwrap(), 3);
+        let found = Matter::search(&db, "bulk").unwrap();
+        assert_eq!(
+            found.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(),
+            vec!["bulk"]
+        );
+        let status = verify_search_index(&db).unwrap();
+        assert_eq!(
+            (status.matters, status.indexed, status.consistent),
+            (2, 2, true)
+        );
+    }
+
+    #[test]
+    fn deleted_matters_can_be_restored_until_purged() {
+        let db = open_in_memory();
+        for id in ["old", "recent", "kept"] {
+            Matter::create(&db, &matter(id, at(9), at(10))).unwrap();
+        }
+        Matter::delete(&db, "old").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let cutoff = Utc::now();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        Matter::delete(&db, "recent").unwrap();
+        Matter::restore(&db, "recent").unwrap();
+        assert!(matches!(
+            Matter::restore(&db, "kept"),
+            Err(DbError::NotFound(_))
+        ));
+
+        // 只清理 cutoff 之前移入回收站的事项
+        Matter::delete(&db, "recent").unwrap();
+        let trashed = || Matter::get_trashed(&db).unwrap().len();
+        assert_eq!(trashed(), 2);
+        assert_eq!(Matter::purge_deleted(&db, at(0)).unwrap(), 0);
+        assert_eq!(Matter::purge_deleted(&db, cutoff).unwrap(), 1);
+        assert_eq!(trashed(), 1);
+        assert!(matches!(
+            Matter::restore(&db, "old"),
+            Err(DbError::NotFound(_))
+        ));
+
+        // 彻底删除不经过回收站
+        Matter::hard_delete(&db, "kept").unwrap();
+        assert_eq!(trashed(), 1);
+        assert!(Matter::get_by_id(&db, "kept").unwrap().is_none());
+    }
 }
@@ -0,0 +1,333 @@
+use crate::database::{
+    has_user_data, split_tags, DbError, KVStore, Matter, NotificationChange, NotificationRecord,
+    NotificationStatus, NotificationType, RepeatTask, SafeConnection, Tag, Todo,
+};
+use crate::utils;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+// 示例数据的 id 前缀，清除时只删除带这个前缀的记录
+const DEMO_ID_PREFIX: &str = "demo-";
+
+// 记录示例数据新建的标签，清除时只删除这些标签
+const DEMO_TAGS_KEY: &str = "demo_data_tags";
+
+// 生成最近多少天的事项
+const DEMO_DAYS: i64 = 14;
+
+// 一条示例事项：标题、标签、本地开始时间、本地结束时间、优先级
+type DemoMatter = (&'static str, &'static str, (u32, u32), (u32, u32), i32);
+
+const WORKDAY_MATTERS: [DemoMatter; 5] = [
+    ("Daily standup", "work,meeting", (9, 30), (9, 45), 1),
+    ("Feature development", "work,coding", (10, 0), (12, 0), 2),
+    ("Lunch", "life", (12, 0), (13, 0), 0),
+    ("Code review", "work,coding", (14, 0), (15, 30), 1),
+    ("Reading", "learning", (21, 0), (21, 45), 0),
+];
+
+const WEEKEND_MATTERS: [DemoMatter; 3] = [
+    ("Morning run", "exercise,health", (8, 0), (9, 0), 1),
+    ("Groceries", "life", (10, 30), (11, 30), 0),
+    ("Reading", "learning", (20, 0), (21, 0), 0),
+];
+
+// 周期任务：标题、标签、repeat_time、优先级
+const DEMO_REPEAT_TASKS: [(&str, &str, &str, i32); 3] = [
+    ("Morning run", "exercise,health", "65|08:00|09:00", 1),
+    ("Weekly planning", "work", "2|09:00|09:30", 2),
+    ("Stretch break", "health", "62|15:30|15:45", 0),
+];
+
+// 待办：标题、状态
+const DEMO_TODOS: [(&str, &str); 4] = [
+    ("Prepare sprint demo", "in_progress"),
+    ("Book dentist appointment", "todo"),
+    ("Renew passport", "todo"),
+    ("Read \"Deep Work\"", "completed"),
+];
+
+// 通知：标题、内容、类型、是否已读
+const DEMO_NOTIFICATIONS: [(&str, &str, NotificationType, bool); 2] = [
+    (
+        "Welcome to Fates",
+        "This is sample data. Clear it once you are ready to track your own time.",
+        NotificationType::System,
+        false,
+    ),
+    (
+        "Time for a stretch",
+        "Stand up and stretch for a few minutes.",
+        NotificationType::Reminder,
+        true,
+    ),
+];
+
+// 生成或清除的示例数据数量
+#[derive(Debug, Default, Serialize)]
+pub struct DemoDataReport {
+    pub matters: usize,
+    pub tags: usize,
+    pub repeat_tasks: usize,
+    pub todos: usize,
+    pub notifications: usize,
+}
+
+fn demo_id(kind: &str, suffix: impl std::fmt::Display) -> String {
+    format!("{}{}-{}", DEMO_ID_PREFIX, kind, suffix)
+}
+
+fn demo_time(day: NaiveDate, (hour, minute): (u32, u32), timezone: Tz) -> DateTime<Utc> {
+    let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default();
+    utils::local_to_utc(day.and_time(time), timezone)
+}
+
+// 最近 DEMO_DAYS 天的事项，工作日和周末按不同的安排生成
+fn demo_matters(today: NaiveDate, timezone: Tz) -> Vec<Matter> {
+    let mut matters = Vec::new();
+    for offset in (1..=DEMO_DAYS).rev() {
+        let day = today - Duration::days(offset);
+        let plan: &[DemoMatter] = match day.weekday() {
+            Weekday::Sat | Weekday::Sun => &WEEKEND_MATTERS,
+            _ => &WORKDAY_MATTERS,
+        };
+        for (index, (title, tags, start, end, priority)) in plan.iter().enumerate() {
+            let end_time = demo_time(day, *end, timezone);
+            matters.push(Matter {
+                id: demo_id("matter", format!("{}-{}", day, index)),
+                title: title.to_string(),
+                description: None,
+                tags: Some(tags.to_string()),
+                start_time: demo_time(day, *start, timezone),
+                end_time,
+                priority: *priority,
+                type_: 0,
+                created_at: end_time,
+                updated_at: end_time,
+                reserved_1: None,
+                reserved_2: None,
+                reserved_3: None,
+                reserved_4: None,
+                reserved_5: None,
+            });
+        }
+    }
+    matters
+}
+
+/// 生成最近两周的示例事项、标签、周期任务、待办和通知，用于新用户上手和测试。
+/// 数据库已有数据时返回 Conflict，force 为 true 时先清除旧的示例数据再生成。
+/// 所有数据在同一个事务中写入，失败时不会留下部分示例数据
+pub fn seed_demo_data(
+    conn: &Arc<SafeConnection>,
+    timezone: Tz,
+    force: bool,
+) -> Result<DemoDataReport, DbError> {
+    if force {
+        clear_demo_data(conn)?;
+    } else if has_user_data(conn)? {
+        return Err(DbError::Conflict(
+            "Database already has data, pass force to seed anyway".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    let today = now.with_timezone(&timezone).date_naive();
+    let matters = demo_matters(today, timezone);
+    let repeat_tasks: Vec<RepeatTask> = DEMO_REPEAT_TASKS
+        .iter()
+        .enumerate()
+        .map(|(index, (title, tags, repeat_time, priority))| RepeatTask {
+            id: demo_id("repeat-task", index),
+            title: title.to_string(),
+            tags: Some(tags.to_string()),
+            repeat_time: repeat_time.to_string(),
+            status: 1,
+            created_at: now,
+            updated_at: now,
+            priority: *priority,
+            description: None,
+        })
+        .collect();
+    let todos: Vec<Todo> = DEMO_TODOS
+        .iter()
+        .enumerate()
+        .map(|(index, (title, status))| Todo {
+            id: demo_id("todo", index),
+            title: title.to_string(),
+            status: status.to_string(),
+            created_at: now,
+            updated_at: now,
+            position: index as i64,
+        })
+        .collect();
+    let notifications: Vec<NotificationRecord> = DEMO_NOTIFICATIONS
+        .iter()
+        .enumerate()
+        .map(|(index, (title, content, type_, read))| {
+            let status = if *read {
+                NotificationStatus::Read
+            } else {
+                NotificationStatus::Unread
+            };
+            NotificationRecord {
+                id: demo_id("notification", index),
+                title: title.to_string(),
+                content: content.to_string(),
+                type_: *type_ as i32,
+                status: status as i32,
+                related_task_id: None,
+                created_at: now,
+                read_at: read.then_some(now),
+                expire_at: None,
+                action_url: None,
+                reserved_1: None,
+                reserved_2: None,
+                reserved_3: None,
+                reserved_4: None,
+                reserved_5: None,
+                fire_at: None,
+            }
+        })
+        .collect();
+    let tags: BTreeSet<&str> = WORKDAY_MATTERS
+        .iter()
+        .chain(WEEKEND_MATTERS.iter())
+        .map(|matter| matter.1)
+        .chain(DEMO_REPEAT_TASKS.iter().map(|task| task.1))
+        .flat_map(split_tags)
+        .collect();
+
+    let report = conn.with_transaction(|tx| -> Result<DemoDataReport, DbError> {
+        for matter in &matters {
+            Matter::insert(tx, matter)?;
+        }
+        for task in &repeat_tasks {
+            RepeatTask::insert(tx, task)?;
+        }
+        // 只记录原本不存在的标签，清除时不会删掉用户自己的标签
+        let mut created_tags = Vec::new();
+        for tag in &tags {
+            if Tag::insert_if_missing(tx, tag)? {
+                created_tags.push(*tag);
+            }
+        }
+        KVStore::upsert(tx, DEMO_TAGS_KEY, &created_tags.join(","))?;
+        for todo in &todos {
+            Todo::insert(tx, todo)?;
+        }
+        for notification in &notifications {
+            NotificationRecord::insert(tx, notification)?;
+        }
+
+        Ok(DemoDataReport {
+            matters: matters.len(),
+            tags: created_tags.len(),
+            repeat_tasks: repeat_tasks.len(),
+            todos: todos.len(),
+            notifications: notifications.len(),
+        })
+    })?;
+
+    for notification in &notifications {
+        conn.notify(NotificationChange::Created(notification));
+    }
+    Ok(report)
+}
+
+/// 清除 seed_demo_data 生成的数据，用户自己创建的记录不受影响。
+/// 示例标签仍被用户的事项或周期任务引用时保留
+pub fn clear_demo_data(conn: &Arc<SafeConnection>) -> Result<DemoDataReport, DbError> {
+    let is_demo = |id: &str| id.starts_with(DEMO_ID_PREFIX);
+    let mut report = DemoDataReport::default();
+
+    let trashed = Matter::get_trashed(conn)?
+        .into_iter()
+        .map(|trashed| trashed.matter);
+    for matter in Matter::get_all(conn)?.into_iter().chain(trashed) {
+        if is_demo(&matter.id) {
+            Matter::hard_delete(conn, &matter.id)?;
+            report.matters += 1;
+        }
+    }
+
+    for task in RepeatTask::get_all(conn)? {
+        if is_demo(&task.id) {
+            RepeatTask::delete(conn, &task.id)?;
+            report.repeat_tasks += 1;
+        }
+    }
+
+    for todo in Todo::get_all(conn)? {
+        if is_demo(&todo.id) {
+            Todo::delete(conn, &todo.id)?;
+            report.todos += 1;
+        }
+    }
+
+    for index in 0..DEMO_NOTIFICATIONS.len() {
+        let id = demo_id("notification", index);
+        if NotificationRecord::get_by_id(conn, &id)?.is_some() {
+            NotificationRecord::delete(conn, &id)?;
+            report.notifications += 1;
+        }
+    }
+
+    let tags = KVStore::get(conn, DEMO_TAGS_KEY, "")?;
+    for tag in split_tags(&tags) {
+        let impact = Tag::delete_impact(conn, tag)?;
+        if impact.matters == 0 && impact.repeat_tasks == 0 {
+            Tag::delete(conn, tag)?;
+            report.tags += 1;
+        }
+    }
+    KVStore::delete(conn, DEMO_TAGS_KEY)?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::open_in_memory;
+
+    #[test]
+    fn seed_then_clear_leaves_database_empty() {
+        let db = open_in_memory();
+        let report = seed_demo_data(&db, chrono_tz::UTC, false).unwrap();
+        assert_eq!(report.matters, Matter::get_all(&db).unwrap().len());
+        assert_eq!(report.tags, Tag::get_all(&db).unwrap().len());
+        assert!(matches!(
+            seed_demo_data(&db, chrono_tz::UTC, false),
+            Err(DbError::Conflict(_))
+        ));
+
+        let cleared = clear_demo_data(&db).unwrap();
+        assert_eq!(cleared.matters, report.matters);
+        assert_eq!(cleared.tags, report.tags);
+        assert_eq!(cleared.repeat_tasks, report.repeat_tasks);
+        assert_eq!(cleared.todos, report.todos);
+        assert_eq!(cleared.notifications, report.notifications);
+        assert!(!has_user_data(&db).unwrap());
+        assert!(Tag::get_all(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn clearing_keeps_user_tags() {
+        let db = open_in_memory();
+        Tag::create(&db, "work").unwrap();
+        let report = seed_demo_data(&db, chrono_tz::UTC, true).unwrap();
+        assert!(report.tags < Tag::get_all(&db).unwrap().len());
+
+        clear_demo_data(&db).unwrap();
+        let names: Vec<String> = Tag::get_all(&db)
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        assert_eq!(names, vec!["work"]);
+    }
+}
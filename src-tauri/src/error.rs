@@ -0,0 +1,77 @@
+use crate::database::DbError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+// 命令返回给前端的错误，序列化为 { code, message }，前端按 code 区分错误类型
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("数据库错误：{0}")]
+    Database(String),
+    #[error("未找到资源：{0}")]
+    NotFound(String),
+    #[error("无效参数：{0}")]
+    Validation(String),
+    #[error("资源冲突：{0}")]
+    Conflict(String),
+    #[error("文件读写失败：{0}")]
+    Io(String),
+    #[error("序列化失败：{0}")]
+    Serialization(String),
+    #[error("系统接口调用失败：{0}")]
+    Platform(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) => "database",
+            AppError::NotFound(_) => "not_found",
+            AppError::Validation(_) => "validation",
+            AppError::Conflict(_) => "conflict",
+            AppError::Io(_) => "io",
+            AppError::Serialization(_) => "serialization",
+            AppError::Platform(_) => "platform",
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        AppError::Database(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Io(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Serialization(err.to_string())
+    }
+}
+
+impl From<DbError> for AppError {
+    fn from(err: DbError) -> Self {
+        match err {
+            DbError::Sqlite(e) => e.into(),
+            DbError::InvalidArgument(msg) => AppError::Validation(msg),
+            DbError::NotFound(msg) => AppError::NotFound(msg),
+            DbError::Conflict(msg) => AppError::Conflict(msg),
+            e @ DbError::InvalidTransition { .. } => AppError::Conflict(e.to_string()),
+            DbError::Io(e) => e.into(),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::database::{csv_field, Matter, RepeatTask};
+use crate::recurrence::RepeatTime;
+use chrono::{DateTime, SecondsFormat, Utc, Weekday};
+use std::collections::HashSet;
+
+const PRODUCT_ID: &str = "-//fates-app//Fates//EN";
+
+// iCalendar 的 UTC 时间格式，必须带 Z 后缀
+const ICAL_DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+// 按 RFC 5545，每行不超过 75 个字节
+const MAX_LINE_BYTES: usize = 75;
+
+// CSV 的列，导入时按表头名称匹配
+pub const MATTER_CSV_COLUMNS: [&str; 10] = [
+    "id",
+    "title",
+    "description",
+    "tags",
+    "start_time",
+    "end_time",
+    "priority",
+    "type",
+    "created_at",
+    "updated_at",
+];
+
+// RRULE 中的星期，顺序与 repeat_time 的位一致（第 0 位为周日）
+const BYDAY: [(Weekday, &str); 7] = [
+    (Weekday::Sun, "SU"),
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+];
+
+fn ical_time(time: DateTime<Utc>) -> String {
+    time.format(ICAL_DATETIME_FORMAT).to_string()
+}
+
+// 转义 TEXT 类型的值
+fn ical_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+// 超长的行折成多行，续行以空格开头，不会截断多字节字符
+fn push_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        // 续行开头的空格也占一个字节
+        if width + c.len_utf8() > MAX_LINE_BYTES {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+// 将 repeat_time 转为每周重复的 RRULE，无法解析时返回 None。排除节假日无法用 RRULE 表示，忽略
+fn repeat_rule(repeat_time: &str) -> Option<String> {
+    let repeat_time = RepeatTime::parse(repeat_time).ok()?;
+    let days: Vec<&str> = BYDAY
+        .iter()
+        .filter(|(weekday, _)| repeat_time.includes(*weekday))
+        .map(|(_, day)| *day)
+        .collect();
+    Some(format!("FREQ=WEEKLY;BYDAY={}", days.join(",")))
+}
+
+fn push_event(ics: &mut String, matter: &Matter, uid: &str, rule: Option<&str>) {
+    push_line(ics, "BEGIN:VEVENT");
+    push_line(ics, &format!("UID:{}", ical_text(uid)));
+    push_line(ics, &format!("DTSTAMP:{}", ical_time(matter.updated_at)));
+    push_line(ics, &format!("DTSTART:{}", ical_time(matter.start_time)));
+    // 未结束的事项不写结束时间
+    if !matter.is_open_ended() {
+        push_line(ics, &format!("DTEND:{}", ical_time(matter.end_time)));
+    }
+    if let Some(rule) = rule {
+        push_line(ics, &format!("RRULE:{}", rule));
+    }
+    push_line(ics, &format!("SUMMARY:{}", ical_text(&matter.title)));
+    if let Some(description) = matter.description.as_deref().filter(|d| !d.is_empty()) {
+        push_line(ics, &format!("DESCRIPTION:{}", ical_text(description)));
+    }
+    if let Some(tags) = matter.tags.as_deref().filter(|t| !t.is_empty()) {
+        push_line(ics, &format!("CATEGORIES:{}", ical_text(tags)));
+    }
+    push_line(ics, "END:VEVENT");
+}
+
+/// 将事项导出为 iCalendar 文本，时间均为 UTC。
+/// 周期任务生成的事项（type_ = 1，reserved_2 为任务 id）按任务合并为一个带 RRULE 的事件，
+/// 以最早的一次为开始时间、任务 id 为 UID；找不到任务或 repeat_time 无效时按普通事项导出
+pub fn matters_to_ical(matters: &[Matter], repeat_tasks: &[RepeatTask]) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, &format!("PRODID:{}", PRODUCT_ID));
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+
+    let mut ordered: Vec<&Matter> = matters.iter().collect();
+    ordered.sort_by_key(|matter| matter.start_time);
+
+    let mut exported_tasks = HashSet::new();
+    for matter in ordered {
+        let series = matter
+            .reserved_2
+            .as_deref()
+            .filter(|_| matter.type_ == 1)
+            .and_then(|task_id| repeat_tasks.iter().find(|task| task.id == task_id))
+            .and_then(|task| Some((task, repeat_rule(&task.repeat_time)?)));
+
+        match series {
+            Some((task, rule)) => {
+                if exported_tasks.insert(task.id.as_str()) {
+                    push_event(&mut ics, matter, &task.id, Some(&rule));
+                }
+            }
+            None => push_event(&mut ics, matter, &matter.id, None),
+        }
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+// 保留完整精度，保证导出后再导入时间不变
+fn csv_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// 将事项导出为 CSV，包含表头，时间为 RFC3339 格式的 UTC 时间。
+/// 保留字段不导出，description 和 tags 为空时写空字符串
+pub fn matters_to_csv(matters: &[Matter]) -> String {
+    let mut csv = MATTER_CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for matter in matters {
+        let row = [
+            matter.id.clone(),
+            matter.title.clone(),
+            matter.description.clone().unwrap_or_default(),
+            matter.tags.clone().unwrap_or_default(),
+            csv_time(matter.start_time),
+            csv_time(matter.end_time),
+            matter.priority.to_string(),
+            matter.type_.to_string(),
+            csv_time(matter.created_at),
+            csv_time(matter.updated_at),
+        ];
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
@@ -1,12 +1,26 @@
 use crate::database::SafeConnection;
-use crate::database::{KVStore, Matter, NotificationRecord, RepeatTask, Tag, Todo};
+use crate::database::{
+    self, ChangeLogEntry, DbError, KVStore, Matter, MatterDraft, NotificationPage,
+    NotificationRecord, NotificationType, RecurringNotification, RepeatTask, RepeatTaskException,
+    Tag, TimelineGroup, Todo, NOTIFICATION_LAST_SEEN_KEY,
+};
+use crate::demo;
+use crate::export;
+use crate::import;
+use crate::recurrence;
+use crate::settings::AppSettings;
+use crate::stats::{self, ProjectionMethod, TimeBucket};
+use crate::toggl;
+use crate::utils;
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
+    http::header,
     response::IntoResponse,
     routing::{delete, get, post, put},
     Json, Router,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -50,6 +64,8 @@ pub enum ServerError {
     BadRequest(String),
     #[error("未找到资源：{0}")]
     NotFound(String),
+    #[error("资源冲突：{0}")]
+    Conflict(String),
 }
 
 impl IntoResponse for ServerError {
@@ -59,23 +75,55 @@ impl IntoResponse for ServerError {
             ServerError::DatabaseError(msg) => (500, msg),
             ServerError::StartupError(msg) => (500, msg),
             ServerError::BadRequest(msg) => (400, msg),
+            ServerError::Conflict(msg) => (409, msg),
         };
 
         Json(ApiResponse::<()>::error(code, &message)).into_response()
     }
 }
 
+impl From<DbError> for ServerError {
+    fn from(err: DbError) -> Self {
+        match err {
+            DbError::Sqlite(e) => ServerError::DatabaseError(e.to_string()),
+            DbError::InvalidArgument(msg) => ServerError::BadRequest(msg),
+            DbError::NotFound(msg) => ServerError::NotFound(msg),
+            DbError::Conflict(msg) => ServerError::Conflict(msg),
+            e @ DbError::InvalidTransition { .. } => ServerError::Conflict(e.to_string()),
+            DbError::Io(e) => ServerError::DatabaseError(e.to_string()),
+        }
+    }
+}
+
 pub struct AppState {
     shutdown_tx: Option<oneshot::Sender<()>>,
     db: Arc<SafeConnection>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AtQuery {
+    at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TimeRangeQuery {
     start: DateTime<Utc>,
     end: DateTime<Utc>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TimezoneQuery {
+    timezone: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    timezone: String,
+    tag: Option<String>,
+}
+
 trait RouteConfig {
     fn configure(self, state: Arc<Mutex<AppState>>) -> Router;
 }
@@ -89,14 +137,96 @@ impl RouteConfig for ApiRoutes {
             .route("/matter/:id", get(get_matter))
             .route("/matter/:id", put(update_matter))
             .route("/matter/:id", delete(delete_matter))
+            .route("/matter/:id/local", get(get_matter_with_local))
+            .route("/matter/:id/split", post(split_matter))
+            .route("/matter/:id/move", post(move_matter_to_day))
+            .route("/matter/:id/elapsed", get(get_running_elapsed))
+            .route("/matter/running/fix", post(fix_multiple_running))
+            .route("/matter/:id/restore", put(restore_matter))
+            .route("/matter/trash", get(get_trashed_matters))
+            .route("/matter/trash/purge", post(purge_trashed_matters))
+            .route("/matter/batch", post(create_matters_batch))
+            .route("/matter/join", post(join_matters))
+            .route("/matter/with-defaults", post(create_matter_with_defaults))
+            .route("/matter/from-local", post(create_matter_from_local))
+            .route("/matter/tag-by-search", post(tag_matters_by_search))
+            .route("/matter/import/toggl", post(import_toggl_matters))
+            .route("/matter/import/ical", post(import_ical_matters))
+            .route("/matter/import/csv", post(import_csv_matters))
             .route("/matter/range", get(get_matters_by_range))
+            .route("/matter/day", get(get_matters_for_day))
+            .route("/matter/export.ics", get(export_matters_ical))
+            .route("/matter/export.csv", get(export_matters_csv))
+            .route("/matter/range/conflicts", get(get_matters_with_conflicts))
+            .route("/matter/conflicts", get(get_conflict_details))
+            .route("/matter/overlaps", get(get_overlapping_matters))
+            .route("/matter/day-layout", get(get_day_layout))
+            .route("/matter/week-grid", get(get_week_grid))
+            .route("/matter/time-since-last", get(get_time_since_last))
             .route("/matter", get(get_all_matters))
             .route("/matter/query", get(query_matter_by_field))
+            .route("/matter/search", get(search_matters))
+            .route("/matter/search-index", get(verify_search_index))
+            .route("/matter/search-index/rebuild", post(rebuild_search_index))
+            .route("/stats/weekday-start", get(get_avg_start_by_weekday))
+            .route("/stats/weekday-coverage", get(get_weekday_coverage))
+            .route("/stats/avg-gap", get(get_avg_gap))
+            .route("/stats/days-with-data", get(get_days_with_data))
+            .route("/stats/tag-streak", get(get_tag_streak))
+            .route("/stats/tag-transitions", get(get_tag_transitions))
+            .route("/stats/time", get(get_time_stats))
+            .route("/stats/tag-series", get(get_tag_time_series))
+            .route("/stats/tag-allocation", get(get_tag_allocation))
+            .route("/stats/category-balance", get(get_category_balance))
+            .route("/stats/remaining-capacity", get(get_remaining_capacity))
+            .route("/stats/month-projection", get(get_month_projection))
+            .route("/stats/weekly-goal", get(get_weekly_goal_progress))
+            .route("/stats/suggest-time", get(get_suggested_time))
+            .route("/stats/tag-phases", get(get_tag_phase_counts))
+            .route("/stats/longest-focus", get(get_longest_focus_block))
+            .route("/stats/completeness", get(get_completeness_report))
+            .route("/stats/duration-histogram", get(get_duration_histogram))
+            .route("/stats/priority-totals", get(get_time_by_priority))
+            .route(
+                "/stats/priority-distribution",
+                get(get_priority_distribution),
+            )
+            .route("/timeline", get(get_grouped_timeline))
+            .route("/timeline/group", post(create_timeline_group))
+            .route("/timeline/group", get(get_all_timeline_groups))
+            .route("/timeline/group/:id", delete(delete_timeline_group))
+            .route("/changes", get(export_changes))
+            .route("/changes/import", post(import_changes))
+            .route("/changes/undo", post(undo_last_change))
+            .route("/changes/redo", post(redo_last_change))
+            .route("/changes/audit.csv", get(export_changelog_csv))
+            .route("/time-anomalies", post(detect_time_anomalies))
+            .route("/backup/binary/export", post(export_binary_backup))
+            .route("/backup/binary/import", post(import_binary_backup))
+            .route("/backup/database/export", post(export_database_backup))
+            .route("/backup/database/import", post(import_database_backup))
+            .route("/demo-data", post(seed_demo_data))
+            .route("/demo-data", delete(clear_demo_data))
+            .route("/settings", get(get_settings))
+            .route("/settings", put(save_settings))
+            .route("/settings/snapshot", get(snapshot_settings))
+            .route("/settings/restore", post(restore_settings))
+            .route("/settings/reset", post(reset_settings))
+            .route("/kv/purge-expired", post(purge_expired_kv))
             .route("/kv/:key", get(get_kv))
             .route("/kv/:key", put(set_kv))
             .route("/kv/:key", delete(delete_kv))
             .route("/tags", post(create_tag))
             .route("/tags", get(get_all_tags))
+            .route("/tags/suggest", get(suggest_tags_for_title))
+            .route("/tags/with-counts", get(get_tags_with_counts))
+            .route("/tags/palette", get(get_tag_palette))
+            .route("/tags/cleanup-orphans", post(cleanup_orphan_tag_refs))
+            .route("/tags/categories", get(get_tag_categories))
+            .route("/tags/import", post(import_tags))
+            .route("/tags/:name/color", put(set_tag_color))
+            .route("/tags/:name/category", put(set_tag_category))
+            .route("/tags/:name/impact", get(get_tag_delete_impact))
             .route("/tags/:name", delete(delete_tag))
             .route("/tags/update/:name", put(update_tag_last_used_at))
             .route("/repeat-task", post(create_repeat_task))
@@ -105,21 +235,79 @@ impl RouteConfig for ApiRoutes {
             .route("/repeat-task/:id", delete(delete_repeat_task))
             .route("/repeat-task", get(get_all_repeat_tasks))
             .route("/repeat-task/active", get(get_active_repeat_tasks))
+            .route(
+                "/repeat-task/status/:status",
+                get(get_repeat_tasks_by_status),
+            )
+            .route("/repeat-task/occurrences", get(get_repeat_task_occurrences))
+            .route("/repeat-task/import", post(import_repeat_tasks))
+            .route("/repeat-task/duplicates", get(get_duplicate_repeat_titles))
             .route(
                 "/repeat-task/:id/status/:status",
                 put(update_repeat_task_status),
             )
+            .route("/repeat-task/:id/activate", put(activate_repeat_task))
+            .route("/repeat-task/:id/archive", put(archive_repeat_task))
+            .route(
+                "/repeat-task/:id/next-occurrence",
+                get(get_next_repeat_occurrence),
+            )
+            .route(
+                "/repeat-task/:id/exceptions",
+                get(get_repeat_task_exceptions),
+            )
+            .route(
+                "/repeat-task/:id/exceptions",
+                post(add_repeat_task_exception),
+            )
+            .route(
+                "/repeat-task/:id/exceptions/:date",
+                delete(remove_repeat_task_exception),
+            )
             .route("/todo", post(create_todo))
             .route("/todo/:id", get(get_todo))
             .route("/todo/:id", put(update_todo))
             .route("/todo/:id", delete(delete_todo))
+            .route("/todo/:id/status", put(set_todo_status))
             .route("/todo", get(get_all_todos))
+            .route("/todo/import", post(import_todos))
+            .route("/todo/carry-over", post(carry_over_todos))
             .route("/notification", post(create_notification))
+            .route("/notification/schedule", post(schedule_notification))
+            .route("/notification/scheduled", get(get_scheduled_notifications))
+            .route("/notification/reminders/scan", post(scan_matter_reminders))
+            .route(
+                "/notification/scheduled/:id",
+                delete(cancel_scheduled_notification),
+            )
+            .route(
+                "/notification/recurring",
+                post(create_recurring_notification),
+            )
+            .route(
+                "/notification/recurring",
+                get(get_all_recurring_notifications),
+            )
+            .route(
+                "/notification/recurring/:id",
+                get(get_recurring_notification),
+            )
+            .route(
+                "/notification/recurring/:id",
+                put(update_recurring_notification),
+            )
+            .route(
+                "/notification/recurring/:id",
+                delete(delete_recurring_notification),
+            )
             .route("/notification/:id", get(get_notification))
             .route("/notification/:id", put(update_notification))
             .route("/notification/:id", delete(delete_notification))
             // .route("/notification", get(get_all_notifications))
             .route("/notification/unread", get(get_unread_notifications))
+            .route("/notification/query", get(query_notifications))
+            .route("/notification/recent", get(get_recent_unread_notifications))
+            .route("/notification/summary", get(get_notification_summary))
             .route("/notification/:id/read", put(mark_notification_as_read))
             // make special type notification as read
             .route(
@@ -130,6 +318,14 @@ impl RouteConfig for ApiRoutes {
                 "/notification/read-all",
                 put(mark_all_notifications_as_read),
             )
+            .route(
+                "/notification/read/task/:task_id",
+                put(mark_notifications_as_read_by_task),
+            )
+            .route(
+                "/notification/type/:type",
+                delete(delete_notifications_by_type),
+            )
             .with_state(state)
     }
 }
@@ -206,7 +402,71 @@ async fn create_matter(
     matter.updated_at = Utc::now();
 
     let state = state.lock().await;
-    Matter::create(&state.db, &matter).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    // 已有进行中的事项时返回 409
+    Matter::create(&state.db, &matter)?;
+
+    Ok(Json(ApiResponse::success(matter)))
+}
+
+// 在一个事务中批量创建事项，任意一条失败时全部不创建
+async fn create_matters_batch(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(mut matters): Json<Vec<Matter>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let now = Utc::now();
+    for matter in &mut matters {
+        if matter.id.trim().is_empty() {
+            matter.id = uuid::Uuid::new_v4().to_string();
+        }
+        matter.created_at = now;
+        matter.updated_at = now;
+    }
+
+    let state = state.lock().await;
+    Matter::create_batch(&state.db, &matters)?;
+
+    Ok(Json(ApiResponse::success(matters)))
+}
+
+async fn create_matter_with_defaults(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(draft): Json<MatterDraft>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let matter = Matter::create_with_defaults(&state.db, draft)?;
+
+    Ok(Json(ApiResponse::success(matter)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocalMatterRequest {
+    title: String,
+    date: NaiveDate,
+    start: String, // "HH:MM" 或 "HH:MM:SS"
+    end: String,
+    timezone: String,
+    tags: Option<String>,
+}
+
+// 由本地日期和时刻创建事项，时区换算在后端完成
+async fn create_matter_from_local(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<LocalMatterRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&payload.timezone).map_err(ServerError::BadRequest)?;
+    let start = utils::parse_time_of_day(&payload.start).map_err(ServerError::BadRequest)?;
+    let end = utils::parse_time_of_day(&payload.end).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let matter = Matter::create_from_local(
+        &state.db,
+        &payload.title,
+        payload.date,
+        start,
+        end,
+        timezone,
+        payload.tags,
+    )?;
 
     Ok(Json(ApiResponse::success(matter)))
 }
@@ -223,6 +483,21 @@ async fn get_matter(
     Ok(Json(ApiResponse::success(matter)))
 }
 
+async fn get_matter_with_local(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Query(query): Query<TimezoneQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let matter = Matter::get_with_local(&state.db, &id, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ServerError::NotFound("Matter not found".into()))?;
+
+    Ok(Json(ApiResponse::success(matter)))
+}
+
 // get all matters
 async fn get_all_matters(
     State(state): State<Arc<Mutex<AppState>>>,
@@ -239,13 +514,13 @@ async fn update_matter(
     Path(id): Path<String>,
     Json(mut matter): Json<Matter>,
 ) -> Result<impl IntoResponse, ServerError> {
+    // 请求中的 updated_at 为读取时的值，用于检测并发修改
+    let expected_updated_at = matter.updated_at;
     matter.id = id;
     matter.updated_at = Utc::now();
 
     let state = state.lock().await;
-    matter
-        .update(&state.db)
-        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    matter.update(&state.db, expected_updated_at)?;
 
     Ok(Json(ApiResponse::success(matter)))
 }
@@ -259,235 +534,1509 @@ async fn delete_matter(
     Ok(Json(ApiResponse::<()>::success(())))
 }
 
-async fn get_matters_by_range(
+async fn restore_matter(
     State(state): State<Arc<Mutex<AppState>>>,
-    Query(range): Query<TimeRangeQuery>,
+    Path(id): Path<String>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    let matters = Matter::get_by_time_range(&state.db, range.start, range.end)
-        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
-
-    Ok(Json(ApiResponse::success(matters)))
+    Matter::restore(&state.db, &id)?;
+    Ok(Json(ApiResponse::<()>::success(())))
 }
 
-// KVStore 相关处理函数
-async fn set_kv(
+async fn get_trashed_matters(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(key): Path<String>,
-    value: String,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    KVStore::set(&state.db, &key, &value).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let matters =
+        Matter::get_trashed(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
 
-    Ok(Json(ApiResponse::<()>::success(())))
+    Ok(Json(ApiResponse::success(matters)))
 }
 
-async fn get_kv(
+#[derive(Debug, Deserialize)]
+pub struct PurgeTrashQuery {
+    older_than: DateTime<Utc>,
+}
+
+// 彻底删除 older_than 之前移入回收站的事项，返回删除的数量
+async fn purge_trashed_matters(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(key): Path<String>,
+    Query(query): Query<PurgeTrashQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    let value =
-        KVStore::get(&state.db, &key, "").map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let removed = Matter::purge_deleted(&state.db, query.older_than)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
 
-    Ok(Json(ApiResponse::success(value)))
+    Ok(Json(ApiResponse::success(removed)))
 }
 
-async fn delete_kv(
+#[derive(Debug, Deserialize)]
+pub struct SplitMatterRequest {
+    at: DateTime<Utc>,
+}
+
+// 在指定时间将事项拆分为两段
+async fn split_matter(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(key): Path<String>,
+    Path(id): Path<String>,
+    Json(payload): Json<SplitMatterRequest>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    KVStore::delete(&state.db, &key).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let matters = Matter::split(&state.db, &id, payload.at)?;
 
-    Ok(Json(ApiResponse::<()>::success(())))
+    Ok(Json(ApiResponse::success(matters)))
 }
 
-// Tag 相关处理函数
-#[derive(Deserialize)]
-struct TagsRequest {
-    names: String,
+#[derive(Debug, Deserialize)]
+pub struct MoveMatterRequest {
+    day: NaiveDate,
+    timezone: String,
 }
 
-async fn create_tag(
+// 将事项移动到另一天，保持本地时刻
+async fn move_matter_to_day(
     State(state): State<Arc<Mutex<AppState>>>,
-    Json(payload): Json<TagsRequest>,
+    Path(id): Path<String>,
+    Json(payload): Json<MoveMatterRequest>,
 ) -> Result<impl IntoResponse, ServerError> {
-    let state = state.lock().await;
-
-    // 分割字符串并去重
-    let names: Vec<String> = payload
-        .names
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
+    let timezone = utils::parse_timezone(&payload.timezone).map_err(ServerError::BadRequest)?;
 
-    if names.is_empty() {
-        return Err(ServerError::BadRequest(
-            "No valid tag names provided".into(),
-        ));
-    }
+    let state = state.lock().await;
+    let matter = Matter::move_to_day(&state.db, &id, payload.day, timezone)?;
 
-    // 批量创建标签
-    for name in names {
-        Tag::create(&state.db, &name).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
-    }
+    Ok(Json(ApiResponse::success(matter)))
+}
 
-    Ok(Json(ApiResponse::<()>::success(())))
+#[derive(Debug, Deserialize)]
+pub struct JoinMattersRequest {
+    id_a: String,
+    id_b: String,
 }
 
-async fn get_all_tags(
+// 合并两个相邻或重叠的事项
+async fn join_matters(
     State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<JoinMattersRequest>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    let tags = Tag::get_all(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let matter = Matter::join(&state.db, &payload.id_a, &payload.id_b)?;
 
-    Ok(Json(ApiResponse::success(tags)))
+    Ok(Json(ApiResponse::success(matter)))
 }
 
-async fn delete_tag(
+#[derive(Debug, Deserialize)]
+pub struct TagBySearchRequest {
+    query: String,
+    tag: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// 给标题匹配搜索词的事项批量加标签
+async fn tag_matters_by_search(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(names): Path<String>,
+    Json(payload): Json<TagBySearchRequest>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
+    let ids = Matter::tag_by_search(&state.db, &payload.query, &payload.tag, payload.dry_run)?;
 
-    // 分割字符串并去重
-    let names: Vec<String> = names
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-
-    if names.is_empty() {
-        return Err(ServerError::BadRequest(
-            "No valid tag names provided".into(),
-        ));
-    }
-
-    // 批量删除标签
-    for name in names {
-        Tag::delete(&state.db, &name).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
-    }
-
-    Ok(Json(ApiResponse::<()>::success(())))
+    Ok(Json(ApiResponse::success(ids)))
 }
 
-async fn update_tag_last_used_at(
+async fn get_matters_by_range(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(names): Path<String>,
+    Query(range): Query<TimeRangeQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
+    let matters = Matter::get_by_time_range(&state.db, range.start, range.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
 
-    // 分割字符串并去重
-    let names: Vec<String> = names
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-
-    if names.is_empty() {
-        return Err(ServerError::BadRequest(
-            "No valid tag names provided".into(),
-        ));
-    }
-
-    // 批量更新标签的最后使用时间
-    for name in names {
-        Tag::update_last_used_at(&state.db, &name)
-            .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
-    }
+    Ok(Json(ApiResponse::success(matters)))
+}
 
-    Ok(Json(ApiResponse::<()>::success(())))
+#[derive(Debug, Deserialize)]
+pub struct DayOffsetQuery {
+    day: Option<DateTime<Utc>>,
+    tz_offset_minutes: i32,
 }
 
-// RepeatTask 相关处理函数
-async fn create_repeat_task(
+// 某一天（默认今天）的事项，本地日按 tz_offset_minutes 计算
+async fn get_matters_for_day(
     State(state): State<Arc<Mutex<AppState>>>,
-    Json(mut task): Json<RepeatTask>,
+    Query(query): Query<DayOffsetQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
-    task.created_at = Utc::now();
-    task.updated_at = Utc::now();
+    let day = query.day.unwrap_or_else(Utc::now);
 
     let state = state.lock().await;
-    RepeatTask::create(&state.db, &task).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let matters = Matter::get_for_day(&state.db, day, query.tz_offset_minutes)?;
 
-    Ok(Json(ApiResponse::success(task)))
+    Ok(Json(ApiResponse::success(matters)))
 }
 
-async fn get_repeat_task(
+// 从 Toggl Track 导出的 JSON 导入事项，不带时区的时间按 timezone 解析
+async fn import_toggl_matters(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(id): Path<String>,
+    Query(query): Query<TimezoneQuery>,
+    data: String,
 ) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
     let state = state.lock().await;
-    let task = RepeatTask::get_by_id(&state.db, &id)
-        .map_err(|e| ServerError::DatabaseError(e.to_string()))?
-        .ok_or_else(|| ServerError::NotFound("RepeatTask not found".into()))?;
+    let report = toggl::import_toggl(&state.db, &data, timezone)?;
 
-    Ok(Json(ApiResponse::success(task)))
+    Ok(Json(ApiResponse::success(report)))
 }
 
-async fn get_all_repeat_tasks(
+// 将时间范围内的事项导出为 iCalendar，便于导入手机日历
+async fn export_matters_ical(
     State(state): State<Arc<Mutex<AppState>>>,
+    Query(range): Query<TimeRangeQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    let tasks =
+    let matters = Matter::get_by_time_range(&state.db, range.start, range.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let repeat_tasks =
         RepeatTask::get_all(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
 
-    Ok(Json(ApiResponse::success(tasks)))
+    Ok((
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        export::matters_to_ical(&matters, &repeat_tasks),
+    ))
 }
 
-async fn get_active_repeat_tasks(
+// 从 iCalendar 文本导入事项，不带时区的时间按 timezone 解析
+async fn import_ical_matters(
     State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TimezoneQuery>,
+    data: String,
 ) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
     let state = state.lock().await;
-    let tasks = RepeatTask::get_active_tasks(&state.db)
-        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let report = import::import_ical(&state.db, &data, timezone)?;
 
-    Ok(Json(ApiResponse::success(tasks)))
+    Ok(Json(ApiResponse::success(report)))
 }
 
-async fn update_repeat_task(
+// 将时间范围内的事项导出为 CSV，便于在表格软件中编辑
+async fn export_matters_csv(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(id): Path<String>,
-    Json(mut task): Json<RepeatTask>,
+    Query(range): Query<TimeRangeQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
-    task.id = id;
-    task.updated_at = Utc::now();
-
     let state = state.lock().await;
-    task.update(&state.db)
+    let matters = Matter::get_by_time_range(&state.db, range.start, range.end)
         .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
 
-    Ok(Json(ApiResponse::success(task)))
+    Ok((
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+        export::matters_to_csv(&matters),
+    ))
 }
 
-async fn delete_repeat_task(
+// 从 CSV 文本导入事项，格式与导出的 CSV 相同
+async fn import_csv_matters(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path(id): Path<String>,
+    data: String,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    RepeatTask::delete(&state.db, &id).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let report = import::matters_from_csv(&state.db, &data)?;
 
-    Ok(Json(ApiResponse::<()>::success(())))
+    Ok(Json(ApiResponse::success(report)))
 }
 
-async fn update_repeat_task_status(
+// 时间范围内的事项，附带是否与其他事项重叠的标记
+async fn get_matters_with_conflicts(
     State(state): State<Arc<Mutex<AppState>>>,
-    Path((id, status)): Path<(String, i32)>,
+    Query(range): Query<TimeRangeQuery>,
 ) -> Result<impl IntoResponse, ServerError> {
     let state = state.lock().await;
-    RepeatTask::update_status(&state.db, &id, status)
+    let matters = Matter::get_range_with_conflicts(&state.db, range.start, range.end)
         .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
 
-    Ok(Json(ApiResponse::<()>::success(())))
+    Ok(Json(ApiResponse::success(matters)))
 }
 
-// Todo 相关处理函数
-async fn create_todo(
+#[derive(Debug, Deserialize)]
+pub struct ConflictDetailsQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    exclude_id: Option<String>,
+}
+
+async fn get_conflict_details(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<ConflictDetailsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let conflicts = Matter::conflict_details(
+        &state.db,
+        query.start,
+        query.end,
+        query.exclude_id.as_deref(),
+    )
+    .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(conflicts)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverlapQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    ignore_markers: Option<bool>,
+}
+
+async fn get_overlapping_matters(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<OverlapQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let overlaps = Matter::find_overlapping_pairs(
+        &state.db,
+        query.start,
+        query.end,
+        query.ignore_markers.unwrap_or(false),
+    )
+    .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(overlaps)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DayLayoutQuery {
+    day: NaiveDate,
+    timezone: String,
+}
+
+// 日视图中重叠事项的分列布局
+async fn get_day_layout(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<DayLayoutQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let layout = Matter::day_layout(&state.db, query.day, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(layout)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeekGridQuery {
+    week_start: NaiveDate,
+    timezone: String,
+}
+
+// 周视图：7 天内每天的事项片段与分列布局
+async fn get_week_grid(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<WeekGridQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let grid = Matter::week_grid(&state.db, query.week_start, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(grid)))
+}
+
+// 距离最近一次活动的秒数，没有任何事项时返回 null
+async fn get_time_since_last(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<AtQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let at = query.at.unwrap_or_else(Utc::now);
+
+    let state = state.lock().await;
+    let seconds = Matter::time_since_last(&state.db, at)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(seconds)))
+}
+
+// 修复同时有多个进行中事项的情况，返回被结束的事项 id
+async fn fix_multiple_running(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let closed = Matter::fix_multiple_running(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(closed)))
+}
+
+async fn get_running_elapsed(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Query(query): Query<AtQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let at = query.at.unwrap_or_else(Utc::now);
+
+    let state = state.lock().await;
+    let seconds = Matter::running_elapsed(&state.db, &id, at)?;
+
+    Ok(Json(ApiResponse::success(seconds)))
+}
+
+// Timeline 相关处理函数
+async fn get_grouped_timeline(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(range): Query<TimeRangeQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let timeline = Matter::get_grouped_timeline(&state.db, range.start, range.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(timeline)))
+}
+
+async fn create_timeline_group(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(group): Json<TimelineGroup>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    TimelineGroup::create(&state.db, &group)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(group)))
+}
+
+async fn get_all_timeline_groups(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let groups =
+        TimelineGroup::get_all(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(groups)))
+}
+
+async fn delete_timeline_group(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    TimelineGroup::delete(&state.db, &id).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+// 统计相关处理函数
+async fn get_avg_start_by_weekday(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<StatsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let averages = Matter::avg_start_by_weekday(
+        &state.db,
+        query.start,
+        query.end,
+        query.tag.as_deref(),
+        timezone,
+    )
+    .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(averages)))
+}
+
+async fn get_weekday_coverage(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<StatsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let coverage = Matter::weekday_coverage(&state.db, query.start, query.end, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(coverage)))
+}
+
+// 相邻事项之间的平均空闲间隔（碎片化程度）
+async fn get_avg_gap(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<StatsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let gaps = Matter::avg_gap(&state.db, query.start, query.end, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(gaps)))
+}
+
+// 日历上需要标记的日期（有事项的本地日期）
+async fn get_days_with_data(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<StatsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let days = Matter::days_with_data(&state.db, query.start, query.end, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(days)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DayQuery {
+    day: NaiveDate,
+    timezone: String,
+}
+
+// 某一天标签之间的切换，用于桑基图
+async fn get_tag_transitions(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<DayQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let flow = Matter::tag_transitions(&state.db, query.day, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(flow)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagStreakQuery {
+    tag: String,
+    timezone: String,
+}
+
+// 某个标签当前和最长的连续天数
+async fn get_tag_streak(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TagStreakQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let streak = Matter::tag_streak(&state.db, &query.tag, Utc::now(), timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(streak)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DurationHistogramQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket_minutes: i64,
+}
+
+async fn get_duration_histogram(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<DurationHistogramQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let histogram =
+        Matter::duration_histogram(&state.db, query.start, query.end, query.bucket_minutes)?;
+
+    Ok(Json(ApiResponse::success(histogram)))
+}
+
+async fn get_time_by_priority(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TimeRangeQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let totals = Matter::time_by_priority(&state.db, query.start, query.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(totals)))
+}
+
+// 各优先级时长的百分比，用于饼图
+async fn get_priority_distribution(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TimeRangeQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let shares = Matter::priority_distribution(&state.db, query.start, query.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(shares)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagSeriesQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket: TimeBucket,
+    timezone: String,
+}
+
+async fn get_tag_time_series(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TagSeriesQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let series = stats::tag_time_series(&state.db, query.start, query.end, query.bucket, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(series)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagAllocationQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tag: String,
+}
+
+async fn get_tag_allocation(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TagAllocationQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let allocation = stats::tag_allocation_ratio(&state.db, query.start, query.end, &query.tag)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(allocation)))
+}
+
+// 区间内的总时长，以及按标签和按事项类型的时长
+async fn get_time_stats(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(range): Query<TimeRangeQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    if range.start >= range.end {
+        return Err(ServerError::BadRequest("start must be before end".into()));
+    }
+    let state = state.lock().await;
+    let summary = stats::time_stats(&state.db, range.start, range.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(summary)))
+}
+
+async fn get_category_balance(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(range): Query<TimeRangeQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let balance = stats::category_balance(&state.db, range.start, range.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(balance)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemainingCapacityQuery {
+    day: NaiveDate,
+    target_seconds: i64,
+    timezone: String,
+}
+
+async fn get_remaining_capacity(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<RemainingCapacityQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let capacity = Matter::remaining_capacity(&state.db, query.day, query.target_seconds, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(capacity)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MonthProjectionQuery {
+    as_of: Option<DateTime<Utc>>,
+    timezone: String,
+    #[serde(default)]
+    method: ProjectionMethod,
+}
+
+async fn get_month_projection(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<MonthProjectionQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+    let as_of = query.as_of.unwrap_or_else(Utc::now);
+
+    let state = state.lock().await;
+    let projection = Matter::project_month_total(&state.db, as_of, timezone, query.method)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(projection)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeeklyGoalQuery {
+    week_start: NaiveDate,
+    timezone: String,
+}
+
+// 每周目标进度，达成目标时（每周一次）同时创建通知
+async fn get_weekly_goal_progress(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<WeeklyGoalQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let progress = Matter::weekly_goal_progress(&state.db, query.week_start, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    stats::notify_weekly_goal_met(&state.db, &progress)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(progress)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestTimeQuery {
+    tag: String,
+    timezone: String,
+}
+
+async fn get_suggested_time(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<SuggestTimeQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let suggestion = Matter::suggest_time_for_tag(&state.db, &query.tag, timezone)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(suggestion)))
+}
+
+async fn get_tag_phase_counts(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<AtQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let at = query.at.unwrap_or_else(Utc::now);
+
+    let state = state.lock().await;
+    let counts = Matter::tag_time_buckets(&state.db, at)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(counts)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FocusBlockQuery {
+    day: NaiveDate,
+    timezone: String,
+    tag: Option<String>,
+    #[serde(default)]
+    max_gap_seconds: i64,
+}
+
+async fn get_longest_focus_block(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<FocusBlockQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let block = Matter::longest_focus_block(
+        &state.db,
+        query.day,
+        query.tag.as_deref(),
+        timezone,
+        query.max_gap_seconds,
+    )
+    .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(block)))
+}
+
+async fn get_completeness_report(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let report = Matter::completeness_report(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+// 变更同步相关处理函数
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    #[serde(default)]
+    since: i64,
+}
+
+// 导出 since 之后的变更，返回 gzip 压缩的 JSON
+async fn export_changes(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let payload = ChangeLogEntry::export_since(&state.db, query.since)?;
+
+    Ok(([(header::CONTENT_TYPE, "application/gzip")], payload))
+}
+
+async fn import_changes(
+    State(state): State<Arc<Mutex<AppState>>>,
+    payload: Bytes,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let applied = ChangeLogEntry::import(&state.db, &payload)?;
+
+    Ok(Json(ApiResponse::success(applied)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+// 导出区间内的变更记录，供审计查看
+async fn export_changelog_csv(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let csv = database::export_changelog_csv(&state.db, query.start, query.end)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], csv))
+}
+
+// 撤销最近一次修改，没有可撤销的修改时返回 null
+async fn undo_last_change(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let entry = ChangeLogEntry::undo_last(&state.db)?;
+
+    Ok(Json(ApiResponse::success(entry)))
+}
+
+async fn redo_last_change(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let entry = ChangeLogEntry::redo_last(&state.db)?;
+
+    Ok(Json(ApiResponse::success(entry)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeAnomalyQuery {
+    #[serde(default)]
+    auto_correct: bool,
+}
+
+// 检查由时钟错误导致的时间异常，auto_correct=true 时同时修正
+async fn detect_time_anomalies(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TimeAnomalyQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let anomalies = database::detect_time_anomalies(&state.db, query.auto_correct)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(anomalies)))
+}
+
+// 二进制备份相关处理函数
+#[derive(Debug, Deserialize)]
+pub struct BackupPathPayload {
+    path: String,
+}
+
+async fn export_binary_backup(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<BackupPathPayload>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let rows = database::export_binary(&state.db, std::path::Path::new(&payload.path))?;
+
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+async fn import_binary_backup(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<BackupPathPayload>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let rows = database::with_auto_backup(&state.db, "import_binary", || {
+        database::import_binary(&state.db, std::path::Path::new(&payload.path))
+    })?;
+
+    Ok(Json(ApiResponse::success(rows)))
+}
+
+// 使用 SQLite 在线备份 API 复制整个数据库文件
+async fn export_database_backup(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<BackupPathPayload>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    database::backup_database(&state.db, std::path::Path::new(&payload.path))?;
+
+    Ok(Json(ApiResponse::success(())))
+}
+
+// 用数据库文件替换当前数据库，客户端需要重新加载所有数据
+async fn import_database_backup(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<BackupPathPayload>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let restored = database::with_auto_backup(&state.db, "restore_database", || {
+        database::restore_database(&state.db, std::path::Path::new(&payload.path))
+    })?;
+
+    Ok(Json(ApiResponse::success(restored)))
+}
+
+// 所有设置，缺少的键使用默认值
+async fn get_settings(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let settings =
+        AppSettings::load(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+async fn save_settings(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(settings): Json<AppSettings>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    settings.save(&state.db)?;
+
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+// 设置快照相关处理函数
+async fn snapshot_settings(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let snapshot = KVStore::snapshot_settings(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(snapshot)))
+}
+
+async fn restore_settings(
+    State(state): State<Arc<Mutex<AppState>>>,
+    snapshot: String,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let restored = database::with_auto_backup(&state.db, "restore_settings", || {
+        KVStore::restore_settings(&state.db, &snapshot)
+    })?;
+
+    Ok(Json(ApiResponse::success(restored)))
+}
+
+// 删除指定的设置，使其回到默认值
+async fn reset_settings(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(keys): Json<Vec<String>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let removed = KVStore::reset_settings_to_defaults(&state.db, &keys)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(removed)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeedDemoQuery {
+    timezone: String,
+    #[serde(default)]
+    force: bool,
+}
+
+// 生成示例数据，数据库已有数据且未指定 force 时返回 409
+async fn seed_demo_data(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<SeedDemoQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let report = demo::seed_demo_data(&state.db, timezone, query.force)?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+async fn clear_demo_data(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let report = demo::clear_demo_data(&state.db)?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetKvQuery {
+    ttl_seconds: Option<i64>,
+}
+
+// KVStore 相关处理函数
+async fn set_kv(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(key): Path<String>,
+    Query(query): Query<SetKvQuery>,
+    value: String,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    match query.ttl_seconds {
+        Some(ttl_seconds) => KVStore::set_with_ttl(&state.db, &key, &value, ttl_seconds)?,
+        None => KVStore::set(&state.db, &key, &value)
+            .map_err(|e| ServerError::DatabaseError(e.to_string()))?,
+    }
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+// 删除所有已过期的键值对，返回删除的数量
+async fn purge_expired_kv(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let removed =
+        KVStore::purge_expired(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(removed)))
+}
+
+async fn get_kv(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(key): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let value =
+        KVStore::get(&state.db, &key, "").map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(value)))
+}
+
+async fn delete_kv(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(key): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    KVStore::delete(&state.db, &key).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+// Tag 相关处理函数
+#[derive(Deserialize)]
+struct TagsRequest {
+    names: String,
+}
+
+async fn create_tag(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<TagsRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+
+    // 分割字符串并去重
+    let names: Vec<String> = payload
+        .names
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    if names.is_empty() {
+        return Err(ServerError::BadRequest(
+            "No valid tag names provided".into(),
+        ));
+    }
+
+    // 批量创建标签
+    for name in names {
+        Tag::create(&state.db, &name).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    }
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+async fn get_all_tags(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let tags = Tag::get_all(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(tags)))
+}
+
+// 标签及使用次数，最常用的在前
+async fn get_tags_with_counts(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let tags =
+        Tag::get_with_counts(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(tags)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestTagsQuery {
+    title: String,
+}
+
+// 根据标题推荐已有标签
+async fn suggest_tags_for_title(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<SuggestTagsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let suggestions = Tag::suggest_for_title(&state.db, &query.title)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(suggestions)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagPaletteQuery {
+    names: String,
+}
+
+// 获取标签颜色
+// 清理事项和周期任务中对已删除标签的引用
+async fn cleanup_orphan_tag_refs(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<DryRunQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let refs = Tag::cleanup_orphan_refs(&state.db, query.dry_run)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(refs)))
+}
+
+async fn get_tag_palette(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<TagPaletteQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let names: Vec<String> = query
+        .names
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let state = state.lock().await;
+    let palette =
+        Tag::palette(&state.db, &names).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(palette)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagColorRequest {
+    color: Option<String>,
+}
+
+// 设置标签颜色，color 为空时恢复为自动生成的颜色
+async fn set_tag_color(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(name): Path<String>,
+    Json(payload): Json<TagColorRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    if let Some(color) = &payload.color {
+        if !utils::is_hex_color(color) {
+            return Err(ServerError::BadRequest(format!("Invalid color: {}", color)));
+        }
+    }
+
+    let state = state.lock().await;
+    Tag::set_color(&state.db, &name, payload.color.as_deref())
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TagCategoryRequest {
+    category: Option<String>,
+}
+
+async fn set_tag_category(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(name): Path<String>,
+    Json(payload): Json<TagCategoryRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    Tag::set_category(&state.db, &name, payload.category.as_deref())
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+async fn get_tag_categories(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let categories =
+        Tag::categories(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(categories)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportTagsQuery {
+    #[serde(default)]
+    prune_unlisted: bool,
+}
+
+// 按外部标签列表（JSON 字符串数组）同步标签
+async fn import_tags(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<ImportTagsQuery>,
+    Json(names): Json<Vec<String>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let report = Tag::import(&state.db, &names, query.prune_unlisted)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+// 删除前查看引用该标签的事项和周期任务数量
+async fn get_tag_delete_impact(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let impact = Tag::delete_impact(&state.db, &name)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(impact)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteTagQuery {
+    #[serde(default)]
+    strip_refs: bool,
+}
+
+async fn delete_tag(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(names): Path<String>,
+    Query(query): Query<DeleteTagQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+
+    // 分割字符串并去重
+    let names: Vec<String> = names
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    if names.is_empty() {
+        return Err(ServerError::BadRequest(
+            "No valid tag names provided".into(),
+        ));
+    }
+
+    // 批量删除标签，返回每个标签影响的事项和周期任务数量
+    let mut impacts = Vec::with_capacity(names.len());
+    for name in names {
+        let impact = Tag::delete_with_refs(&state.db, &name, query.strip_refs)
+            .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+        impacts.push(impact);
+    }
+
+    Ok(Json(ApiResponse::success(impacts)))
+}
+
+async fn update_tag_last_used_at(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(names): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+
+    // 分割字符串并去重
+    let names: Vec<String> = names
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    if names.is_empty() {
+        return Err(ServerError::BadRequest(
+            "No valid tag names provided".into(),
+        ));
+    }
+
+    // 批量更新标签的最后使用时间
+    for name in names {
+        Tag::update_last_used_at(&state.db, &name)
+            .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    }
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+// RepeatTask 相关处理函数
+async fn create_repeat_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(mut task): Json<RepeatTask>,
+) -> Result<impl IntoResponse, ServerError> {
+    task.created_at = Utc::now();
+    task.updated_at = Utc::now();
+
+    let state = state.lock().await;
+    RepeatTask::create(&state.db, &task)?;
+
+    Ok(Json(ApiResponse::success(task)))
+}
+
+async fn get_repeat_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let task = RepeatTask::get_by_id(&state.db, &id)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ServerError::NotFound("RepeatTask not found".into()))?;
+
+    Ok(Json(ApiResponse::success(task)))
+}
+
+async fn get_all_repeat_tasks(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let tasks =
+        RepeatTask::get_all(&state.db).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(tasks)))
+}
+
+async fn get_active_repeat_tasks(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let tasks = RepeatTask::get_active_tasks(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(tasks)))
+}
+
+// 指定状态（1 启用、0 停用、-1 归档）的周期任务
+async fn get_repeat_tasks_by_status(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(status): Path<i32>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let tasks = RepeatTask::get_by_status(&state.db, status)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(tasks)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OccurrencesQuery {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    timezone: String,
+}
+
+// 将所有启用的周期任务展开为 [from, to) 内的具体事项，不写入数据库
+async fn get_repeat_task_occurrences(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<OccurrencesQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    if query.from >= query.to {
+        return Err(ServerError::BadRequest("from must be before to".into()));
+    }
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let exceptions = RepeatTaskException::get_all(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let mut occurrences: Vec<Matter> = RepeatTask::get_active_tasks(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?
+        .iter()
+        .flat_map(|task| {
+            recurrence::expand_task(task, query.from, query.to, &timezone, &exceptions)
+        })
+        .collect();
+    occurrences.sort_by_key(|matter| matter.start_time);
+
+    Ok(Json(ApiResponse::success(occurrences)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NextOccurrenceQuery {
+    timezone: String,
+    after: Option<DateTime<Utc>>,
+}
+
+// 周期任务在 after（默认为现在）之后的下一次开始时间，停用或没有下一次时为 null
+async fn get_next_repeat_occurrence(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Query(query): Query<NextOccurrenceQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let task = RepeatTask::get_by_id(&state.db, &id)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ServerError::NotFound(format!("Repeat task {}", id)))?;
+    let next = task.next_occurrence(query.after.unwrap_or_else(Utc::now), &timezone);
+
+    Ok(Json(ApiResponse::success(next)))
+}
+
+async fn update_repeat_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Json(mut task): Json<RepeatTask>,
+) -> Result<impl IntoResponse, ServerError> {
+    let expected_updated_at = task.updated_at;
+    task.id = id;
+    task.updated_at = Utc::now();
+
+    let state = state.lock().await;
+    task.update(&state.db, expected_updated_at)?;
+
+    Ok(Json(ApiResponse::success(task)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DryRunQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// 从 JSON 数组批量导入重复任务
+async fn import_repeat_tasks(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<DryRunQuery>,
+    json: String,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let report = RepeatTask::import(&state.db, &json, query.dry_run)?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+async fn get_duplicate_repeat_titles(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let duplicates = RepeatTask::find_duplicate_titles(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(duplicates)))
+}
+
+async fn delete_repeat_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    RepeatTask::delete(&state.db, &id).map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+async fn get_repeat_task_exceptions(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let exceptions = RepeatTaskException::get_by_task(&state.db, &id)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(exceptions)))
+}
+
+// 跳过或修改周期任务某一天的事项，同一天已有例外时替换
+async fn add_repeat_task_exception(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Json(mut exception): Json<RepeatTaskException>,
+) -> Result<impl IntoResponse, ServerError> {
+    exception.task_id = id;
+    exception.created_at = Utc::now();
+
+    let state = state.lock().await;
+    RepeatTaskException::add(&state.db, &exception)?;
+
+    Ok(Json(ApiResponse::success(exception)))
+}
+
+async fn remove_repeat_task_exception(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path((id, date)): Path<(String, NaiveDate)>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let removed = RepeatTaskException::remove(&state.db, &id, date)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    if !removed {
+        return Err(ServerError::NotFound(format!(
+            "Exception of repeat task {} on {}",
+            id, date
+        )));
+    }
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+async fn update_repeat_task_status(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path((id, status)): Path<(String, i32)>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    RepeatTask::update_status(&state.db, &id, status)?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+// 启用周期任务，repeat_time 无效时返回 400
+async fn activate_repeat_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    RepeatTask::activate(&state.db, &id)?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+async fn archive_repeat_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    RepeatTask::archive(&state.db, &id)?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+// Todo 相关处理函数
+async fn create_todo(
     State(state): State<Arc<Mutex<AppState>>>,
     Json(mut todo): Json<Todo>,
 ) -> Result<impl IntoResponse, ServerError> {
@@ -500,6 +2049,39 @@ async fn create_todo(
     Ok(Json(ApiResponse::success(todo)))
 }
 
+// 从多行清单文本批量创建待办
+async fn import_todos(
+    State(state): State<Arc<Mutex<AppState>>>,
+    text: String,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let ids = Todo::import_checklist(&state.db, &text)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(ids)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CarryOverQuery {
+    timezone: String,
+    #[serde(default)]
+    bump: bool,
+}
+
+// 取出之前几天未完成的待办，bump 时将其排到最前
+async fn carry_over_todos(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<CarryOverQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let timezone = utils::parse_timezone(&query.timezone).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let todos = Todo::carry_over(&state.db, timezone, query.bump)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(todos)))
+}
+
 async fn get_todo(
     State(state): State<Arc<Mutex<AppState>>>,
     Path(id): Path<String>,
@@ -526,16 +2108,32 @@ async fn update_todo(
     Path(id): Path<String>,
     Json(mut todo): Json<Todo>,
 ) -> Result<impl IntoResponse, ServerError> {
+    let expected_updated_at = todo.updated_at;
     todo.id = id;
     todo.updated_at = Utc::now();
 
     let state = state.lock().await;
-    todo.update(&state.db)
-        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    todo.update(&state.db, expected_updated_at)?;
 
     Ok(Json(ApiResponse::success(todo)))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TodoStatusRequest {
+    status: String,
+}
+
+// 按 todo -> in_progress -> completed 的顺序修改状态，跳过中间状态时返回 409
+async fn set_todo_status(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Json(request): Json<TodoStatusRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    Todo::set_status(&state.db, &id, &request.status)?;
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
 async fn delete_todo(
     State(state): State<Arc<Mutex<AppState>>>,
     Path(id): Path<String>,
@@ -606,6 +2204,127 @@ async fn create_notification(
     Ok(Json(ApiResponse::success(notification)))
 }
 
+// 重复提醒相关处理函数
+async fn create_recurring_notification(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(mut notification): Json<RecurringNotification>,
+) -> Result<impl IntoResponse, ServerError> {
+    if notification.id.is_empty() {
+        notification.id = uuid::Uuid::new_v4().to_string();
+    }
+    notification.created_at = Utc::now();
+    notification.updated_at = Utc::now();
+
+    let state = state.lock().await;
+    RecurringNotification::create(&state.db, &notification)?;
+
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+async fn get_recurring_notification(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let notification = RecurringNotification::get_by_id(&state.db, &id)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ServerError::NotFound("RecurringNotification not found".into()))?;
+
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+async fn get_all_recurring_notifications(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let notifications = RecurringNotification::get_all(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(notifications)))
+}
+
+async fn update_recurring_notification(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+    Json(mut notification): Json<RecurringNotification>,
+) -> Result<impl IntoResponse, ServerError> {
+    notification.id = id;
+    notification.updated_at = Utc::now();
+
+    let state = state.lock().await;
+    notification.update(&state.db)?;
+
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+async fn delete_recurring_notification(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    RecurringNotification::delete(&state.db, &id)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::<()>::success(())))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleNotificationRequest {
+    title: String,
+    content: String,
+    fire_at: DateTime<Utc>,
+}
+
+// 创建一条在指定时间触发的自定义通知
+async fn schedule_notification(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Json(payload): Json<ScheduleNotificationRequest>,
+) -> Result<impl IntoResponse, ServerError> {
+    if payload.fire_at <= Utc::now() {
+        return Err(ServerError::BadRequest(
+            "fire_at must be in the future".into(),
+        ));
+    }
+
+    let state = state.lock().await;
+    let notification =
+        NotificationRecord::schedule(&state.db, &payload.title, &payload.content, payload.fire_at)
+            .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+async fn get_scheduled_notifications(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let notifications = NotificationRecord::get_scheduled(&state.db, Utc::now())
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(notifications)))
+}
+
+// 立即为即将开始的事项生成提醒，便于测试；调度器每次轮询也会执行
+async fn scan_matter_reminders(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let reminders = NotificationRecord::schedule_matter_reminders(&state.db, Utc::now())
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(reminders)))
+}
+
+async fn cancel_scheduled_notification(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    NotificationRecord::cancel_scheduled(&state.db, &id)?;
+
+    Ok(Json(ApiResponse::success(())))
+}
+
 async fn get_notification(
     State(state): State<Arc<Mutex<AppState>>>,
     Path(id): Path<String>,
@@ -640,6 +2359,94 @@ async fn get_unread_notifications(
     Ok(Json(ApiResponse::success(notifications)))
 }
 
+// 通知分页的默认和最大条数
+const DEFAULT_NOTIFICATION_PAGE_SIZE: i64 = 50;
+const MAX_NOTIFICATION_PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationFilterQuery {
+    status: Option<i32>,
+    type_: Option<i32>,
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: i64,
+}
+
+// 按状态和类型分页查询通知，返回当前页和总数
+async fn query_notifications(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<NotificationFilterQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let limit = query.limit.unwrap_or(DEFAULT_NOTIFICATION_PAGE_SIZE);
+    if !(1..=MAX_NOTIFICATION_PAGE_SIZE).contains(&limit) {
+        return Err(ServerError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_NOTIFICATION_PAGE_SIZE
+        )));
+    }
+    if query.offset < 0 {
+        return Err(ServerError::BadRequest(
+            "offset must not be negative".into(),
+        ));
+    }
+
+    let state = state.lock().await;
+    let items =
+        NotificationRecord::get_filtered(&state.db, query.status, query.type_, limit, query.offset)
+            .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    let total = NotificationRecord::count_filtered(&state.db, query.status, query.type_)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(NotificationPage {
+        items,
+        total,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentNotificationsQuery {
+    since: Option<DateTime<Utc>>,
+}
+
+async fn get_recent_unread_notifications(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<RecentNotificationsQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let since = match query.since {
+        Some(since) => since,
+        None => {
+            let last_seen = KVStore::get(&state.db, NOTIFICATION_LAST_SEEN_KEY, "")
+                .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+            DateTime::parse_from_rfc3339(&last_seen)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_default()
+        }
+    };
+
+    let notifications = NotificationRecord::get_recent_unread(&state.db, since)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+    KVStore::set(
+        &state.db,
+        NOTIFICATION_LAST_SEEN_KEY,
+        &Utc::now().to_rfc3339(),
+    )
+    .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(notifications)))
+}
+
+// 通知中心按类型分组的总数和未读数
+async fn get_notification_summary(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let summary = NotificationRecord::summary_by_type(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(summary)))
+}
+
 async fn update_notification(
     State(state): State<Arc<Mutex<AppState>>>,
     Path(id): Path<String>,
@@ -698,6 +2505,40 @@ async fn mark_all_notifications_as_read(
     Ok(Json(ApiResponse::<()>::success(())))
 }
 
+// 将关联到某个任务的通知标记为已读，返回更新的条数
+async fn mark_notifications_as_read_by_task(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(task_id): Path<String>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let count = NotificationRecord::mark_as_read_by_task(&state.db, &task_id)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(count)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteByTypeParams {
+    #[serde(default)]
+    only_read: bool,
+}
+
+// 按类型批量删除通知
+async fn delete_notifications_by_type(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(type_): Path<i32>,
+    Query(params): Query<DeleteByTypeParams>,
+) -> Result<impl IntoResponse, ServerError> {
+    let type_ = NotificationType::try_from(type_).map_err(ServerError::BadRequest)?;
+
+    let state = state.lock().await;
+    let count = database::with_auto_backup(&state.db, "delete_notifications", || {
+        NotificationRecord::delete_by_type(&state.db, type_, params.only_read)
+    })?;
+
+    Ok(Json(ApiResponse::success(count)))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct QueryFieldParams {
     field: String,
@@ -710,37 +2551,52 @@ fn default_exact_match() -> bool {
     false
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SearchMattersQuery {
+    q: String,
+}
+
+// 在标题、描述和标签中全文搜索事项
+async fn search_matters(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Query(query): Query<SearchMattersQuery>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let matters = Matter::search(&state.db, &query.q)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(matters)))
+}
+
+// 检查全文索引是否与未删除的事项一致
+async fn verify_search_index(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let status = database::verify_search_index(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(status)))
+}
+
+// 从 matter 表重建全文索引，返回索引的行数
+async fn rebuild_search_index(
+    State(state): State<Arc<Mutex<AppState>>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let state = state.lock().await;
+    let indexed = database::rebuild_search_index(&state.db)
+        .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::success(indexed)))
+}
+
 async fn query_matter_by_field(
     State(state): State<Arc<Mutex<AppState>>>,
     Query(params): Query<QueryFieldParams>,
 ) -> Result<impl IntoResponse, ServerError> {
-    // 验证字段名是否合法
-    let valid_fields = vec![
-        "id",
-        "title",
-        "description",
-        "tags",
-        "priority",
-        "type",
-        "reserved_1",
-        "reserved_2",
-        "reserved_3",
-        "reserved_4",
-        "reserved_5",
-    ];
-
-    if !valid_fields.contains(&params.field.as_str()) {
-        return Err(ServerError::BadRequest(format!(
-            "Invalid field name: {}. Valid fields are: {}",
-            params.field,
-            valid_fields.join(", ")
-        )));
-    }
-
     let state = state.lock().await;
     let matters =
-        Matter::query_by_field(&state.db, &params.field, &params.value, params.exact_match)
-            .map_err(|e| ServerError::DatabaseError(e.to_string()))?;
+        Matter::query_by_field(&state.db, &params.field, &params.value, params.exact_match)?;
 
     Ok(Json(ApiResponse::success(matters)))
 }
@@ -0,0 +1,535 @@
+use crate::database::{DbError, ImportItemError, ImportReport, Matter, SafeConnection};
+use crate::export::MATTER_CSV_COLUMNS;
+use crate::utils;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// 从 .ics 中解析出的一个 VEVENT，只保留导入需要的属性
+#[derive(Debug, Default)]
+struct IcalEvent {
+    uid: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    categories: Vec<String>,
+    dtstart: Option<IcalProperty>,
+    dtend: Option<IcalProperty>,
+    duration: Option<String>,
+}
+
+// 属性的参数和值，例如 DTSTART;TZID=Asia/Shanghai:20240101T090000
+#[derive(Debug)]
+struct IcalProperty {
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl IcalProperty {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// 合并折行：以空格或制表符开头的行是上一行的延续
+fn unfold_lines(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+// 拆分为 (属性名, 属性)，参数值中带引号时可以包含 ":" 和 ";"
+fn parse_line(line: &str) -> Option<(String, IcalProperty)> {
+    let mut in_quotes = false;
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut value_start = None;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                parts.push(&line[start..index]);
+                start = index + 1;
+            }
+            ':' if !in_quotes => {
+                parts.push(&line[start..index]);
+                value_start = Some(index + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let value = line[value_start?..].to_string();
+    let name = parts.first()?.to_ascii_uppercase();
+    let params = parts[1..]
+        .iter()
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.trim_matches('"').to_string()))
+        .collect();
+    Some((name, IcalProperty { params, value }))
+}
+
+// 还原 TEXT 类型值中的转义
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+// 按未转义的逗号拆分，用于 CATEGORIES
+fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            current.push('\\');
+            current.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ',' {
+            items.push(unescape_text(&current));
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    items.push(unescape_text(&current));
+    items
+}
+
+// 提取顶层的 VEVENT，忽略其中嵌套的 VALARM 等组件
+fn parse_events(ics: &str) -> Vec<IcalEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<IcalEvent> = None;
+    let mut nested = 0;
+    for line in unfold_lines(ics) {
+        let Some((name, property)) = parse_line(&line) else {
+            continue;
+        };
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if property.value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(IcalEvent::default());
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if property.value.eq_ignore_ascii_case("VEVENT") => {
+                events.extend(current.take());
+            }
+            (_, Some(event)) if nested == 0 => match name.as_str() {
+                "UID" => event.uid = Some(property.value),
+                "SUMMARY" => event.summary = Some(unescape_text(&property.value)),
+                "DESCRIPTION" => event.description = Some(unescape_text(&property.value)),
+                "CATEGORIES" => event.categories.extend(split_text_list(&property.value)),
+                "DTSTART" => event.dtstart = Some(property),
+                "DTEND" => event.dtend = Some(property),
+                "DURATION" => event.duration = Some(property.value),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    events
+}
+
+// 解析 DATE 或 DATE-TIME，返回时间以及是否为全天（DATE）。
+// 带 Z 的为 UTC，带 TZID 的按该时区，其余的浮动时间按 timezone 解析
+fn parse_time(property: &IcalProperty, timezone: Tz) -> Result<(DateTime<Utc>, bool), String> {
+    let value = property.value.trim();
+    let is_date = property
+        .param("VALUE")
+        .is_some_and(|kind| kind.eq_ignore_ascii_case("DATE"))
+        || value.len() == 8;
+    if is_date {
+        let day = NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map_err(|_| format!("Invalid date: {}", value))?;
+        return Ok((utils::local_midnight(day, timezone), true));
+    }
+
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map_err(|_| format!("Invalid time: {}", value))?;
+        return Ok((time.and_utc(), false));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map_err(|_| format!("Invalid time: {}", value))?;
+    let timezone = match property.param("TZID") {
+        Some(tzid) => utils::parse_timezone(tzid)?,
+        None => timezone,
+    };
+    Ok((utils::local_to_utc(local, timezone), false))
+}
+
+// 解析 DURATION，例如 PT1H30M、P1D、P2W，不支持负数
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration: {}", value);
+    let rest = value
+        .trim()
+        .trim_start_matches('+')
+        .strip_prefix('P')
+        .ok_or_else(invalid)?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let amount: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(amount),
+                    ('D', false) => Duration::days(amount),
+                    ('H', true) => Duration::hours(amount),
+                    ('M', true) => Duration::minutes(amount),
+                    ('S', true) => Duration::seconds(amount),
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+// 将一个 VEVENT 转换为事项：SUMMARY 作为标题，CATEGORIES 作为标签，没有 UID 时生成新的 id。
+// 没有 DTEND 时使用 DURATION，两者都没有时全天事件持续一天，其余事件结束于开始时间
+fn event_to_matter(event: IcalEvent, timezone: Tz, now: DateTime<Utc>) -> Result<Matter, String> {
+    let title = event.summary.as_deref().map(str::trim).unwrap_or("");
+    if title.is_empty() {
+        return Err("Event has no SUMMARY".to_string());
+    }
+    let dtstart = event.dtstart.as_ref().ok_or("Event has no DTSTART")?;
+    let (start_time, all_day) = parse_time(dtstart, timezone)?;
+    let end_time = match (&event.dtend, &event.duration) {
+        (Some(dtend), _) => parse_time(dtend, timezone)?.0,
+        (None, Some(duration)) => start_time + parse_duration(duration)?,
+        (None, None) if all_day => start_time + Duration::days(1),
+        (None, None) => start_time,
+    };
+    if end_time < start_time {
+        return Err(format!(
+            "DTEND {} is before DTSTART {}",
+            end_time, start_time
+        ));
+    }
+
+    let mut tags: Vec<&str> = Vec::new();
+    for tag in event.categories.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    Ok(Matter {
+        id: event
+            .uid
+            .map(|uid| uid.trim().to_string())
+            .filter(|uid| !uid.is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        title: title.to_string(),
+        description: event.description.filter(|d| !d.trim().is_empty()),
+        tags: (!tags.is_empty()).then(|| tags.join(",")),
+        start_time,
+        end_time,
+        priority: 0,
+        type_: 0,
+        created_at: now,
+        updated_at: now,
+        reserved_1: None,
+        reserved_2: None,
+        reserved_3: None,
+        reserved_4: None,
+        reserved_5: None,
+    })
+}
+
+/// 从 iCalendar 文本导入事项，不带时区的时间按 timezone 解析。
+/// UID 与已有事项（包括回收站中的事项）或同一文件中之前的事件相同时跳过；
+/// 无法导入的事件写入 errors，index 为事件在文件中的序号。重复规则不展开，只导入第一次
+pub fn import_ical(
+    conn: &Arc<SafeConnection>,
+    ics: &str,
+    timezone: Tz,
+) -> Result<ImportReport, DbError> {
+    if !ics
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with("BEGIN:VCALENDAR")
+    {
+        return Err(DbError::InvalidArgument(
+            "Not an iCalendar file".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+    let mut report = ImportReport {
+        imported: Vec::new(),
+        errors: Vec::new(),
+        dry_run: false,
+    };
+    let mut seen = HashSet::new();
+    for (index, event) in parse_events(ics).into_iter().enumerate() {
+        let result = event_to_matter(event, timezone, now).and_then(|matter| {
+            let exists = Matter::get_by_id(conn, &matter.id).map_err(|e| e.to_string())?;
+            if exists.is_some() || !seen.insert(matter.id.clone()) {
+                return Err(format!("Matter {} already exists", matter.id));
+            }
+            Matter::create(conn, &matter).map_err(|e| e.to_string())?;
+            Ok(matter.id)
+        });
+
+        match result {
+            Ok(id) => report.imported.push(id),
+            Err(message) => report.errors.push(ImportItemError { index, message }),
+        }
+    }
+    Ok(report)
+}
+
+// 解析 CSV 文本为行，引号内可以包含逗号、换行和加倍的引号。空行会被跳过
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = csv.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n' | '\r', false) => {
+                row.push(std::mem::take(&mut field));
+                if row.len() > 1 || !row[0].is_empty() {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quoted field".to_string());
+    }
+    if !row.is_empty() || !field.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_csv_time(value: &str, column: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid {}: {}", column, value))
+}
+
+// 将一行转换为事项，columns 为列名到下标的映射。
+// id 为空时生成新的 id，没有 created_at / updated_at 时使用当前时间
+fn row_to_matter(
+    row: &[String],
+    columns: &[(&str, usize)],
+    now: DateTime<Utc>,
+) -> Result<Matter, String> {
+    let field = |name: &str| {
+        columns
+            .iter()
+            .find(|(column, _)| *column == name)
+            .and_then(|(_, index)| row.get(*index))
+            .map(String::as_str)
+            .unwrap_or("")
+    };
+    let optional_time = |name: &str| match field(name).trim() {
+        "" => Ok(now),
+        value => parse_csv_time(value, name),
+    };
+    let integer = |name: &str| match field(name).trim() {
+        "" => Ok(0),
+        value => value
+            .parse::<i32>()
+            .map_err(|_| format!("Invalid {}: {}", name, value)),
+    };
+
+    let title = field("title");
+    if title.trim().is_empty() {
+        return Err("Row has no title".to_string());
+    }
+    let start_time = parse_csv_time(field("start_time"), "start_time")?;
+    let end_time = parse_csv_time(field("end_time"), "end_time")?;
+    let id = field("id").trim();
+
+    Ok(Matter {
+        id: if id.is_empty() {
+            uuid::Uuid::new_v4().to_string()
+        } else {
+            id.to_string()
+        },
+        title: title.to_string(),
+        description: Some(field("description").to_string()).filter(|d| !d.is_empty()),
+        tags: Some(field("tags").to_string()).filter(|t| !t.is_empty()),
+        start_time,
+        end_time,
+        priority: integer("priority")?,
+        type_: integer("type")?,
+        created_at: optional_time("created_at")?,
+        updated_at: optional_time("updated_at")?,
+        reserved_1: None,
+        reserved_2: None,
+        reserved_3: None,
+        reserved_4: None,
+        reserved_5: None,
+    })
+}
+
+/// 从 matters_to_csv 导出的 CSV 导入事项，列按表头名称匹配，顺序可以不同。
+/// 必须包含 title、start_time 和 end_time 列；id 与已有事项或之前的行相同时跳过，
+/// 无法导入的行写入 errors，index 为去掉表头后的行号（从 0 开始）
+pub fn matters_from_csv(conn: &Arc<SafeConnection>, csv: &str) -> Result<ImportReport, DbError> {
+    let mut rows = parse_csv(csv)
+        .map_err(DbError::InvalidArgument)?
+        .into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| DbError::InvalidArgument("CSV is empty".to_string()))?;
+    let columns: Vec<(&str, usize)> = MATTER_CSV_COLUMNS
+        .iter()
+        .filter_map(|column| {
+            let index = header
+                .iter()
+                .position(|name| name.trim().eq_ignore_ascii_case(column))?;
+            Some((*column, index))
+        })
+        .collect();
+    for required in ["title", "start_time", "end_time"] {
+        if !columns.iter().any(|(column, _)| *column == required) {
+            return Err(DbError::InvalidArgument(format!(
+                "Missing column: {}",
+                required
+            )));
+        }
+    }
+
+    let now = Utc::now();
+    let mut report = ImportReport {
+        imported: Vec::new(),
+        errors: Vec::new(),
+        dry_run: false,
+    };
+    let mut seen = HashSet::new();
+    for (index, row) in rows.enumerate() {
+        let result = row_to_matter(&row, &columns, now).and_then(|matter| {
+            let exists = Matter::get_by_id(conn, &matter.id).map_err(|e| e.to_string())?;
+            if exists.is_some() || !seen.insert(matter.id.clone()) {
+                return Err(format!("Matter {} already exists", matter.id));
+            }
+            Matter::create(conn, &matter).map_err(|e| e.to_string())?;
+            Ok(matter.id)
+        });
+
+        match result {
+            Ok(id) => report.imported.push(id),
+            Err(message) => report.errors.push(ImportItemError { index, message }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::open_in_memory;
+    use crate::export::matters_to_csv;
+    use chrono::TimeZone;
+
+    fn matter(id: &str, title: &str, description: Option<&str>, tags: Option<&str>) -> Matter {
+        let start_time = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+        Matter {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: description.map(str::to_string),
+            tags: tags.map(str::to_string),
+            start_time,
+            end_time: start_time + Duration::milliseconds(5_400_123),
+            priority: 2,
+            type_: 3,
+            created_at: start_time - Duration::days(1),
+            updated_at: start_time,
+            reserved_1: None,
+            reserved_2: None,
+            reserved_3: None,
+            reserved_4: None,
+            reserved_5: None,
+        }
+    }
+
+    #[test]
+    fn csv_export_then_import_keeps_matters() {
+        let source = open_in_memory();
+        let matters = [
+            matter("plain", "Plain", None, None),
+            matter(
+                "quoted",
+                "Review \"Q1\", part 1",
+                Some("line one\nline two, with comma"),
+                Some("work,review"),
+            ),
+            matter("unicode", "读书", Some("  padded  "), Some("learning")),
+        ];
+        Matter::create_batch(&source, &matters).unwrap();
+        let csv = matters_to_csv(&Matter::get_all(&source).unwrap());
+
+        let target = open_in_memory();
+        let report = matters_from_csv(&target, &csv).unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.imported.len(), matters.len());
+
+        for expected in &matters {
+            let actual = Matter::get_by_id(&target, &expected.id).unwrap().unwrap();
+            assert_eq!(actual.title, expected.title);
+            assert_eq!(actual.description, expected.description);
+            assert_eq!(actual.tags, expected.tags);
+            assert_eq!(actual.start_time, expected.start_time);
+            assert_eq!(actual.end_time, expected.end_time);
+            assert_eq!(actual.priority, expected.priority);
+            assert_eq!(actual.type_, expected.type_);
+            assert_eq!(actual.created_at, expected.created_at);
+            assert_eq!(actual.updated_at, expected.updated_at);
+        }
+
+        // 再次导入时 id 已存在，全部跳过
+        let again = matters_from_csv(&target, &csv).unwrap();
+        assert!(again.imported.is_empty());
+        assert_eq!(again.errors.len(), matters.len());
+    }
+}
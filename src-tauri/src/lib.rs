@@ -2,14 +2,36 @@
 
 mod autostart;
 mod database;
+mod demo;
+mod error;
+mod export;
+mod import;
 mod http_server;
 mod models;
+mod recurrence;
+mod scheduler;
+mod settings;
+mod stats;
 mod utils;
 mod tray;
 mod calendar;
+mod toggl;
 
+use crate::database::{
+    BackedUp, ImportReport, KVStore, Matter, MatterOverlap, NotificationPage, NotificationRecord,
+    RepeatTask, RepeatTaskException, SafeConnection, SearchIndexStatus, Tag, TagUsage, Todo,
+    TrashedMatter,
+};
+use crate::demo::DemoDataReport;
+use crate::error::AppError;
 use crate::http_server::start_http_server;
+use crate::settings::AppSettings;
+use crate::stats::TimeStats;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::path::Path;
+use std::sync::Arc;
 use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_log::{Target, TargetKind, WEBVIEW_TARGET};
@@ -31,6 +53,557 @@ async fn show_main_window(app: tauri::AppHandle) {
     }
 }
 
+// 事项的增删改查命令，与 HTTP 接口共用同一个数据库连接
+#[tauri::command]
+fn create_matter(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    matter: Matter,
+) -> Result<(), AppError> {
+    Matter::create(&state, &matter).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn create_matters_batch(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    matters: Vec<Matter>,
+) -> Result<(), AppError> {
+    Matter::create_batch(&state, &matters).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_matter_by_id(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<Option<Matter>, AppError> {
+    Matter::get_by_id(&state, &id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_all_matters(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<Matter>, AppError> {
+    Matter::get_all(&state).map_err(AppError::from)
+}
+
+// matter.updated_at 为读取时的值，之后被修改过时返回 conflict
+#[tauri::command]
+fn update_matter(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    mut matter: Matter,
+) -> Result<Matter, AppError> {
+    let expected_updated_at = matter.updated_at;
+    matter.updated_at = Utc::now();
+    matter.update(&state, expected_updated_at)?;
+    Ok(matter)
+}
+
+// 回收站：删除的事项移入回收站，可以通过 restore_matter 恢复
+#[tauri::command]
+fn delete_matter(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<(), AppError> {
+    Matter::delete(&state, &id).map_err(AppError::from)
+}
+
+// 彻底删除事项，不经过回收站，无法恢复
+#[tauri::command]
+fn purge_matter(state: tauri::State<'_, Arc<SafeConnection>>, id: String) -> Result<(), AppError> {
+    Matter::hard_delete(&state, &id).map_err(AppError::from)
+}
+
+// 彻底删除 older_than 之前移入回收站的事项，返回删除的数量
+#[tauri::command]
+fn purge_trashed_matters(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    older_than: DateTime<Utc>,
+) -> Result<usize, AppError> {
+    Matter::purge_deleted(&state, older_than).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn restore_matter(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<(), AppError> {
+    Matter::restore(&state, &id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn list_trashed_matters(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<TrashedMatter>, AppError> {
+    Matter::get_trashed(&state).map_err(AppError::from)
+}
+
+// 有多个进行中的事项时只保留最近开始的一个，返回被结束的事项 id
+#[tauri::command]
+fn fix_multiple_running(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<String>, AppError> {
+    Matter::fix_multiple_running(&state).map_err(AppError::from)
+}
+
+// day 所在本地日的事项，tz_offset_minutes 为相对 UTC 的偏移分钟数
+#[tauri::command]
+fn get_matters_for_day(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    day: DateTime<Utc>,
+    tz_offset_minutes: i32,
+) -> Result<Vec<Matter>, AppError> {
+    Matter::get_for_day(&state, day, tz_offset_minutes).map_err(AppError::from)
+}
+
+// 时间重叠的事项对，ignore_markers 为 true 时忽略零时长和全天的事项
+#[tauri::command]
+fn get_matter_conflicts(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    ignore_markers: Option<bool>,
+) -> Result<Vec<MatterOverlap>, AppError> {
+    Matter::find_overlapping_pairs(&state, start, end, ignore_markers.unwrap_or(false))
+        .map_err(AppError::from)
+}
+
+// 在标题、描述和标签中全文搜索事项
+#[tauri::command]
+async fn search_matters(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    query: String,
+) -> Result<Vec<Matter>, AppError> {
+    Matter::search(&state, &query).map_err(AppError::from)
+}
+
+// 检查全文索引与未删除的事项是否一致
+#[tauri::command]
+fn verify_search_index(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<SearchIndexStatus, AppError> {
+    database::verify_search_index(&state).map_err(AppError::from)
+}
+
+// 绕过触发器导入数据后重建全文索引，返回索引的行数
+#[tauri::command]
+fn rebuild_search_index(state: tauri::State<'_, Arc<SafeConnection>>) -> Result<i64, AppError> {
+    database::rebuild_search_index(&state).map_err(AppError::from)
+}
+
+// 待办的增删改查命令，状态修改通过 set_todo_status 校验转换是否合法
+#[tauri::command]
+fn create_todo(state: tauri::State<'_, Arc<SafeConnection>>, todo: Todo) -> Result<(), AppError> {
+    Todo::create(&state, &todo).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_todo_by_id(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<Option<Todo>, AppError> {
+    Todo::get_by_id(&state, &id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_all_todos(state: tauri::State<'_, Arc<SafeConnection>>) -> Result<Vec<Todo>, AppError> {
+    Todo::get_all(&state).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn update_todo(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    mut todo: Todo,
+) -> Result<Todo, AppError> {
+    let expected_updated_at = todo.updated_at;
+    todo.updated_at = Utc::now();
+    todo.update(&state, expected_updated_at)?;
+    Ok(todo)
+}
+
+#[tauri::command]
+fn delete_todo(state: tauri::State<'_, Arc<SafeConnection>>, id: String) -> Result<(), AppError> {
+    Todo::delete(&state, &id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn set_todo_status(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+    status: String,
+) -> Result<(), AppError> {
+    Todo::set_status(&state, &id, &status).map_err(AppError::from)
+}
+
+// 周期任务的增删改查命令，开启 unique_repeat_titles 时创建同名任务返回 conflict
+#[tauri::command]
+fn create_repeat_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    task: RepeatTask,
+) -> Result<(), AppError> {
+    RepeatTask::create(&state, &task).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_repeat_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<Option<RepeatTask>, AppError> {
+    RepeatTask::get_by_id(&state, &id).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_all_repeat_tasks(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<RepeatTask>, AppError> {
+    RepeatTask::get_all(&state).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_active_repeat_tasks(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<RepeatTask>, AppError> {
+    RepeatTask::get_active_tasks(&state).map_err(AppError::from)
+}
+
+// task.updated_at 为读取时的值，之后被修改过时返回 conflict
+#[tauri::command]
+fn update_repeat_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    mut task: RepeatTask,
+) -> Result<RepeatTask, AppError> {
+    let expected_updated_at = task.updated_at;
+    task.updated_at = Utc::now();
+    task.update(&state, expected_updated_at)?;
+    Ok(task)
+}
+
+#[tauri::command]
+fn delete_repeat_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<(), AppError> {
+    RepeatTask::delete(&state, &id).map_err(AppError::from)
+}
+
+// 启用（1）时会校验 repeat_time
+#[tauri::command]
+fn update_repeat_task_status(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+    status: i32,
+) -> Result<(), AppError> {
+    RepeatTask::update_status(&state, &id, status).map_err(AppError::from)
+}
+
+// 周期任务的下一次开始时间，按本机时区计算
+#[tauri::command]
+fn get_next_occurrence(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let task = RepeatTask::get_by_id(&state, &id)?
+        .ok_or_else(|| AppError::NotFound(format!("Repeat task {}", id)))?;
+    Ok(task.next_occurrence(Utc::now(), &Local))
+}
+
+// 将所有启用的周期任务展开为 [from, to) 内的具体事项，按本机时区计算，不写入数据库
+#[tauri::command]
+fn get_repeat_task_occurrences(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<Matter>, AppError> {
+    if from >= to {
+        return Err(AppError::Validation("from must be before to".to_string()));
+    }
+    let exceptions = RepeatTaskException::get_all(&state)?;
+    let mut occurrences: Vec<Matter> = RepeatTask::get_active_tasks(&state)?
+        .iter()
+        .flat_map(|task| recurrence::expand_task(task, from, to, &Local, &exceptions))
+        .collect();
+    occurrences.sort_by_key(|matter| matter.start_time);
+    Ok(occurrences)
+}
+
+// 启用周期任务，repeat_time 无效时返回 validation
+#[tauri::command]
+fn activate_repeat_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<(), AppError> {
+    RepeatTask::activate(&state, &id).map_err(AppError::from)
+}
+
+// 按状态列出周期任务，用于管理已归档的任务
+#[tauri::command]
+fn get_repeat_tasks_by_status(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    status: i32,
+) -> Result<Vec<RepeatTask>, AppError> {
+    RepeatTask::get_by_status(&state, status).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn archive_repeat_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<(), AppError> {
+    RepeatTask::archive(&state, &id).map_err(AppError::from)
+}
+
+// 跳过或修改周期任务某一天的事项，同一天已有例外时替换
+#[tauri::command]
+fn add_repeat_exception(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    exception: RepeatTaskException,
+) -> Result<(), AppError> {
+    RepeatTaskException::add(&state, &exception).map_err(AppError::from)
+}
+
+// 删除例外后这一天恢复按规则展开，返回是否存在该例外
+#[tauri::command]
+fn remove_repeat_exception(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    task_id: String,
+    occurrence_date: NaiveDate,
+) -> Result<bool, AppError> {
+    RepeatTaskException::remove(&state, &task_id, occurrence_date).map_err(AppError::from)
+}
+
+// 通知中心按状态和类型分页加载通知
+#[tauri::command]
+fn query_notifications(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    status: Option<i32>,
+    type_: Option<i32>,
+    limit: i64,
+    offset: i64,
+) -> Result<NotificationPage, AppError> {
+    let items = NotificationRecord::get_filtered(&state, status, type_, limit, offset)?;
+    let total = NotificationRecord::count_filtered(&state, status, type_)?;
+    Ok(NotificationPage { items, total })
+}
+
+// 任务完成时清除与它关联的提醒，返回标记为已读的条数
+#[tauri::command]
+fn mark_notifications_read_by_task(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    task_id: String,
+) -> Result<usize, AppError> {
+    NotificationRecord::mark_as_read_by_task(&state, &task_id).map_err(AppError::from)
+}
+
+// 尚未触发的定时通知，按触发时间排序
+#[tauri::command]
+fn get_scheduled_notifications(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<NotificationRecord>, AppError> {
+    NotificationRecord::get_scheduled(&state, Utc::now()).map_err(AppError::from)
+}
+
+// 已经触发或不存在时返回 not_found
+#[tauri::command]
+fn cancel_scheduled_notification(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    id: String,
+) -> Result<(), AppError> {
+    NotificationRecord::cancel_scheduled(&state, &id).map_err(AppError::from)
+}
+
+// 标签的增删查命令，touch_tag 更新最后使用时间
+#[tauri::command]
+fn create_tag(state: tauri::State<'_, Arc<SafeConnection>>, name: String) -> Result<(), AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("Tag name must not be empty".to_string()));
+    }
+    Tag::create(&state, name).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn get_all_tags(state: tauri::State<'_, Arc<SafeConnection>>) -> Result<Vec<Tag>, AppError> {
+    Tag::get_all(&state).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn touch_tag(state: tauri::State<'_, Arc<SafeConnection>>, name: String) -> Result<(), AppError> {
+    Tag::update_last_used_at(&state, &name).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn delete_tag(state: tauri::State<'_, Arc<SafeConnection>>, name: String) -> Result<(), AppError> {
+    Tag::delete(&state, &name).map_err(AppError::from)
+}
+
+// 所有设置，缺少的键使用默认值
+#[tauri::command]
+fn get_settings(state: tauri::State<'_, Arc<SafeConnection>>) -> Result<AppSettings, AppError> {
+    AppSettings::load(&state).map_err(AppError::from)
+}
+
+// 校验后一次性保存所有设置
+#[tauri::command]
+fn save_settings(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    settings: AppSettings,
+) -> Result<(), AppError> {
+    settings.save(&state).map_err(AppError::from)
+}
+
+// 以 JSON 形式读写键值对，用于保存设置对象
+#[tauri::command]
+fn kv_set_json(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), AppError> {
+    KVStore::set_json(&state, &key, &value).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn kv_get_json(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    key: String,
+) -> Result<Option<serde_json::Value>, AppError> {
+    KVStore::get_json(&state, &key).map_err(AppError::from)
+}
+
+// 按使用次数排序的标签，用于标签推荐
+#[tauri::command]
+fn get_tags_with_counts(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<TagUsage>, AppError> {
+    Tag::get_with_counts(&state).map_err(AppError::from)
+}
+
+// 区间内按标签和事项类型汇总的时长
+#[tauri::command]
+fn get_time_stats(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<TimeStats, AppError> {
+    if start >= end {
+        return Err(AppError::Validation("start must be before end".to_string()));
+    }
+    stats::time_stats(&state, start, end).map_err(AppError::from)
+}
+
+// 手动扫描即将开始的事项并生成提醒，提醒会在调度器下一次轮询时弹出
+#[tauri::command]
+fn scan_matter_reminders(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Vec<NotificationRecord>, AppError> {
+    NotificationRecord::schedule_matter_reminders(&state, Utc::now()).map_err(AppError::from)
+}
+
+// 将时间范围内的事项导出为 iCalendar 文本
+#[tauri::command]
+fn export_matters_ical(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<String, AppError> {
+    let matters = Matter::get_by_time_range(&state, start, end)?;
+    let repeat_tasks = RepeatTask::get_all(&state)?;
+    Ok(export::matters_to_ical(&matters, &repeat_tasks))
+}
+
+// 通过文件对话框选择 .ics 文件并导入事项，取消选择时返回 None
+#[tauri::command]
+async fn import_matters_ical(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    timezone: String,
+) -> Result<Option<ImportReport>, AppError> {
+    let timezone = utils::parse_timezone(&timezone).map_err(AppError::Validation)?;
+    let Some(file) = app
+        .dialog()
+        .file()
+        .add_filter("iCalendar", &["ics"])
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+
+    let path = file.into_path().map_err(|e| AppError::Io(e.to_string()))?;
+    let ics = std::fs::read_to_string(path)?;
+    import::import_ical(&state, &ics, timezone)
+        .map(Some)
+        .map_err(AppError::from)
+}
+
+// 将时间范围内的事项导出为 CSV 文本
+#[tauri::command]
+fn export_matters_csv(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<String, AppError> {
+    let matters = Matter::get_by_time_range(&state, start, end)?;
+    Ok(export::matters_to_csv(&matters))
+}
+
+// 通过文件对话框选择 .csv 文件并导入事项，取消选择时返回 None
+#[tauri::command]
+async fn import_matters_csv(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<Option<ImportReport>, AppError> {
+    let Some(file) = app
+        .dialog()
+        .file()
+        .add_filter("CSV", &["csv"])
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+
+    let path = file.into_path().map_err(|e| AppError::Io(e.to_string()))?;
+    let csv = std::fs::read_to_string(path)?;
+    import::matters_from_csv(&state, &csv)
+        .map(Some)
+        .map_err(AppError::from)
+}
+
+// 将整个数据库备份到 dest_path，应用运行中也可以安全执行
+#[tauri::command]
+fn backup_database(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    dest_path: String,
+) -> Result<(), AppError> {
+    database::backup_database(&state, Path::new(&dest_path)).map_err(AppError::from)
+}
+
+// 用备份的数据库文件替换当前数据库，完成后前端需要重新加载所有数据
+#[tauri::command]
+fn restore_database(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    src_path: String,
+) -> Result<BackedUp<()>, AppError> {
+    database::with_auto_backup(&state, "restore_database", || {
+        database::restore_database(&state, Path::new(&src_path))
+    })
+    .map_err(AppError::from)
+}
+
+// 示例数据，便于新用户上手；已有数据时需要 force 才会生成
+#[tauri::command]
+fn seed_demo_data(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+    timezone: String,
+    force: bool,
+) -> Result<DemoDataReport, AppError> {
+    let timezone = utils::parse_timezone(&timezone).map_err(AppError::Validation)?;
+    demo::seed_demo_data(&state, timezone, force).map_err(AppError::from)
+}
+
+#[tauri::command]
+fn clear_demo_data(
+    state: tauri::State<'_, Arc<SafeConnection>>,
+) -> Result<DemoDataReport, AppError> {
+    demo::clear_demo_data(&state).map_err(AppError::from)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let logger_builder = tauri_plugin_log::Builder::new()
@@ -80,6 +653,65 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             auto_launch,
             show_main_window,
+            create_matter,
+            create_matters_batch,
+            get_matter_by_id,
+            get_all_matters,
+            update_matter,
+            delete_matter,
+            purge_matter,
+            purge_trashed_matters,
+            restore_matter,
+            list_trashed_matters,
+            fix_multiple_running,
+            get_matters_for_day,
+            get_matter_conflicts,
+            search_matters,
+            verify_search_index,
+            rebuild_search_index,
+            create_todo,
+            get_todo_by_id,
+            get_all_todos,
+            update_todo,
+            delete_todo,
+            set_todo_status,
+            create_repeat_task,
+            get_repeat_task,
+            get_all_repeat_tasks,
+            get_active_repeat_tasks,
+            update_repeat_task,
+            delete_repeat_task,
+            update_repeat_task_status,
+            get_next_occurrence,
+            get_repeat_task_occurrences,
+            activate_repeat_task,
+            get_repeat_tasks_by_status,
+            archive_repeat_task,
+            add_repeat_exception,
+            remove_repeat_exception,
+            query_notifications,
+            scan_matter_reminders,
+            mark_notifications_read_by_task,
+            get_scheduled_notifications,
+            cancel_scheduled_notification,
+            create_tag,
+            get_all_tags,
+            touch_tag,
+            delete_tag,
+            get_settings,
+            save_settings,
+            kv_set_json,
+            kv_get_json,
+            get_tags_with_counts,
+            get_time_stats,
+            export_matters_ical,
+            import_matters_ical,
+            export_matters_csv,
+            import_matters_csv,
+            backup_database,
+            restore_database,
+            seed_demo_data,
+            clear_demo_data,
             calendar::get_calendar_events,
             calendar::request_calendar_access,
             calendar::get_calendar_permission_status,
@@ -87,8 +719,30 @@ pub fn run() {
         ])
         .setup(|app| {
             try_register_tray_icon(app).unwrap();
-            let db = database::initialize_database(&app.handle()).unwrap();
-            if let Err(e) = start_http_server(8523, db.clone()) {
+            let db = match database::initialize_database(&app.handle()) {
+                Ok(db) => {
+                    scheduler::start_scheduler(app.handle().clone(), db.clone());
+                    db
+                }
+                // 降级为只读模式：仍可查看数据，但不启动会写库的调度器
+                Err(e) => {
+                    log::error!("Failed to initialize database: {}", e);
+                    match database::open_read_only_database(&app.handle()) {
+                        Ok(db) => {
+                            log::warn!("Database opened in read-only mode");
+                            db
+                        }
+                        // 没有数据库时所有命令都不可用，让启动直接失败
+                        Err(e) => {
+                            log::error!("Failed to open database read-only: {}", e);
+                            return Err(e.into());
+                        }
+                    }
+                }
+            };
+            scheduler::forward_notification_changes(app.handle().clone(), &db);
+            app.manage(db.clone());
+            if let Err(e) = start_http_server(8523, db) {
                 log::error!("Failed to start HTTP server: {}", e);
             }
             Ok(())
@@ -0,0 +1,327 @@
+use crate::database::{Matter, RepeatExceptionAction, RepeatTask, RepeatTaskException};
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use serde::Deserialize;
+
+// 排除节假日标志位，与前端 EXCLUDE_HOLIDAYS_BIT 保持一致
+pub const EXCLUDE_HOLIDAYS_BIT: u8 = 1 << 7;
+
+/// 重复任务的时间设置，存储格式为 "weekdayBits|HH:MM|HH:MM"
+/// weekdayBits 的第 0~6 位分别表示周日到周六
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepeatTime {
+    pub weekdays_bits: u8,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl RepeatTime {
+    pub fn parse(value: &str) -> Result<RepeatTime, String> {
+        let parts: Vec<&str> = value.split('|').map(str::trim).collect();
+        if parts.len() != 3 {
+            return Err(format!("Invalid repeat_time format: {}", value));
+        }
+
+        let weekdays_bits = parts[0]
+            .parse::<u8>()
+            .map_err(|_| format!("Invalid weekday bits: {}", parts[0]))?;
+        if weekdays_bits & !EXCLUDE_HOLIDAYS_BIT == 0 {
+            return Err("repeat_time must select at least one weekday".into());
+        }
+
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("Invalid time: {}", time))
+        };
+        let start = parse_time(parts[1])?;
+        let end = parse_time(parts[2])?;
+        if start >= end {
+            return Err(format!(
+                "Start time {} must be earlier than end time {}",
+                parts[1], parts[2]
+            ));
+        }
+
+        Ok(RepeatTime {
+            weekdays_bits,
+            start,
+            end,
+        })
+    }
+
+    pub fn includes(&self, weekday: Weekday) -> bool {
+        self.weekdays_bits & (1 << weekday.num_days_from_sunday()) != 0
+    }
+
+    pub fn excludes_holidays(&self) -> bool {
+        self.weekdays_bits & EXCLUDE_HOLIDAYS_BIT != 0
+    }
+}
+
+/// 例外中替换某一次事项的字段，未出现的字段保持不变。
+/// 只修改开始时间时保持原有时长
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MatterOverride {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub priority: Option<i32>,
+}
+
+impl MatterOverride {
+    pub fn parse(json: &str) -> Result<MatterOverride, String> {
+        let value: MatterOverride =
+            serde_json::from_str(json).map_err(|e| format!("Invalid override_json: {}", e))?;
+        if value
+            .title
+            .as_deref()
+            .is_some_and(|title| title.trim().is_empty())
+        {
+            return Err("Override title must not be empty".into());
+        }
+        if let (Some(start_time), Some(end_time)) = (value.start_time, value.end_time) {
+            if end_time < start_time {
+                return Err(format!(
+                    "Override end time {} is before start time {}",
+                    end_time, start_time
+                ));
+            }
+        }
+        Ok(value)
+    }
+
+    fn apply(self, matter: &mut Matter) {
+        let duration = matter.end_time - matter.start_time;
+        if let Some(title) = self.title {
+            matter.title = title;
+        }
+        if self.description.is_some() {
+            matter.description = self.description;
+        }
+        if self.tags.is_some() {
+            matter.tags = self.tags;
+        }
+        if let Some(priority) = self.priority {
+            matter.priority = priority;
+        }
+        if let Some(start_time) = self.start_time {
+            matter.start_time = start_time;
+            matter.end_time = start_time + duration;
+        }
+        if let Some(end_time) = self.end_time {
+            matter.end_time = end_time.max(matter.start_time);
+        }
+    }
+}
+
+// 本地时间转为 UTC：夏令时重复的时刻取较早的一次，被跳过的时刻顺延一小时
+fn resolve_local<T: TimeZone>(timezone: &T, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// 将周期任务展开为 [from, to) 内开始的具体事项（type_ = 1），按 timezone 的本地日期计算。
+/// 非启用状态或 repeat_time 无效的任务不产生事项，不排除节假日。
+/// 事项 id 由任务 id 和本地日期组成，同一天只会生成一次。
+/// exceptions 中属于该任务的例外按本地日期匹配：跳过这一次或替换字段，
+/// 是否在范围内按替换前的开始时间判断
+pub fn expand_task<T: TimeZone>(
+    task: &RepeatTask,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    timezone: &T,
+    exceptions: &[RepeatTaskException],
+) -> Vec<Matter> {
+    if task.status != 1 || from >= to {
+        return Vec::new();
+    }
+    let Ok(repeat_time) = RepeatTime::parse(&task.repeat_time) else {
+        return Vec::new();
+    };
+
+    let first_day = from.with_timezone(timezone).date_naive();
+    let last_day = to.with_timezone(timezone).date_naive();
+    let mut occurrences = Vec::new();
+    for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+        if !repeat_time.includes(day.weekday()) {
+            continue;
+        }
+        let (Some(start_time), Some(end_time)) = (
+            resolve_local(timezone, day.and_time(repeat_time.start)),
+            resolve_local(timezone, day.and_time(repeat_time.end)),
+        ) else {
+            continue;
+        };
+        if start_time < from || start_time >= to {
+            continue;
+        }
+
+        let exception = exceptions
+            .iter()
+            .find(|exception| exception.task_id == task.id && exception.occurrence_date == day);
+        let matter_override = match exception
+            .and_then(|exception| RepeatExceptionAction::try_from(exception.action.as_str()).ok())
+        {
+            Some(RepeatExceptionAction::Skip) => continue,
+            Some(RepeatExceptionAction::Override) => exception
+                .and_then(|exception| exception.override_json.as_deref())
+                .and_then(|json| MatterOverride::parse(json).ok()),
+            None => None,
+        };
+
+        let mut matter = Matter {
+            id: format!("{}-{}", task.id, day),
+            title: task.title.clone(),
+            description: task.description.clone(),
+            tags: task.tags.clone(),
+            start_time,
+            end_time,
+            priority: task.priority,
+            type_: 1,
+            created_at: task.created_at,
+            updated_at: task.updated_at,
+            reserved_1: None,
+            reserved_2: Some(task.id.clone()), // 与前端一致，记录来源的周期任务
+            reserved_3: None,
+            reserved_4: None,
+            reserved_5: None,
+        };
+        if let Some(matter_override) = matter_override {
+            matter_override.apply(&mut matter);
+        }
+        occurrences.push(matter);
+    }
+    occurrences
+}
+
+impl RepeatTask {
+    /// after 之后（不含 after）最近一次开始的时间，按 timezone 的本地时间计算。
+    /// 非启用状态或 repeat_time 无效时返回 None，不排除节假日
+    pub fn next_occurrence<T: TimeZone>(
+        &self,
+        after: DateTime<Utc>,
+        timezone: &T,
+    ) -> Option<DateTime<Utc>> {
+        if self.status != 1 {
+            return None;
+        }
+        let repeat_time = RepeatTime::parse(&self.repeat_time).ok()?;
+
+        let first_day = after.with_timezone(timezone).date_naive();
+        // 至少选择了一天，所以八天之内必然有下一次
+        first_day
+            .iter_days()
+            .take(8)
+            .filter(|day| repeat_time.includes(day.weekday()))
+            .filter_map(|day| resolve_local(timezone, day.and_time(repeat_time.start)))
+            .find(|start_time| *start_time > after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono_tz::Tz;
+
+    const NEW_YORK: Tz = Tz::America__New_York;
+
+    fn task(repeat_time: &str) -> RepeatTask {
+        serde_json::from_value(serde_json::json!({
+            "id": "t",
+            "title": "x",
+            "repeat_time": repeat_time
+        }))
+        .unwrap()
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn local_starts(matters: &[Matter]) -> Vec<String> {
+        matters
+            .iter()
+            .map(|m| {
+                m.start_time
+                    .with_timezone(&NEW_YORK)
+                    .format("%m-%d %H:%M")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expand_task_crosses_month_and_dst_boundaries() {
+        // 周一、周三、周五 09:00-10:00
+        let weekly = task("42|09:00|10:00");
+        let got = expand_task(
+            &weekly,
+            utc(2024, 1, 29, 0),
+            utc(2024, 2, 6, 0),
+            &NEW_YORK,
+            &[],
+        );
+        assert_eq!(
+            local_starts(&got),
+            vec!["01-29 09:00", "01-31 09:00", "02-02 09:00", "02-05 09:00"]
+        );
+        assert!(got
+            .iter()
+            .all(|m| m.type_ == 1 && m.reserved_2.as_deref() == Some("t")));
+        // 2024-03-10 夏令时开始，本地时间保持不变
+        let got = expand_task(
+            &weekly,
+            utc(2024, 3, 8, 0),
+            utc(2024, 3, 16, 0),
+            &NEW_YORK,
+            &[],
+        );
+        assert_eq!(
+            local_starts(&got),
+            vec!["03-08 09:00", "03-11 09:00", "03-13 09:00", "03-15 09:00"]
+        );
+
+        // 2024-11-03 回拨时重复的 01:30 只生成一次
+        let daily = task("127|01:30|03:00");
+        let got = expand_task(
+            &daily,
+            utc(2024, 11, 3, 0),
+            utc(2024, 11, 4, 0),
+            &NEW_YORK,
+            &[],
+        );
+        assert_eq!(got.len(), 1);
+        // 拨快时跳过的 02:30 顺延到 03:30
+        let skipped = task("127|02:30|03:30");
+        let got = expand_task(
+            &skipped,
+            utc(2024, 3, 10, 5),
+            utc(2024, 3, 11, 5),
+            &NEW_YORK,
+            &[],
+        );
+        assert_eq!(local_starts(&got), vec!["03-10 03:30"]);
+
+        let paused = RepeatTask {
+            status: 0,
+            ..weekly
+        };
+        assert!(expand_task(
+            &paused,
+            utc(2024, 1, 1, 0),
+            utc(2024, 2, 1, 0),
+            &NEW_YORK,
+            &[]
+        )
+        .is_empty());
+    }
+}
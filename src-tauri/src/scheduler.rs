@@ -0,0 +1,83 @@
+use crate::database::{
+    KVStore, NotificationChange, NotificationRecord, RecurringNotification, SafeConnection,
+    SCHEDULER_LAST_FIRE_CHECK_KEY,
+};
+use chrono::{DateTime, Local, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+// 调度器轮询间隔
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+// 定时通知触发时发送给前端的事件
+pub const NOTIFICATION_FIRED_EVENT: &str = "notification://fired";
+
+// 新建通知时发送给主窗口的事件，载荷为完整的通知记录（定时通知的 fire_at 可能在未来）
+pub const NOTIFICATION_CREATED_EVENT: &str = "notification://created";
+
+// 通知被标记为已读时发送给主窗口的事件，载荷为 { id, type_, related_task_id }：
+// 单条标记时 id 有值，按类型标记时 type_ 有值，按任务标记时 related_task_id 有值，
+// 三者都为 null 表示全部已读
+pub const NOTIFICATION_READ_EVENT: &str = "notification://read";
+
+// 主窗口的 label
+const MAIN_WINDOW: &str = "main";
+
+/// 将数据库中通知的新建和已读转发为主窗口的事件，调度器、HTTP 接口和命令的修改都会触发
+pub fn forward_notification_changes(app: AppHandle, db: &Arc<SafeConnection>) {
+    db.set_notification_listener(move |change| {
+        let event = match change {
+            NotificationChange::Created(_) => NOTIFICATION_CREATED_EVENT,
+            NotificationChange::Read { .. } => NOTIFICATION_READ_EVENT,
+        };
+        if let Err(e) = app.emit_to(MAIN_WINDOW, event, change) {
+            log::error!("Failed to emit {}: {}", event, e);
+        }
+    });
+}
+
+/// 启动后台调度器，定时检查并触发到期的通知
+pub fn start_scheduler(app: AppHandle, db: Arc<SafeConnection>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = fire_due_notifications(&app, &db) {
+                log::error!("Failed to fire scheduled notifications: {}", e);
+            }
+        }
+    });
+}
+
+fn fire_due_notifications(app: &AppHandle, db: &Arc<SafeConnection>) -> rusqlite::Result<()> {
+    let now = Utc::now();
+    let last_check = KVStore::get(db, SCHEDULER_LAST_FIRE_CHECK_KEY, "")?;
+    let since = DateTime::parse_from_rfc3339(&last_check)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now);
+
+    // 重复提醒和即将开始的事项先生成定时通知，再与其他定时通知一起触发
+    RecurringNotification::schedule_due(db, since, now, &Local)?;
+    NotificationRecord::schedule_matter_reminders(db, now)?;
+
+    for notification in NotificationRecord::get_due(db, since, now)? {
+        log::info!("Firing scheduled notification: {}", notification.id);
+        if let Err(e) = app
+            .notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.content)
+            .show()
+        {
+            log::error!("Failed to show notification: {}", e);
+        }
+        if let Err(e) = app.emit(NOTIFICATION_FIRED_EVENT, &notification) {
+            log::error!("Failed to emit {}: {}", NOTIFICATION_FIRED_EVENT, e);
+        }
+    }
+
+    KVStore::set(db, SCHEDULER_LAST_FIRE_CHECK_KEY, &now.to_rfc3339())?;
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use crate::database::{
+    DbError, KVStore, SafeConnection, AUTO_BACKUP_KEY, DEFAULT_MATTER_PRIORITY_KEY,
+    DEFAULT_MATTER_TYPE_KEY, REMINDER_LEAD_MINUTES_KEY, UNIQUE_REPEAT_TITLES_KEY, WEEKLY_GOAL_KEY,
+};
+use rusqlite::Result;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub const THEME_KEY: &str = "theme";
+pub const FIRST_DAY_OF_WEEK_KEY: &str = "first_day_of_week";
+pub const DEFAULT_MATTER_DURATION_KEY: &str = "default_matter_duration_minutes";
+
+const THEMES: [&str; 2] = ["light", "dark"];
+
+/// 应用设置，每个字段对应 kvstore 中的一个键，缺少或无法解析的键使用默认值。
+/// 反序列化时缺少的字段同样使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub theme: String,              // "light", "dark"
+    pub reminder_lead_minutes: i64, // 事项开始前多少分钟提醒，0 表示不提醒
+    pub first_day_of_week: u32,     // 0 为周日，1 为周一，以此类推
+    pub default_matter_duration_minutes: i64,
+    pub default_matter_priority: i32,
+    pub default_matter_type: i32,
+    pub weekly_goal_seconds: i64, // 0 表示没有目标
+    pub auto_backup: bool,        // 替换导入、恢复等操作前自动备份
+    pub unique_repeat_titles: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            theme: "light".to_string(),
+            reminder_lead_minutes: 10,
+            first_day_of_week: 1,
+            default_matter_duration_minutes: 30,
+            default_matter_priority: 0,
+            default_matter_type: 0,
+            weekly_goal_seconds: 0,
+            auto_backup: true,
+            unique_repeat_titles: false,
+        }
+    }
+}
+
+// 读取并解析一个键，不存在或无法解析时返回 default
+fn load_value<T: FromStr + ToString>(
+    conn: &Arc<SafeConnection>,
+    key: &str,
+    default: T,
+) -> Result<T> {
+    let value = KVStore::get(conn, key, &default.to_string())?;
+    Ok(value.parse().unwrap_or_else(|_| {
+        log::warn!(
+            "Invalid value {:?} for setting {}, using default",
+            value,
+            key
+        );
+        default
+    }))
+}
+
+impl AppSettings {
+    pub fn load(conn: &Arc<SafeConnection>) -> Result<AppSettings> {
+        let defaults = AppSettings::default();
+        let theme = KVStore::get(conn, THEME_KEY, &defaults.theme)?;
+        Ok(AppSettings {
+            theme: if THEMES.contains(&theme.as_str()) {
+                theme
+            } else {
+                defaults.theme
+            },
+            reminder_lead_minutes: load_value(
+                conn,
+                REMINDER_LEAD_MINUTES_KEY,
+                defaults.reminder_lead_minutes,
+            )?,
+            first_day_of_week: load_value(conn, FIRST_DAY_OF_WEEK_KEY, defaults.first_day_of_week)?
+                .min(6),
+            default_matter_duration_minutes: load_value(
+                conn,
+                DEFAULT_MATTER_DURATION_KEY,
+                defaults.default_matter_duration_minutes,
+            )?,
+            default_matter_priority: load_value(
+                conn,
+                DEFAULT_MATTER_PRIORITY_KEY,
+                defaults.default_matter_priority,
+            )?,
+            default_matter_type: load_value(
+                conn,
+                DEFAULT_MATTER_TYPE_KEY,
+                defaults.default_matter_type,
+            )?,
+            weekly_goal_seconds: load_value(conn, WEEKLY_GOAL_KEY, defaults.weekly_goal_seconds)?,
+            auto_backup: load_value(conn, AUTO_BACKUP_KEY, defaults.auto_backup)?,
+            unique_repeat_titles: load_value(
+                conn,
+                UNIQUE_REPEAT_TITLES_KEY,
+                defaults.unique_repeat_titles,
+            )?,
+        })
+    }
+
+    fn validate(&self) -> std::result::Result<(), DbError> {
+        let invalid = |message: String| Err(DbError::InvalidArgument(message));
+        if !THEMES.contains(&self.theme.as_str()) {
+            return invalid(format!("Invalid theme: {}", self.theme));
+        }
+        if self.first_day_of_week > 6 {
+            return invalid(format!(
+                "first_day_of_week must be between 0 and 6, got {}",
+                self.first_day_of_week
+            ));
+        }
+        if self.reminder_lead_minutes < 0 {
+            return invalid("reminder_lead_minutes must not be negative".to_string());
+        }
+        if self.default_matter_duration_minutes <= 0 {
+            return invalid("default_matter_duration_minutes must be positive".to_string());
+        }
+        if self.weekly_goal_seconds < 0 {
+            return invalid("weekly_goal_seconds must not be negative".to_string());
+        }
+        Ok(())
+    }
+
+    // 校验后在同一个事务中写入所有键
+    pub fn save(&self, conn: &Arc<SafeConnection>) -> std::result::Result<(), DbError> {
+        self.validate()?;
+
+        KVStore::set_many(
+            conn,
+            &[
+                (THEME_KEY, self.theme.clone()),
+                (
+                    REMINDER_LEAD_MINUTES_KEY,
+                    self.reminder_lead_minutes.to_string(),
+                ),
+                (FIRST_DAY_OF_WEEK_KEY, self.first_day_of_week.to_string()),
+                (
+                    DEFAULT_MATTER_DURATION_KEY,
+                    self.default_matter_duration_minutes.to_string(),
+                ),
+                (
+                    DEFAULT_MATTER_PRIORITY_KEY,
+                    self.default_matter_priority.to_string(),
+                ),
+                (
+                    DEFAULT_MATTER_TYPE_KEY,
+                    self.default_matter_type.to_string(),
+                ),
+                (WEEKLY_GOAL_KEY, self.weekly_goal_seconds.to_string()),
+                (AUTO_BACKUP_KEY, self.auto_backup.to_string()),
+                (
+                    UNIQUE_REPEAT_TITLES_KEY,
+                    self.unique_repeat_titles.to_string(),
+                ),
+            ],
+        )?;
+        Ok(())
+    }
+}
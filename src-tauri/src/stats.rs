@@ -0,0 +1,1602 @@
+use crate::database::{
+    check_is_default_datetime, split_tags, DbError, KVStore, Matter, MatterPhase,
+    NotificationRecord, NotificationStatus, NotificationType, SafeConnection, Tag, WEEKLY_GOAL_KEY,
+    WEEKLY_GOAL_NOTIFIED_KEY,
+};
+use crate::utils;
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use rusqlite::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+// 没有设置分类的标签（以及没有标签的事项）归入该分类
+const UNCATEGORIZED: &str = "uncategorized";
+
+// 历史事项少于该数量时不给出建议时间
+const MIN_SUGGESTION_SAMPLES: usize = 3;
+
+#[derive(Debug, Serialize)]
+pub struct WeekdayAverageStart {
+    pub weekday: Weekday,
+    pub average_start: Option<String>, // 本地时间 "HH:MM"
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeekdayCoverage {
+    pub weekday: Weekday,
+    pub matter_count: usize,
+    pub days_in_range: usize,
+    pub is_empty: bool, // 区间内该工作日没有任何事项
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    Day,
+    Week,
+    Month,
+}
+
+// 月度预测的计算方式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectionMethod {
+    #[default]
+    WorkingDays, // 按已过去的工作日（周一至周五）推算
+    CalendarDays, // 按已过去的自然日推算
+}
+
+#[derive(Debug, Serialize)]
+pub struct MonthProjection {
+    pub month: NaiveDate, // 当月第一天（本地）
+    pub month_to_date_seconds: i64,
+    pub projected_seconds: i64,
+    pub elapsed_days: i64, // 包含 as_of 当天
+    pub total_days: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TagPhaseCounts {
+    pub tag: String,
+    pub past: usize,
+    pub ongoing: usize,
+    pub upcoming: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FocusBlock {
+    pub tag: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_seconds: i64,
+    pub matter_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeSuggestion {
+    pub tag: String,
+    pub start: String,       // 本地时间 "HH:MM"
+    pub end: String,         // 本地时间 "HH:MM"，为 start 之后一小时
+    pub matter_count: usize, // 在该小时开始的事项数量
+    pub sample_size: usize,
+}
+
+// 相邻事项之间的空闲间隔，用于衡量时间的碎片化程度
+#[derive(Debug, Serialize)]
+pub struct GapSummary {
+    pub gap_count: usize,
+    pub total_gap_seconds: i64,
+    pub average_gap_seconds: Option<i64>, // 没有间隔时为空
+}
+
+// 某个标签的连续天数，按本地日期计算
+#[derive(Debug, Serialize)]
+pub struct TagStreak {
+    pub tag: String,
+    pub current_days: usize, // 今天还没有该标签的事项时，从昨天开始往前计算
+    pub longest_days: usize,
+    pub last_day: Option<NaiveDate>,
+}
+
+// 一天内相邻事项之间从 from 标签切换到 to 标签的次数
+#[derive(Debug, Serialize)]
+pub struct TagTransition {
+    pub from: String,
+    pub to: String,
+    pub count: usize,
+}
+
+// 一天内按时间顺序的标签序列和标签之间的切换，用于桑基图
+#[derive(Debug, Serialize)]
+pub struct TagFlow {
+    pub day: NaiveDate,
+    pub sequence: Vec<String>,           // 相邻相同的标签合并为一项
+    pub transitions: Vec<TagTransition>, // 按第一次出现的顺序排列
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayCapacity {
+    pub day: NaiveDate,
+    pub booked_seconds: i64,
+    pub target_seconds: i64,
+    pub remaining_seconds: i64, // 超出目标时为负数
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriorityTotal {
+    pub priority: i32,
+    pub total_seconds: i64,
+    pub matter_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagTotal {
+    pub tag: String,
+    pub total_seconds: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeTotal {
+    pub type_: i32,
+    pub total_seconds: i64,
+}
+
+// 区间内的时长汇总，total_seconds 中每个事项只计一次，多个标签的事项计入每个标签
+#[derive(Debug, Serialize)]
+pub struct TimeStats {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub total_seconds: i64,
+    pub by_tag: Vec<TagTotal>,
+    pub by_type: Vec<TypeTotal>,
+}
+
+// 各优先级占已记录时长的整数百分比，所有优先级之和恰好为 100
+#[derive(Debug, Serialize)]
+pub struct PriorityShare {
+    pub priority: i32,
+    pub seconds: i64,
+    pub percent: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeeklyGoalProgress {
+    pub week_start: NaiveDate,
+    pub goal_seconds: i64, // 未设置目标时为 0
+    pub tracked_seconds: i64,
+    pub percent: f64,        // 可能超过 100，未设置目标时为 0
+    pub days_remaining: i64, // 包含今天
+    pub goal_met: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DurationBucket {
+    pub min_minutes: i64, // 包含
+    pub max_minutes: i64, // 不包含
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagAllocation {
+    pub tag: String,
+    pub tag_seconds: i64,
+    pub total_seconds: i64,
+    pub tag_percent: f64,   // 没有任何记录时为 0
+    pub other_percent: f64, // 没有任何记录时为 0
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryShare {
+    pub category: String,
+    pub seconds: i64,
+    pub percent: f64, // 占所有分类时长之和的百分比
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagSeries {
+    pub tag: String,
+    pub totals: Vec<i64>, // 每个时间段的秒数，与 buckets 一一对应
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagTimeSeries {
+    pub buckets: Vec<NaiveDate>, // 每个时间段的开始日期（本地）
+    pub series: Vec<TagSeries>,
+}
+
+// 事项落在 [from, to) 内的秒数，未结束或时间无效的事项记为 0
+fn clamped_seconds(matter: &Matter, from: DateTime<Utc>, to: DateTime<Utc>) -> i64 {
+    if matter.is_open_ended() {
+        return 0;
+    }
+    let start = matter.start_time.max(from);
+    let end = matter.end_time.min(to);
+    (end - start).num_seconds().max(0)
+}
+
+// 与 clamped_seconds 相同，但未结束的事项计算到 now 为止
+fn clamped_seconds_until(
+    matter: &Matter,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> i64 {
+    if matter.is_open_ended() {
+        return (now.min(to) - matter.start_time.max(from))
+            .num_seconds()
+            .max(0);
+    }
+    clamped_seconds(matter, from, to)
+}
+
+// 按标签累计 [from, to) 内的时长，多个标签的事项会计入每个标签
+fn tally_by_tag(
+    matters: &[Matter],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> BTreeMap<String, i64> {
+    let mut totals = BTreeMap::new();
+    for matter in matters {
+        let seconds = clamped_seconds(matter, from, to);
+        if seconds == 0 {
+            continue;
+        }
+        for tag in matter.tags.as_deref().map(split_tags).into_iter().flatten() {
+            *totals.entry(tag.to_string()).or_insert(0) += seconds;
+        }
+    }
+    totals
+}
+
+// [from, to) 内已记录的总时长，每个事项只计一次
+fn total_tracked_seconds(matters: &[Matter], from: DateTime<Utc>, to: DateTime<Utc>) -> i64 {
+    matters
+        .iter()
+        .map(|matter| clamped_seconds(matter, from, to))
+        .sum()
+}
+
+// 区间内开始时间有效的事项，开始时间为默认值的事项无法计算时长
+fn matters_with_valid_start(
+    conn: &Arc<SafeConnection>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<Matter>> {
+    let mut matters = Matter::get_by_time_range(conn, start, end)?;
+    matters.retain(|matter| !check_is_default_datetime(matter.start_time));
+    Ok(matters)
+}
+
+// 按标签汇总，时长多的在前
+fn tag_totals(matters: &[Matter], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<TagTotal> {
+    let mut totals: Vec<TagTotal> = tally_by_tag(matters, from, to)
+        .into_iter()
+        .map(|(tag, total_seconds)| TagTotal { tag, total_seconds })
+        .collect();
+    totals.sort_by_key(|total| std::cmp::Reverse(total.total_seconds));
+    totals
+}
+
+// 按事项类型汇总，按类型排序
+fn type_totals(matters: &[Matter], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<TypeTotal> {
+    let mut totals: BTreeMap<i32, i64> = BTreeMap::new();
+    for matter in matters {
+        let seconds = clamped_seconds(matter, from, to);
+        if seconds > 0 {
+            *totals.entry(matter.type_).or_insert(0) += seconds;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(type_, total_seconds)| TypeTotal {
+            type_,
+            total_seconds,
+        })
+        .collect()
+}
+
+// (开始, 结束)
+type Span = (DateTime<Utc>, DateTime<Utc>);
+
+// (开始, 结束, 合并的事项数量)
+type MergedSpan = (DateTime<Utc>, DateTime<Utc>, usize);
+
+// 合并间隔不超过 max_gap_seconds 的时间段，返回最长的一段
+fn longest_merged_span(mut spans: Vec<Span>, max_gap_seconds: i64) -> Option<MergedSpan> {
+    spans.sort();
+    let mut longest: Option<MergedSpan> = None;
+    let mut current: Option<MergedSpan> = None;
+    for (start, end) in spans {
+        current = match current {
+            Some((block_start, block_end, count))
+                if (start - block_end).num_seconds() <= max_gap_seconds =>
+            {
+                Some((block_start, block_end.max(end), count + 1))
+            }
+            _ => Some((start, end, 1)),
+        };
+        if let Some((block_start, block_end, _)) = current {
+            if longest.is_none_or(|(s, e, _)| block_end - block_start > e - s) {
+                longest = current;
+            }
+        }
+    }
+    longest
+}
+
+// 按开始时间所在的本地日期分组
+fn group_by_local_day(matters: &[Matter], timezone: Tz) -> BTreeMap<NaiveDate, Vec<&Matter>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&Matter>> = BTreeMap::new();
+    for matter in matters {
+        let day = matter.start_time.with_timezone(&timezone).date_naive();
+        days.entry(day).or_default().push(matter);
+    }
+    days
+}
+
+fn has_tag(matter: &Matter, tag: &str) -> bool {
+    matter
+        .tags
+        .as_deref()
+        .map(|tags| split_tags(tags).any(|t| t == tag))
+        .unwrap_or(false)
+}
+
+impl Matter {
+    // 统计每个工作日的平均开始时间（本地时间），忽略未结束的事项
+    pub fn avg_start_by_weekday(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        tag_filter: Option<&str>,
+        timezone: Tz,
+    ) -> Result<Vec<WeekdayAverageStart>> {
+        let matters = Matter::get_by_time_range(conn, start, end)?;
+
+        let mut totals = [(0u64, 0usize); 7];
+        for matter in matters
+            .iter()
+            .filter(|m| !m.is_open_ended())
+            .filter(|m| tag_filter.is_none_or(|tag| has_tag(m, tag)))
+        {
+            let local = matter.start_time.with_timezone(&timezone);
+            let index = local.weekday().num_days_from_monday() as usize;
+            totals[index].0 += local.num_seconds_from_midnight() as u64;
+            totals[index].1 += 1;
+        }
+
+        Ok(WEEKDAYS
+            .iter()
+            .zip(totals)
+            .map(|(weekday, (seconds, count))| {
+                let average_start = if count == 0 {
+                    None
+                } else {
+                    let average = (seconds / count as u64) as u32;
+                    NaiveTime::from_num_seconds_from_midnight_opt(average, 0)
+                        .map(|time| time.format("%H:%M").to_string())
+                };
+                WeekdayAverageStart {
+                    weekday: *weekday,
+                    average_start,
+                    count,
+                }
+            })
+            .collect())
+    }
+
+    // 根据该标签历史事项最常见的开始小时（本地时间）建议时间段，历史不足时返回 None
+    pub fn suggest_time_for_tag(
+        conn: &Arc<SafeConnection>,
+        tag: &str,
+        timezone: Tz,
+    ) -> Result<Option<TimeSuggestion>> {
+        let mut counts = [0usize; 24];
+        for matter in Matter::get_all(conn)?.iter().filter(|m| has_tag(m, tag)) {
+            counts[matter.start_time.with_timezone(&timezone).hour() as usize] += 1;
+        }
+
+        let sample_size: usize = counts.iter().sum();
+        if sample_size < MIN_SUGGESTION_SAMPLES {
+            return Ok(None);
+        }
+
+        // 次数相同时取较早的小时
+        let (hour, matter_count) = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, count)| **count)
+            .map(|(hour, count)| (hour as u32, *count))
+            .unwrap_or_default();
+
+        Ok(Some(TimeSuggestion {
+            tag: tag.to_string(),
+            start: format!("{:02}:00", hour),
+            end: format!("{:02}:00", (hour + 1) % 24),
+            matter_count,
+            sample_size,
+        }))
+    }
+
+    // 每个标签下已结束、进行中和未开始的事项数量
+    pub fn tag_time_buckets(
+        conn: &Arc<SafeConnection>,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<TagPhaseCounts>> {
+        let mut counts: BTreeMap<String, TagPhaseCounts> = BTreeMap::new();
+        for matter in Matter::get_all(conn)? {
+            let phase = matter.phase_at(at);
+            for tag in matter.tags.as_deref().map(split_tags).into_iter().flatten() {
+                let entry = counts
+                    .entry(tag.to_string())
+                    .or_insert_with(|| TagPhaseCounts {
+                        tag: tag.to_string(),
+                        ..Default::default()
+                    });
+                match phase {
+                    MatterPhase::Past => entry.past += 1,
+                    MatterPhase::Ongoing => entry.ongoing += 1,
+                    MatterPhase::Upcoming => entry.upcoming += 1,
+                }
+            }
+        }
+
+        Ok(counts.into_values().collect())
+    }
+
+    // 某一天最长的连续专注时段：同一标签下间隔不超过 max_gap_seconds 的事项合并为一段，
+    // 未指定 tag_filter 时分别计算每个标签并取最长
+    pub fn longest_focus_block(
+        conn: &Arc<SafeConnection>,
+        day: NaiveDate,
+        tag_filter: Option<&str>,
+        timezone: Tz,
+        max_gap_seconds: i64,
+    ) -> Result<Option<FocusBlock>> {
+        let day_start = utils::local_midnight(day, timezone);
+        let day_end = utils::local_midnight(day.succ_opt().unwrap_or(day), timezone);
+
+        let mut spans_by_tag: BTreeMap<&str, Vec<Span>> = BTreeMap::new();
+        let matters = Matter::get_by_time_range(conn, day_start, day_end)?;
+        for matter in matters
+            .iter()
+            .filter(|m| clamped_seconds(m, day_start, day_end) > 0)
+        {
+            let span = (
+                matter.start_time.max(day_start),
+                matter.end_time.min(day_end),
+            );
+            for tag in matter.tags.as_deref().map(split_tags).into_iter().flatten() {
+                if tag_filter.is_none_or(|filter| filter == tag) {
+                    spans_by_tag.entry(tag).or_default().push(span);
+                }
+            }
+        }
+
+        let longest = spans_by_tag
+            .into_iter()
+            .filter_map(|(tag, spans)| {
+                longest_merged_span(spans, max_gap_seconds).map(|(start, end, count)| FocusBlock {
+                    tag: tag.to_string(),
+                    start,
+                    end,
+                    duration_seconds: (end - start).num_seconds(),
+                    matter_count: count,
+                })
+            })
+            .max_by_key(|block| block.duration_seconds);
+
+        Ok(longest)
+    }
+
+    // 某一天已安排的时长与目标时长之差，未结束的事项计算到现在
+    pub fn remaining_capacity(
+        conn: &Arc<SafeConnection>,
+        day: NaiveDate,
+        target_seconds: i64,
+        timezone: Tz,
+    ) -> Result<DayCapacity> {
+        let day_start = utils::local_midnight(day, timezone);
+        let day_end = utils::local_midnight(day.succ_opt().unwrap_or(day), timezone);
+        let now = Utc::now();
+
+        let booked_seconds = Matter::get_by_time_range(conn, day_start, day_end)?
+            .iter()
+            .map(|matter| clamped_seconds_until(matter, day_start, day_end, now))
+            .sum();
+
+        Ok(DayCapacity {
+            day,
+            booked_seconds,
+            target_seconds,
+            remaining_seconds: target_seconds - booked_seconds,
+        })
+    }
+
+    // 当月截至 as_of 的总时长，并按已过去的天数线性推算到月底
+    pub fn project_month_total(
+        conn: &Arc<SafeConnection>,
+        as_of: DateTime<Utc>,
+        timezone: Tz,
+        method: ProjectionMethod,
+    ) -> Result<MonthProjection> {
+        let today = as_of.with_timezone(&timezone).date_naive();
+        let month = today.with_day(1).unwrap();
+        let next_month = month + Months::new(1);
+        let month_start = utils::local_midnight(month, timezone);
+
+        let month_to_date_seconds = Matter::get_by_time_range(conn, month_start, as_of)?
+            .iter()
+            .map(|matter| clamped_seconds_until(matter, month_start, as_of, as_of))
+            .sum();
+
+        let counts = |day: &NaiveDate| match method {
+            ProjectionMethod::WorkingDays => day.weekday().num_days_from_monday() < 5,
+            ProjectionMethod::CalendarDays => true,
+        };
+        let days = month.iter_days().take_while(|day| *day < next_month);
+        let elapsed_days = days
+            .clone()
+            .filter(|day| *day <= today && counts(day))
+            .count() as i64;
+        let total_days = days.filter(counts).count() as i64;
+
+        // 还没有经过可计入的天数时，无法推算，直接使用当前总时长
+        let projected_seconds = if elapsed_days == 0 {
+            month_to_date_seconds
+        } else {
+            month_to_date_seconds * total_days / elapsed_days
+        };
+
+        Ok(MonthProjection {
+            month,
+            month_to_date_seconds,
+            projected_seconds,
+            elapsed_days,
+            total_days,
+        })
+    }
+
+    // 本周（从 week_start 开始的 7 天）已记录时长相对每周目标的进度，未结束的事项计算到现在
+    pub fn weekly_goal_progress(
+        conn: &Arc<SafeConnection>,
+        week_start: NaiveDate,
+        timezone: Tz,
+    ) -> Result<WeeklyGoalProgress> {
+        let goal_seconds: i64 = KVStore::get(conn, WEEKLY_GOAL_KEY, "0")?
+            .parse()
+            .unwrap_or(0);
+        let week_end = week_start + Days::new(7);
+        let from = utils::local_midnight(week_start, timezone);
+        let to = utils::local_midnight(week_end, timezone);
+        let now = Utc::now();
+
+        let tracked_seconds = Matter::get_by_time_range(conn, from, to)?
+            .iter()
+            .map(|matter| clamped_seconds_until(matter, from, to, now))
+            .sum();
+
+        let today = now.with_timezone(&timezone).date_naive();
+        let days_remaining = (week_end - today.max(week_start)).num_days().max(0);
+        let percent = if goal_seconds > 0 {
+            tracked_seconds as f64 * 100.0 / goal_seconds as f64
+        } else {
+            0.0
+        };
+
+        Ok(WeeklyGoalProgress {
+            week_start,
+            goal_seconds,
+            tracked_seconds,
+            percent,
+            days_remaining,
+            goal_met: goal_seconds > 0 && tracked_seconds >= goal_seconds,
+        })
+    }
+
+    // 带有 tag 的事项按本地日期统计当前和最长的连续天数，只统计 at 之前开始的事项
+    pub fn tag_streak(
+        conn: &Arc<SafeConnection>,
+        tag: &str,
+        at: DateTime<Utc>,
+        timezone: Tz,
+    ) -> Result<TagStreak> {
+        let matters: Vec<Matter> = Matter::get_all(conn)?
+            .into_iter()
+            .filter(|m| m.start_time <= at && has_tag(m, tag))
+            .collect();
+        let days: BTreeSet<NaiveDate> =
+            group_by_local_day(&matters, timezone).into_keys().collect();
+
+        let mut longest_days = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for day in &days {
+            run = match previous {
+                Some(prev) if prev.succ_opt() == Some(*day) => run + 1,
+                _ => 1,
+            };
+            longest_days = longest_days.max(run);
+            previous = Some(*day);
+        }
+
+        let today = at.with_timezone(&timezone).date_naive();
+        let mut day = if days.contains(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let mut current_days = 0;
+        while let Some(d) = day.filter(|d| days.contains(d)) {
+            current_days += 1;
+            day = d.pred_opt();
+        }
+
+        Ok(TagStreak {
+            tag: tag.to_string(),
+            current_days,
+            longest_days,
+            last_day: days.last().copied(),
+        })
+    }
+
+    // [start, end) 内有事项的本地日期，跨天的事项标记其经过的每一天，未结束的事项只标记开始当天
+    pub fn days_with_data(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        timezone: Tz,
+    ) -> Result<Vec<NaiveDate>> {
+        let mut days = BTreeSet::new();
+        for matter in Matter::get_by_time_range(conn, start, end)? {
+            let from = matter.start_time.max(start);
+            // 恰好在零点结束的事项不算入下一天
+            let to = if matter.is_open_ended() {
+                from
+            } else {
+                (matter.end_time.min(end) - chrono::Duration::nanoseconds(1)).max(from)
+            };
+            if from >= end || to < start {
+                continue;
+            }
+
+            let last_day = to.with_timezone(&timezone).date_naive();
+            days.extend(
+                from.with_timezone(&timezone)
+                    .date_naive()
+                    .iter_days()
+                    .take_while(|day| *day <= last_day),
+            );
+        }
+
+        Ok(days.into_iter().collect())
+    }
+
+    // 某一天按开始时间排序的事项之间主标签（第一个标签）的切换，没有标签的事项不参与
+    pub fn tag_transitions(
+        conn: &Arc<SafeConnection>,
+        day: NaiveDate,
+        timezone: Tz,
+    ) -> Result<TagFlow> {
+        let day_start = utils::local_midnight(day, timezone);
+        let day_end = utils::local_midnight(day.succ_opt().unwrap_or(day), timezone);
+
+        let mut matters = Matter::get_by_time_range(conn, day_start, day_end)?;
+        matters.retain(|m| m.start_time >= day_start && m.start_time < day_end);
+        matters.sort_by_key(|m| m.start_time);
+
+        let mut sequence: Vec<String> = Vec::new();
+        for matter in &matters {
+            let Some(tag) = matter
+                .tags
+                .as_deref()
+                .and_then(|tags| split_tags(tags).next())
+            else {
+                continue;
+            };
+            if sequence.last().is_none_or(|last| last != tag) {
+                sequence.push(tag.to_string());
+            }
+        }
+
+        let mut transitions: Vec<TagTransition> = Vec::new();
+        for pair in sequence.windows(2) {
+            match transitions
+                .iter_mut()
+                .find(|t| t.from == pair[0] && t.to == pair[1])
+            {
+                Some(transition) => transition.count += 1,
+                None => transitions.push(TagTransition {
+                    from: pair[0].clone(),
+                    to: pair[1].clone(),
+                    count: 1,
+                }),
+            }
+        }
+
+        Ok(TagFlow {
+            day,
+            sequence,
+            transitions,
+        })
+    }
+
+    // 同一本地日期内相邻事项之间的平均空闲间隔，重叠或紧接的事项不计为间隔，
+    // 跨天（如下班到次日上班）的间隔也不计入；未结束的事项不参与统计
+    pub fn avg_gap(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        timezone: Tz,
+    ) -> Result<GapSummary> {
+        let matters: Vec<Matter> = Matter::get_by_time_range(conn, start, end)?
+            .into_iter()
+            .filter(|m| !m.is_open_ended() && clamped_seconds(m, start, end) > 0)
+            .collect();
+
+        let mut gap_count = 0;
+        let mut total_gap_seconds = 0;
+        for (_, matters) in group_by_local_day(&matters, timezone) {
+            let mut spans: Vec<Span> = matters
+                .iter()
+                .map(|m| (m.start_time.max(start), m.end_time.min(end)))
+                .collect();
+            spans.sort();
+
+            let mut busy_until: Option<DateTime<Utc>> = None;
+            for (span_start, span_end) in spans {
+                if let Some(previous_end) = busy_until {
+                    let gap = (span_start - previous_end).num_seconds();
+                    if gap > 0 {
+                        gap_count += 1;
+                        total_gap_seconds += gap;
+                    }
+                }
+                busy_until = Some(busy_until.map_or(span_end, |until| until.max(span_end)));
+            }
+        }
+
+        Ok(GapSummary {
+            gap_count,
+            total_gap_seconds,
+            average_gap_seconds: (gap_count > 0).then(|| total_gap_seconds / gap_count as i64),
+        })
+    }
+
+    // 统计每个工作日的事项数量，并标记区间内从未安排事项的工作日
+    pub fn weekday_coverage(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        timezone: Tz,
+    ) -> Result<Vec<WeekdayCoverage>> {
+        let matters = Matter::get_by_time_range(conn, start, end)?;
+        let first_day = start.with_timezone(&timezone).date_naive();
+        let last_day = end.with_timezone(&timezone).date_naive();
+
+        let mut counts = [0usize; 7];
+        for (day, matters) in group_by_local_day(&matters, timezone) {
+            if day >= first_day && day <= last_day {
+                counts[day.weekday().num_days_from_monday() as usize] += matters.len();
+            }
+        }
+
+        let mut days_in_range = [0usize; 7];
+        for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+            days_in_range[day.weekday().num_days_from_monday() as usize] += 1;
+        }
+
+        Ok(WEEKDAYS
+            .iter()
+            .enumerate()
+            .map(|(index, weekday)| WeekdayCoverage {
+                weekday: *weekday,
+                matter_count: counts[index],
+                days_in_range: days_in_range[index],
+                is_empty: counts[index] == 0,
+            })
+            .collect())
+    }
+
+    // 区间内按优先级分组的已记录时长，未结束或不在区间内的事项不计入
+    pub fn time_by_priority(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<PriorityTotal>> {
+        let mut totals: BTreeMap<i32, PriorityTotal> = BTreeMap::new();
+        for matter in Matter::get_by_time_range(conn, start, end)? {
+            let seconds = clamped_seconds(&matter, start, end);
+            if seconds == 0 {
+                continue;
+            }
+            let total = totals
+                .entry(matter.priority)
+                .or_insert_with(|| PriorityTotal {
+                    priority: matter.priority,
+                    total_seconds: 0,
+                    matter_count: 0,
+                });
+            total.total_seconds += seconds;
+            total.matter_count += 1;
+        }
+
+        Ok(totals.into_values().collect())
+    }
+
+    // 区间内按标签分组的已记录时长，跨越区间边界的事项只计算区间内的部分
+    pub fn time_by_tag(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<TagTotal>> {
+        let matters = matters_with_valid_start(conn, start, end)?;
+        Ok(tag_totals(&matters, start, end))
+    }
+
+    // 区间内按事项类型分组的已记录时长
+    pub fn time_by_type(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<TypeTotal>> {
+        let matters = matters_with_valid_start(conn, start, end)?;
+        Ok(type_totals(&matters, start, end))
+    }
+
+    // 各优先级的时长占比，用于饼图；按最大余数法取整，保证百分比之和为 100
+    pub fn priority_distribution(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<PriorityShare>> {
+        let totals = Matter::time_by_priority(conn, start, end)?;
+        let total: i64 = totals.iter().map(|t| t.total_seconds).sum();
+        if total == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut shares: Vec<PriorityShare> = totals
+            .iter()
+            .map(|t| PriorityShare {
+                priority: t.priority,
+                seconds: t.total_seconds,
+                percent: (t.total_seconds * 100 / total) as u32,
+            })
+            .collect();
+
+        // 余数最大的优先级依次补 1，直到总和为 100
+        let assigned: u32 = shares.iter().map(|share| share.percent).sum();
+        let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+        by_remainder.sort_by_key(|&i| std::cmp::Reverse(shares[i].seconds * 100 % total));
+        for &i in by_remainder.iter().take((100 - assigned) as usize) {
+            shares[i].percent += 1;
+        }
+
+        Ok(shares)
+    }
+
+    // 按时长分段统计事项数量，分段之间没有事项的补 0，未结束的事项不计入
+    pub fn duration_histogram(
+        conn: &Arc<SafeConnection>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        bucket_minutes: i64,
+    ) -> std::result::Result<Vec<DurationBucket>, DbError> {
+        if bucket_minutes <= 0 {
+            return Err(DbError::InvalidArgument(
+                "bucket_minutes must be positive".to_string(),
+            ));
+        }
+
+        let mut counts: Vec<usize> = Vec::new();
+        for matter in Matter::get_by_time_range(conn, start, end)? {
+            if matter.is_open_ended() || matter.end_time < matter.start_time {
+                continue;
+            }
+            let index =
+                ((matter.end_time - matter.start_time).num_minutes() / bucket_minutes) as usize;
+            if counts.len() <= index {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        }
+
+        Ok(counts
+            .into_iter()
+            .enumerate()
+            .map(|(index, count)| DurationBucket {
+                min_minutes: index as i64 * bucket_minutes,
+                max_minutes: (index as i64 + 1) * bucket_minutes,
+                count,
+            })
+            .collect())
+    }
+}
+
+impl TimeBucket {
+    // 包含 day 的时间段的开始日期，周从周一开始
+    fn start_of(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            TimeBucket::Day => day,
+            TimeBucket::Week => day - Days::new(day.weekday().num_days_from_monday() as u64),
+            TimeBucket::Month => day.with_day(1).unwrap(),
+        }
+    }
+
+    fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            TimeBucket::Day => start + Days::new(1),
+            TimeBucket::Week => start + Days::new(7),
+            TimeBucket::Month => start + Months::new(1),
+        }
+    }
+}
+
+// 按天/周/月统计每个标签的时长，没有数据的时间段补 0
+pub fn tag_time_series(
+    conn: &Arc<SafeConnection>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    bucket: TimeBucket,
+    timezone: Tz,
+) -> Result<TagTimeSeries> {
+    let matters = Matter::get_by_time_range(conn, start, end)?;
+    let last_day = end.with_timezone(&timezone).date_naive();
+
+    let mut buckets = Vec::new();
+    let mut tallies = Vec::new();
+    let mut day = bucket.start_of(start.with_timezone(&timezone).date_naive());
+    while day <= last_day {
+        let next = bucket.next(day);
+        let from = utils::local_midnight(day, timezone).max(start);
+        let to = utils::local_midnight(next, timezone).min(end);
+        buckets.push(day);
+        tallies.push(tally_by_tag(&matters, from, to));
+        day = next;
+    }
+
+    let tags: BTreeSet<&String> = tallies.iter().flat_map(|tally| tally.keys()).collect();
+    let series = tags
+        .into_iter()
+        .map(|tag| {
+            let totals: Vec<i64> = tallies
+                .iter()
+                .map(|tally| tally.get(tag).copied().unwrap_or(0))
+                .collect();
+            TagSeries {
+                tag: tag.clone(),
+                total: totals.iter().sum(),
+                totals,
+            }
+        })
+        .collect();
+
+    Ok(TagTimeSeries { buckets, series })
+}
+
+// 区间内的总时长以及按标签、按类型的时长，未结束或开始时间无效的事项不计入
+pub fn time_stats(
+    conn: &Arc<SafeConnection>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<TimeStats> {
+    let matters = matters_with_valid_start(conn, start, end)?;
+    Ok(TimeStats {
+        start,
+        end,
+        total_seconds: total_tracked_seconds(&matters, start, end),
+        by_tag: tag_totals(&matters, start, end),
+        by_type: type_totals(&matters, start, end),
+    })
+}
+
+// 标签时长占总记录时长的百分比
+pub fn tag_allocation_ratio(
+    conn: &Arc<SafeConnection>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tag: &str,
+) -> Result<TagAllocation> {
+    let matters = Matter::get_by_time_range(conn, start, end)?;
+    let tag_seconds = tally_by_tag(&matters, start, end)
+        .get(tag)
+        .copied()
+        .unwrap_or(0);
+    let total_seconds = total_tracked_seconds(&matters, start, end);
+
+    let tag_percent = if total_seconds > 0 {
+        tag_seconds as f64 * 100.0 / total_seconds as f64
+    } else {
+        0.0
+    };
+    let other_percent = if total_seconds > 0 {
+        100.0 - tag_percent
+    } else {
+        0.0
+    };
+
+    Ok(TagAllocation {
+        tag: tag.to_string(),
+        tag_seconds,
+        total_seconds,
+        tag_percent,
+        other_percent,
+    })
+}
+
+// 按标签分类统计时长分布；事项的多个标签属于不同分类时计入每个分类，同一分类只计一次
+pub fn category_balance(
+    conn: &Arc<SafeConnection>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<CategoryShare>> {
+    let categories = Tag::categories(conn)?;
+
+    let mut totals: BTreeMap<&str, i64> = BTreeMap::new();
+    for matter in Matter::get_by_time_range(conn, start, end)? {
+        let seconds = clamped_seconds(&matter, start, end);
+        if seconds == 0 {
+            continue;
+        }
+        let mut matter_categories: BTreeSet<&str> = matter
+            .tags
+            .as_deref()
+            .map(split_tags)
+            .into_iter()
+            .flatten()
+            .map(|tag| categories.get(tag).map_or(UNCATEGORIZED, String::as_str))
+            .collect();
+        if matter_categories.is_empty() {
+            matter_categories.insert(UNCATEGORIZED);
+        }
+        for category in matter_categories {
+            *totals.entry(category).or_insert(0) += seconds;
+        }
+    }
+
+    let total: i64 = totals.values().sum();
+    Ok(totals
+        .into_iter()
+        .map(|(category, seconds)| CategoryShare {
+            category: category.to_string(),
+            seconds,
+            percent: if total > 0 {
+                seconds as f64 * 100.0 / total as f64
+            } else {
+                0.0
+            },
+        })
+        .collect())
+}
+
+// 达成每周目标时发送一条通知，同一周只发送一次
+pub fn notify_weekly_goal_met(
+    conn: &Arc<SafeConnection>,
+    progress: &WeeklyGoalProgress,
+) -> Result<Option<NotificationRecord>> {
+    let week = progress.week_start.to_string();
+    if !progress.goal_met || KVStore::get(conn, WEEKLY_GOAL_NOTIFIED_KEY, "")? == week {
+        return Ok(None);
+    }
+
+    let notification = NotificationRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: "本周目标已完成".to_string(),
+        content: format!(
+            "本周已记录 {}，达到目标 {}",
+            utils::format_duration(progress.tracked_seconds),
+            utils::format_duration(progress.goal_seconds)
+        ),
+        type_: NotificationType::System as i32,
+        status: NotificationStatus::Unread as i32,
+        related_task_id: None,
+        created_at: Utc::now(),
+        read_at: None,
+        expire_at: None,
+        action_url: None,
+        reserved_1: None,
+        reserved_2: None,
+        reserved_3: None,
+        reserved_4: None,
+        reserved_5: None,
+        fire_at: None,
+    };
+    NotificationRecord::create(conn, &notification)?;
+    KVStore::set(conn, WEEKLY_GOAL_NOTIFIED_KEY, &week)?;
+
+    Ok(Some(notification))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::open_in_memory;
+    use chrono::{Duration, TimeZone};
+
+    const SHANGHAI: Tz = Tz::Asia__Shanghai;
+
+    fn matter(id: &str, start_time: DateTime<Utc>, end_time: DateTime<Utc>, tags: &str) -> Matter {
+        let now = Utc::now();
+        Matter {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: None,
+            tags: Some(tags.to_string()),
+            start_time,
+            end_time,
+            priority: 0,
+            type_: 0,
+            created_at: now,
+            updated_at: now,
+            reserved_1: None,
+            reserved_2: None,
+            reserved_3: None,
+            reserved_4: None,
+            reserved_5: None,
+        }
+    }
+
+    // 东八区 2024 年 1 月某日的整点，2024-01-01 是周一
+    fn local(day: u32, hour: u32) -> DateTime<Utc> {
+        SHANGHAI
+            .with_ymd_and_hms(2024, 1, day, hour, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn average_start_is_computed_per_weekday() {
+        let db = open_in_memory();
+        let monday = local(1, 9);
+        let next_monday = local(8, 9) + Duration::minutes(30);
+        let a = matter("a", monday, monday + Duration::hours(1), "work");
+        Matter::create(&db, &a).unwrap();
+        let b = matter("b", next_monday, next_monday + Duration::hours(1), "work");
+        Matter::create(&db, &b).unwrap();
+        let c = matter("c", next_monday, next_monday + Duration::hours(1), "play");
+        Matter::create(&db, &c).unwrap();
+
+        let weekdays =
+            Matter::avg_start_by_weekday(&db, local(1, 0), local(9, 0), Some("work"), SHANGHAI)
+                .unwrap();
+        assert_eq!(weekdays[0].average_start.as_deref(), Some("09:15"));
+        assert_eq!(weekdays[0].count, 2);
+        assert_eq!(weekdays[1].average_start, None);
+        assert_eq!(weekdays[1].count, 0);
+    }
+
+    #[test]
+    fn weekdays_without_matters_are_flagged() {
+        let db = open_in_memory();
+        // 两周内除了周五（5 日和 12 日）每天都有事项
+        for day in (1..=14).filter(|day| *day != 5 && *day != 12) {
+            let start = local(day, 10);
+            let id = format!("m{day}");
+            Matter::create(&db, &matter(&id, start, start + Duration::hours(1), "")).unwrap();
+        }
+
+        let end = local(15, 0) - Duration::seconds(1);
+        let coverage = Matter::weekday_coverage(&db, local(1, 0), end, SHANGHAI).unwrap();
+        assert_eq!(coverage.len(), 7);
+        for weekday in &coverage {
+            assert_eq!(weekday.days_in_range, 2);
+            assert_eq!(weekday.is_empty, weekday.weekday == Weekday::Fri);
+        }
+    }
+
+    #[test]
+    fn tag_time_series_is_bucketed() {
+        let db = open_in_memory();
+        for day in [1, 3, 9, 20] {
+            let start = local(day, 10);
+            let end = start + Duration::hours(day as i64);
+            Matter::create(&db, &matter(&format!("m{day}"), start, end, "work")).unwrap();
+        }
+        let (start, end) = (local(1, 0), local(31, 23) + Duration::minutes(59));
+
+        let weeks = tag_time_series(&db, start, end, TimeBucket::Week, SHANGHAI).unwrap();
+        assert_eq!(weeks.buckets.len(), 5);
+        assert_eq!(weeks.series[0].tag, "work");
+        assert_eq!(
+            weeks.series[0].totals,
+            vec![4 * 3600, 9 * 3600, 20 * 3600, 0, 0]
+        );
+        assert_eq!(weeks.series[0].total, 33 * 3600);
+        let months = tag_time_series(&db, start, end, TimeBucket::Month, SHANGHAI).unwrap();
+        assert_eq!(months.series[0].totals, vec![33 * 3600]);
+        let days = tag_time_series(&db, start, end, TimeBucket::Day, SHANGHAI).unwrap();
+        assert_eq!(days.buckets.len(), 31);
+    }
+
+    #[test]
+    fn durations_are_counted_into_histogram_buckets() {
+        let db = open_in_memory();
+        let start = local(1, 10);
+        for minutes in [5, 10, 20, 50] {
+            let id = format!("m{minutes}");
+            let end = start + Duration::minutes(minutes);
+            Matter::create(&db, &matter(&id, start, end, "")).unwrap();
+        }
+
+        let buckets = Matter::duration_histogram(&db, local(1, 0), local(2, 0), 15).unwrap();
+        let counts: Vec<usize> = buckets.iter().map(|bucket| bucket.count).collect();
+        assert_eq!(counts, vec![2, 1, 0, 1]);
+        assert_eq!(buckets[3].min_minutes, 45);
+        assert!(Matter::duration_histogram(&db, local(1, 0), local(2, 0), 0).is_err());
+    }
+
+    #[test]
+    fn tag_allocation_is_split_against_other_time() {
+        let db = open_in_memory();
+        let (start, end) = (local(1, 0), local(2, 0));
+        let empty = tag_allocation_ratio(&db, start, end, "focus").unwrap();
+        assert_eq!((empty.tag_percent, empty.other_percent), (0.0, 0.0));
+
+        Matter::create(&db, &matter("a", local(1, 9), local(1, 12), "focus,work")).unwrap();
+        Matter::create(&db, &matter("b", local(1, 12), local(1, 13), "mail")).unwrap();
+        let ratio = tag_allocation_ratio(&db, start, end, "focus").unwrap();
+        assert_eq!(
+            (ratio.tag_seconds, ratio.total_seconds),
+            (3 * 3600, 4 * 3600)
+        );
+        assert_eq!((ratio.tag_percent, ratio.other_percent), (75.0, 25.0));
+    }
+
+    #[test]
+    fn remaining_capacity_can_go_negative() {
+        let db = open_in_memory();
+        Matter::create(&db, &matter("a", local(1, 8), local(1, 14), "")).unwrap();
+        Matter::create(&db, &matter("b", local(1, 15), local(1, 18), "")).unwrap();
+        // 跨过午夜的事项只计当天部分
+        Matter::create(&db, &matter("night", local(1, 23), local(2, 2), "")).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let capacity = Matter::remaining_capacity(&db, day, 8 * 3600, SHANGHAI).unwrap();
+        assert_eq!(capacity.booked_seconds, 10 * 3600);
+        assert_eq!(capacity.remaining_seconds, -2 * 3600);
+    }
+
+    #[test]
+    fn matters_are_counted_per_tag_and_phase() {
+        let db = open_in_memory();
+        let now = local(1, 12);
+        Matter::create(&db, &matter("past", local(1, 9), local(1, 10), "w")).unwrap();
+        Matter::create(&db, &matter("ended", local(1, 10), now, "w")).unwrap();
+        Matter::create(&db, &matter("ongoing", local(1, 11), local(1, 13), "w,x")).unwrap();
+        Matter::create(&db, &matter("upcoming", local(1, 13), local(1, 14), "w")).unwrap();
+
+        let buckets = Matter::tag_time_buckets(&db, now).unwrap();
+        let counts: Vec<_> = buckets
+            .iter()
+            .map(|b| (b.tag.as_str(), b.past, b.ongoing, b.upcoming))
+            .collect();
+        assert_eq!(counts, vec![("w", 2, 1, 1), ("x", 0, 1, 0)]);
+    }
+
+    #[test]
+    fn longest_focus_block_merges_short_breaks() {
+        let db = open_in_memory();
+        let start = local(1, 9);
+        let at = |minutes: i64| start + Duration::minutes(minutes);
+        Matter::create(&db, &matter("a", at(0), at(60), "focus")).unwrap();
+        Matter::create(&db, &matter("b", at(61), at(130), "focus")).unwrap();
+        Matter::create(&db, &matter("c", at(150), at(250), "focus")).unwrap();
+        Matter::create(&db, &matter("d", at(0), at(20), "mail")).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        // 间隔不超过 30 秒才合并，c 单独最长
+        let block = Matter::longest_focus_block(&db, day, Some("focus"), SHANGHAI, 30)
+            .unwrap()
+            .unwrap();
+        assert_eq!((block.duration_seconds, block.matter_count), (100 * 60, 1));
+        let block = Matter::longest_focus_block(&db, day, Some("focus"), SHANGHAI, 600)
+            .unwrap()
+            .unwrap();
+        assert_eq!((block.start, block.matter_count), (start, 2));
+        assert_eq!(block.duration_seconds, 130 * 60);
+        let block = Matter::longest_focus_block(&db, day, None, SHANGHAI, 1200)
+            .unwrap()
+            .unwrap();
+        assert_eq!((block.tag.as_str(), block.matter_count), ("focus", 3));
+        assert!(
+            Matter::longest_focus_block(&db, day, Some("none"), SHANGHAI, 60)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn month_total_is_projected_from_month_to_date() {
+        let db = open_in_memory();
+        let utc = |day, hour| Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap();
+        Matter::create(&db, &matter("a", utc(2, 0), utc(2, 10), "")).unwrap();
+        Matter::create(&db, &matter("b", utc(10, 0), utc(10, 12), "")).unwrap();
+        Matter::create(&db, &matter("future", utc(20, 0), utc(20, 5), "")).unwrap();
+
+        let as_of = utc(15, 12);
+        let working =
+            Matter::project_month_total(&db, as_of, Tz::UTC, ProjectionMethod::WorkingDays)
+                .unwrap();
+        assert_eq!(working.month_to_date_seconds, 22 * 3600);
+        assert_eq!((working.elapsed_days, working.total_days), (11, 23));
+        assert_eq!(working.projected_seconds, 165600);
+        let calendar =
+            Matter::project_month_total(&db, as_of, Tz::UTC, ProjectionMethod::CalendarDays)
+                .unwrap();
+        assert_eq!((calendar.elapsed_days, calendar.total_days), (15, 31));
+        assert_eq!(calendar.projected_seconds, 163680);
+        // UTC 1 月 31 日 17 点在东八区已经是 2 月
+        let shanghai =
+            Matter::project_month_total(&db, utc(31, 17), SHANGHAI, ProjectionMethod::CalendarDays)
+                .unwrap();
+        assert_eq!(shanghai.month, NaiveDate::from_ymd_opt(2024, 2, 1).unwrap());
+        assert_eq!((shanghai.elapsed_days, shanghai.total_days), (1, 29));
+    }
+
+    #[test]
+    fn start_time_is_suggested_from_tag_history() {
+        let db = open_in_memory();
+        for day in 1..=3 {
+            let id = format!("standup{day}");
+            Matter::create(&db, &matter(&id, local(day, 9), local(day, 10), "standup")).unwrap();
+        }
+        let odd = matter("odd", local(5, 14), local(5, 15), "standup,other");
+        Matter::create(&db, &odd).unwrap();
+        // other 只有两条记录，样本太少时不给出建议
+        Matter::create(&db, &matter("other", local(5, 14), local(5, 15), "other")).unwrap();
+
+        let suggestion = Matter::suggest_time_for_tag(&db, "standup", SHANGHAI)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (suggestion.start.as_str(), suggestion.end.as_str()),
+            ("09:00", "10:00")
+        );
+        assert_eq!((suggestion.matter_count, suggestion.sample_size), (3, 4));
+        assert!(Matter::suggest_time_for_tag(&db, "other", SHANGHAI)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn tracked_time_is_totalled_per_priority() {
+        let db = open_in_memory();
+        let with_priority = |id: &str, start, end, priority| Matter {
+            priority,
+            ..matter(id, start, end, "")
+        };
+        Matter::create(&db, &with_priority("a", local(1, 9), local(1, 11), 2)).unwrap();
+        Matter::create(&db, &with_priority("b", local(1, 12), local(1, 13), 2)).unwrap();
+        let short = local(1, 9) + Duration::minutes(30);
+        Matter::create(&db, &with_priority("short", local(1, 9), short, 0)).unwrap();
+        let open = with_priority("open", local(1, 9), DateTime::<Utc>::default(), 1);
+        Matter::create(&db, &open).unwrap();
+
+        // 区间外的部分不计入，未结束的事项不计入
+        let totals = Matter::time_by_priority(&db, short, local(2, 0)).unwrap();
+        let totals: Vec<_> = totals
+            .iter()
+            .map(|p| (p.priority, p.total_seconds, p.matter_count))
+            .collect();
+        assert_eq!(totals, vec![(2, 9000, 2)]);
+    }
+
+    #[test]
+    fn weekly_goal_is_notified_once_per_week() {
+        let db = open_in_memory();
+        let week = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        Matter::create(&db, &matter("a", local(1, 9), local(1, 12), "")).unwrap();
+        // 周日跨到下周一，只计本周的 2 小时
+        Matter::create(&db, &matter("b", local(7, 22), local(8, 2), "")).unwrap();
+        Matter::create(&db, &matter("next", local(8, 9), local(8, 12), "")).unwrap();
+
+        let progress = Matter::weekly_goal_progress(&db, week, SHANGHAI).unwrap();
+        assert_eq!(
+            (progress.goal_seconds, progress.tracked_seconds),
+            (0, 5 * 3600)
+        );
+        assert!(!progress.goal_met);
+        assert!(notify_weekly_goal_met(&db, &progress).unwrap().is_none());
+
+        KVStore::set(&db, WEEKLY_GOAL_KEY, "36000").unwrap();
+        let progress = Matter::weekly_goal_progress(&db, week, SHANGHAI).unwrap();
+        assert_eq!((progress.percent, progress.goal_met), (50.0, false));
+        KVStore::set(&db, WEEKLY_GOAL_KEY, "18000").unwrap();
+        let progress = Matter::weekly_goal_progress(&db, week, SHANGHAI).unwrap();
+        assert!(progress.goal_met);
+        assert!(notify_weekly_goal_met(&db, &progress).unwrap().is_some());
+        assert!(notify_weekly_goal_met(&db, &progress).unwrap().is_none());
+        // 记录已通知的周不属于用户设置
+        let snapshot = KVStore::snapshot_settings(&db).unwrap();
+        assert!(!snapshot.contains(WEEKLY_GOAL_NOTIFIED_KEY));
+    }
+
+    #[test]
+    fn category_balance_counts_each_matter_once_per_category() {
+        let db = open_in_memory();
+        Tag::set_category(&db, "code", Some("work")).unwrap();
+        Tag::set_category(&db, "mail", Some("work")).unwrap();
+        Tag::set_category(&db, "run", Some(" health ")).unwrap();
+        Tag::set_category(&db, "old", Some("x")).unwrap();
+        Tag::set_category(&db, "old", None).unwrap();
+        Matter::create(&db, &matter("a", local(1, 9), local(1, 12), "code,mail")).unwrap();
+        Matter::create(&db, &matter("b", local(1, 13), local(1, 14), "run")).unwrap();
+        Matter::create(&db, &matter("c", local(1, 15), local(1, 16), "")).unwrap();
+
+        let balance = category_balance(&db, local(1, 0), local(2, 0)).unwrap();
+        let shares: Vec<_> = balance
+            .iter()
+            .map(|s| (s.category.as_str(), s.seconds, s.percent))
+            .collect();
+        assert_eq!(
+            shares,
+            vec![
+                ("health", 3600, 20.0),
+                ("uncategorized", 3600, 20.0),
+                ("work", 3 * 3600, 60.0)
+            ]
+        );
+        assert_eq!(Tag::categories(&db).unwrap().len(), 3);
+        Tag::delete(&db, "run").unwrap();
+        assert_eq!(Tag::categories(&db).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn average_gap_skips_overlaps_and_day_boundaries() {
+        let db = open_in_memory();
+        let at = |day, hour, minute| local(day, hour) + Duration::minutes(minute);
+        Matter::create(&db, &matter("a", at(2, 9, 0), at(2, 10, 0), "")).unwrap();
+        // 30 分钟间隔
+        Matter::create(&db, &matter("b", at(2, 10, 30), at(2, 11, 30), "")).unwrap();
+        // 与 b 重叠，不计间隔
+        Matter::create(&db, &matter("c", at(2, 11, 0), at(2, 12, 0), "")).unwrap();
+        // 90 分钟间隔
+        Matter::create(&db, &matter("d", at(2, 13, 30), at(2, 14, 0), "")).unwrap();
+        // 次日的第一条不与前一天计算间隔
+        Matter::create(&db, &matter("e", at(3, 9, 0), at(3, 10, 0), "")).unwrap();
+
+        let gaps = Matter::avg_gap(&db, local(1, 0), local(5, 0), SHANGHAI).unwrap();
+        assert_eq!((gaps.gap_count, gaps.total_gap_seconds), (2, 7200));
+        assert_eq!(gaps.average_gap_seconds, Some(3600));
+        let none = Matter::avg_gap(&db, local(3, 0), local(4, 0), SHANGHAI).unwrap();
+        assert_eq!((none.gap_count, none.average_gap_seconds), (0, None));
+    }
+
+    #[test]
+    fn tag_streaks_count_consecutive_local_days() {
+        let db = open_in_memory();
+        for day in [1, 2, 3, 5, 6, 8] {
+            let id = format!("e{day}");
+            Matter::create(&db, &matter(&id, local(day, 7), local(day, 8), "exercise")).unwrap();
+        }
+        // 4 日只有其他标签，不延续连续天数
+        Matter::create(&db, &matter("o", local(4, 7), local(4, 8), "reading")).unwrap();
+
+        let streak = Matter::tag_streak(&db, "exercise", local(8, 23), SHANGHAI).unwrap();
+        assert_eq!((streak.current_days, streak.longest_days), (1, 3));
+        // 当天还没有记录时从前一天往前算
+        let current = |now| {
+            Matter::tag_streak(&db, "exercise", now, SHANGHAI)
+                .unwrap()
+                .current_days
+        };
+        assert_eq!(current(local(9, 6)), 1);
+        assert_eq!(current(local(7, 12)), 2);
+        assert_eq!(current(local(10, 12)), 0);
+    }
+
+    #[test]
+    fn days_with_data_include_every_day_a_matter_spans() {
+        let db = open_in_memory();
+        // 3 日 22 点到 6 日零点，跨 3、4、5 三天
+        Matter::create(&db, &matter("multi", local(3, 22), local(6, 0), "")).unwrap();
+        Matter::create(&db, &matter("one", local(9, 10), local(9, 11), "")).unwrap();
+        Matter::create(&db, &matter("outside", local(20, 10), local(20, 11), "")).unwrap();
+
+        let days = Matter::days_with_data(&db, local(1, 0), local(15, 0), SHANGHAI).unwrap();
+        let days: Vec<String> = days.iter().map(|day| day.to_string()).collect();
+        assert_eq!(
+            days,
+            vec!["2024-01-03", "2024-01-04", "2024-01-05", "2024-01-09"]
+        );
+        // 跨天事项只标记区间内的日期
+        let days = Matter::days_with_data(&db, local(4, 12), local(5, 12), SHANGHAI).unwrap();
+        assert_eq!(days.len(), 2);
+    }
+
+    #[test]
+    fn tag_transitions_follow_the_day_in_order() {
+        let db = open_in_memory();
+        let day = [
+            (9, "work,deep"),
+            (10, "break"),
+            (11, "work"),
+            (12, ""),
+            (13, "work"),
+            (14, "break"),
+            (15, "work"),
+        ];
+        for (hour, tags) in day {
+            let start = local(2, hour);
+            let id = format!("m{hour}");
+            Matter::create(
+                &db,
+                &matter(&id, start, start + Duration::minutes(30), tags),
+            )
+            .unwrap();
+        }
+        Matter::create(&db, &matter("other-day", local(3, 9), local(3, 10), "gym")).unwrap();
+
+        let flow =
+            Matter::tag_transitions(&db, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(), SHANGHAI)
+                .unwrap();
+        assert_eq!(
+            flow.sequence,
+            vec!["work", "break", "work", "break", "work"]
+        );
+        let transitions: Vec<(&str, &str, usize)> = flow
+            .transitions
+            .iter()
+            .map(|t| (t.from.as_str(), t.to.as_str(), t.count))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![("work", "break", 2), ("break", "work", 2)]
+        );
+    }
+
+    #[test]
+    fn priority_percentages_add_up_to_one_hundred() {
+        let db = open_in_memory();
+        let with_priority = |id: &str, start, end, priority| Matter {
+            priority,
+            ..matter(id, start, end, "")
+        };
+        // 1:1:1 各 33.3%，取整后仍需加起来等于 100
+        for priority in 0..3 {
+            let hour = 9 + priority as u32;
+            let id = format!("p{priority}");
+            let m = with_priority(&id, local(1, hour), local(1, hour + 1), priority);
+            Matter::create(&db, &m).unwrap();
+        }
+        let running = with_priority("running", local(1, 9), DateTime::<Utc>::default(), 3);
+        Matter::create(&db, &running).unwrap();
+
+        let shares = Matter::priority_distribution(&db, local(1, 0), local(2, 0)).unwrap();
+        assert_eq!(shares.iter().map(|s| s.percent).sum::<u32>(), 100);
+        assert_eq!(
+            shares.iter().map(|s| s.priority).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(shares.iter().all(|s| s.percent == 33 || s.percent == 34));
+
+        Matter::create(&db, &with_priority("long", local(1, 14), local(1, 16), 0)).unwrap();
+        let shares = Matter::priority_distribution(&db, local(1, 0), local(2, 0)).unwrap();
+        let shares: Vec<_> = shares
+            .iter()
+            .map(|s| (s.priority, s.seconds, s.percent))
+            .collect();
+        assert_eq!(shares, vec![(0, 10800, 60), (1, 3600, 20), (2, 3600, 20)]);
+        assert!(
+            Matter::priority_distribution(&db, local(20, 0), local(21, 0))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::database::{DbError, ImportItemError, ImportReport, Matter, SafeConnection};
+use crate::utils;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::sync::Arc;
+
+// 不带时区的时间按导入时指定的时区解析
+const NAIVE_DATETIME_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"];
+
+// Toggl Track 导出的一条时间记录，只包含导入需要的字段
+#[derive(Debug, Deserialize)]
+pub struct TogglEntry {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, alias = "project_name")]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub start: Option<String>,
+    #[serde(default, alias = "end")]
+    pub stop: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// 带时区偏移的时间直接转换，否则视为 timezone 的本地时间
+fn parse_time(value: &str, timezone: Tz) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NAIVE_DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|local| utils::local_to_utc(local, timezone))
+        .ok_or_else(|| format!("Invalid time: {}", value))
+}
+
+// 将一条 Toggl 记录转换为事项：project 和 tags 作为标签，description 作为标题
+pub fn entry_to_matter(
+    entry: &TogglEntry,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<Matter, String> {
+    let start = entry.start.as_deref().ok_or("Entry has no start time")?;
+    // 仍在计时的记录没有结束时间
+    let stop = entry.stop.as_deref().ok_or("Entry is still running")?;
+    let start_time = parse_time(start, timezone)?;
+    let end_time = parse_time(stop, timezone)?;
+    if end_time < start_time {
+        return Err(format!("Stop time {} is before start time {}", stop, start));
+    }
+
+    let description = entry.description.as_deref().map(str::trim).unwrap_or("");
+    let project = entry.project.as_deref().map(str::trim).unwrap_or("");
+    let title = if description.is_empty() {
+        project
+    } else {
+        description
+    };
+    if title.is_empty() {
+        return Err("Entry has neither description nor project".to_string());
+    }
+
+    let mut tags: Vec<&str> = Vec::new();
+    for tag in std::iter::once(project).chain(entry.tags.iter().map(|tag| tag.trim())) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    Ok(Matter {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: title.to_string(),
+        description: None,
+        tags: Some(tags.join(",")),
+        start_time,
+        end_time,
+        priority: 0,
+        type_: 0,
+        created_at: now,
+        updated_at: now,
+        reserved_1: None,
+        reserved_2: None,
+        reserved_3: None,
+        reserved_4: None,
+        reserved_5: None,
+    })
+}
+
+// 从 Toggl Track 导出的 JSON 数组导入事项，无法导入的记录写入 errors
+pub fn import_toggl(
+    conn: &Arc<SafeConnection>,
+    data: &str,
+    timezone: Tz,
+) -> Result<ImportReport, DbError> {
+    let items: Vec<serde_json::Value> = serde_json::from_str(data)
+        .map_err(|e| DbError::InvalidArgument(format!("Invalid JSON array: {}", e)))?;
+
+    let now = Utc::now();
+    let mut report = ImportReport {
+        imported: Vec::new(),
+        errors: Vec::new(),
+        dry_run: false,
+    };
+    for (index, item) in items.into_iter().enumerate() {
+        let result = serde_json::from_value::<TogglEntry>(item)
+            .map_err(|e| e.to_string())
+            .and_then(|entry| entry_to_matter(&entry, timezone, now))
+            .and_then(|matter| {
+                Matter::create(conn, &matter).map_err(|e| e.to_string())?;
+                Ok(matter.id)
+            });
+
+        match result {
+            Ok(id) => report.imported.push(id),
+            Err(message) => report.errors.push(ImportItemError { index, message }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::open_in_memory;
+    use chrono::TimeZone;
+
+    #[test]
+    fn entries_are_converted_in_the_import_timezone() {
+        let now = Utc::now();
+        let entry: TogglEntry = serde_json::from_value(serde_json::json!({
+            "description": " Write report ",
+            "project_name": "Work",
+            "start": "2024-01-01T09:00:00+08:00",
+            "end": "2024-01-01T10:30:00+08:00",
+            "tags": ["writing", "Work"]
+        }))
+        .unwrap();
+        let m = entry_to_matter(&entry, Tz::Asia__Shanghai, now).unwrap();
+        assert_eq!(m.title, "Write report");
+        assert_eq!(m.tags.as_deref(), Some("Work,writing"));
+        assert_eq!(
+            m.start_time,
+            Utc.with_ymd_and_hms(2024, 1, 1, 1, 0, 0).unwrap()
+        );
+        assert_eq!(
+            m.end_time,
+            Utc.with_ymd_and_hms(2024, 1, 1, 2, 30, 0).unwrap()
+        );
+
+        // 没有描述时用项目名作标题，不带时区的时间按导入时区解析
+        let naive: TogglEntry = serde_json::from_value(serde_json::json!({
+            "project": "Gym",
+            "start": "2024-01-02 19:00:00",
+            "stop": "2024-01-02T20:00:00"
+        }))
+        .unwrap();
+        let m = entry_to_matter(&naive, Tz::Asia__Shanghai, now).unwrap();
+        assert_eq!((m.title.as_str(), m.tags.as_deref()), ("Gym", Some("Gym")));
+        assert_eq!(
+            m.start_time,
+            Utc.with_ymd_and_hms(2024, 1, 2, 11, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_by_index() {
+        let db = open_in_memory();
+        let data = r#"[
+            {"description": "a", "start": "2024-01-01T09:00:00Z", "stop": "2024-01-01T10:00:00Z"},
+            {"description": "running", "start": "2024-01-01T09:00:00Z"},
+            {"description": "b", "start": "2024-01-01T09:00:00Z", "stop": "2024-01-01T08:00:00Z"},
+            {"start": "2024-01-01T09:00:00Z", "stop": "2024-01-01T10:00:00Z"},
+            {"description": "bad", "start": "yesterday", "stop": "2024-01-01T10:00:00Z"},
+            42
+        ]"#;
+        let report = import_toggl(&db, data, Tz::Asia__Shanghai).unwrap();
+        assert_eq!(report.imported.len(), 1);
+        let failed: Vec<usize> = report.errors.iter().map(|e| e.index).collect();
+        assert_eq!(failed, vec![1, 2, 3, 4, 5]);
+        assert_eq!(Matter::get_all(&db).unwrap().len(), 1);
+        assert!(import_toggl(&db, "{}", Tz::Asia__Shanghai).is_err());
+    }
+}
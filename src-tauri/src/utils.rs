@@ -1,15 +1,110 @@
 pub const APP_NAME: &str = "Fates";
 
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use std::fs;
 use tauri::Manager;
 
 pub fn get_app_data_dir(app_handle: tauri::AppHandle) -> Result<std::path::PathBuf, String> {
     // 获取基础目录
-    let base_dir = app_handle.path().app_data_dir().unwrap();
-    let app_dir = base_dir;
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve the app data directory: {}", e))?;
 
     // 创建目录
-    fs::create_dir_all(&app_dir).expect("The app data directory could not be created");
+    fs::create_dir_all(&app_dir).map_err(|e| {
+        format!(
+            "Failed to create the app data directory {}: {}",
+            app_dir.display(),
+            e
+        )
+    })?;
 
     Ok(app_dir)
 }
+
+// 解析 IANA 时区名称，例如 "Asia/Shanghai"
+pub fn parse_timezone(timezone: &str) -> Result<Tz, String> {
+    timezone
+        .parse::<Tz>()
+        .map_err(|_| format!("Invalid timezone: {}", timezone))
+}
+
+// 本地时间转换为 UTC；重复的时间取较早的一个，夏令时跳过的时间顺延一小时
+pub fn local_to_utc(local: NaiveDateTime, timezone: Tz) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .unwrap_or_else(|| timezone.from_utc_datetime(&local))
+        .with_timezone(&Utc)
+}
+
+// 解析 "HH:MM" 或 "HH:MM:SS" 格式的时刻
+pub fn parse_time_of_day(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .map_err(|_| format!("Invalid time: {}", time))
+}
+
+// 本地日期零点对应的 UTC 时间
+pub fn local_midnight(day: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    local_to_utc(day.and_time(NaiveTime::MIN), timezone)
+}
+
+// 将秒数格式化为 "1h 30m" 形式的时长字符串
+pub fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.abs();
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    if hours > 0 {
+        format!("{}{}h {}m", sign, hours, minutes)
+    } else {
+        format!("{}{}m", sign, minutes)
+    }
+}
+
+// FNV-1a 哈希，结果在不同版本和会话间保持稳定
+fn stable_hash(value: &str) -> u32 {
+    value.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+// 根据名称生成固定的颜色，返回 "#rrggbb"
+pub fn color_for_name(name: &str) -> String {
+    let hue = (stable_hash(name) % 360) as f64;
+    hsl_to_hex(hue, 0.65, 0.55)
+}
+
+fn hsl_to_hex(hue: f64, saturation: f64, lightness: f64) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = lightness - chroma / 2.0;
+    let (r, g, b) = match hue as u32 {
+        0..=59 => (chroma, x, 0.0),
+        60..=119 => (x, chroma, 0.0),
+        120..=179 => (0.0, chroma, x),
+        180..=239 => (0.0, x, chroma),
+        240..=299 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let to_byte = |value: f64| ((value + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", to_byte(r), to_byte(g), to_byte(b))
+}
+
+// 校验 "#rgb" 或 "#rrggbb" 格式的颜色
+pub fn is_hex_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => {
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
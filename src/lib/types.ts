@@ -44,3 +44,9 @@ export enum TaskStatus {
     Stopped = 0,
     Archived = -1,
 }
+
+// Error rejected by Tauri commands, matches AppError in src-tauri/src/error.rs
+export interface AppError {
+    code: "database" | "not_found" | "validation" | "conflict" | "io" | "serialization" | "platform";
+    message: string;
+}
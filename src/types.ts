@@ -12,7 +12,7 @@ export interface Matter {
     updated_at: string;
     reserved_1?: string; // for className
     reserved_2?: string; // assigned repeat task id  or  todo item id
-    reserved_3?: string;
+    reserved_3?: string; // timeline group id
     reserved_4?: string;
     reserved_5?: string;
 }
@@ -29,6 +29,16 @@ export interface RepeatTask {
     description?: string;
 }
 
+// Skips or overrides one occurrence of a repeat task. override_json holds the replaced fields
+// (title, description, tags, start_time, end_time, priority).
+export interface RepeatTaskException {
+    task_id: string;
+    occurrence_date: string; // YYYY-MM-DD
+    action: "skip" | "override";
+    override_json?: string;
+    created_at: string;
+}
+
 export interface Todo {
     id: string;
     title: string;
@@ -36,6 +46,7 @@ export interface Todo {
     created_at: string;
     updated_at: string;
     start_time?: string;
+    position?: number;
 }
 
 export interface NotificationRecord {
@@ -54,9 +65,34 @@ export interface NotificationRecord {
     reserved_3?: string;
     reserved_4?: string;
     reserved_5?: string;
+    fire_at?: string;
+}
+
+// Payload of "notification://created" is a NotificationRecord.
+// Payload of "notification://read": id is set for a single notification, type_ when a whole
+// type was marked, related_task_id when a task's notifications were marked, and all are null
+// when everything was marked as read.
+export interface NotificationReadEvent {
+    id: string | null;
+    type_: number | null;
+    related_task_id: string | null;
 }
 
 export interface Tag {
     name: string;
     last_used_at: string;
+    color?: string;
+}
+
+// Read and written as a whole through the get_settings / save_settings commands.
+export interface AppSettings {
+    theme: "light" | "dark";
+    reminder_lead_minutes: number;
+    first_day_of_week: number; // 0: Sunday, 1: Monday, ...
+    default_matter_duration_minutes: number;
+    default_matter_priority: number;
+    default_matter_type: number;
+    weekly_goal_seconds: number;
+    auto_backup: boolean;
+    unique_repeat_titles: boolean;
 }